@@ -27,11 +27,17 @@ pub enum AsyncSheetsHubError {
   #[error(transparent)]
   ScoreTableRecordError(#[from] ScoreTableRecordError),
   #[error(transparent)]
-  GoogleSheetsApiError(#[from] google_sheets4::Error),
+  GoogleSheetsApiError(Box<google_sheets4::Error>),
   #[error(transparent)]
   IO(#[from] std::io::Error),
   #[error(transparent)]
   InvalidFetchedData(InvalidFetchedData),
   #[error("Unknown error has occured: `{0}`")]
   Unknown(&'static str),
-}
\ No newline at end of file
+}
+
+impl From<google_sheets4::Error> for AsyncSheetsHubError {
+  fn from(err: google_sheets4::Error) -> Self {
+    AsyncSheetsHubError::GoogleSheetsApiError(Box::new(err))
+  }
+}
@@ -0,0 +1,33 @@
+use futures::future::BoxFuture;
+
+use crate::{
+  api::error::{AsyncSheetsHubError as Error, InvalidFetchedData::EmptySheets},
+  dashboard::{score_table::ScoreTable, Dashboard},
+};
+
+use super::SheetsDataSource;
+
+/// Data source returning the canned tables, or failing like a spreadsheet without sheets
+pub struct FakeSheetsHub {
+  tables: Option<Vec<ScoreTable>>,
+}
+
+impl FakeSheetsHub {
+  pub fn with_tables(tables: Vec<ScoreTable>) -> Self {
+    Self { tables: Some(tables) }
+  }
+
+  pub fn failing() -> Self {
+    Self { tables: None }
+  }
+}
+
+impl SheetsDataSource for FakeSheetsHub {
+  fn fetch_dashboard(&self) -> BoxFuture<'_, Result<Dashboard, Error>> {
+    let result = match &self.tables {
+      Some(tables) => Ok(Dashboard::from(tables.clone())),
+      None => Err(Error::InvalidFetchedData(EmptySheets)),
+    };
+    Box::pin(async move { result })
+  }
+}
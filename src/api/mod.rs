@@ -1,22 +1,92 @@
 pub mod error;
+#[cfg(test)]
+pub mod fake;
 pub mod requests;
+use chrono::{Datelike, Months, NaiveDate};
 
 use google_sheets4::{
-  api::{GetSpreadsheetByDataFilterRequest, GridData, RowData, Spreadsheet},
+  api::{CellData, GetSpreadsheetByDataFilterRequest, GridData, RowData, Spreadsheet},
   oauth2::{self, authenticator::Authenticator},
   Sheets,
 };
+use futures::future::BoxFuture;
 use hyper::{client::HttpConnector, Client};
+use itertools::free::join;
 use log::{debug, error, info, trace, warn};
+use std::{
+  future::Future,
+  time::{Duration, Instant},
+};
 
 use crate::{
   api::error::{AsyncSheetsHubError as Error, InvalidFetchedData::*},
   api::requests::RequestFactory,
-  dashboard::{score_table::entities::Person, score_table::ScoreTable, score_table::ScoreTableRecord, Dashboard},
+  dashboard::{score_table::entities::Person, score_table::ScoreTable, score_table::ScoreTableRecord, Dashboard, FetchStats},
   helpers,
 };
 
-use self::requests::ScoreTableRequest;
+use self::requests::{ScoreTableRequest, SheetLayout};
+
+/// How the rows of a participant's block were parsed
+#[derive(Debug, Default)]
+pub struct ParseReport {
+  pub raw_rows: usize,
+  pub empty_rows: usize,
+  pub parsed_rows: usize,
+  pub skipped_rows: usize,
+  pub first_error: Option<String>,
+}
+
+impl std::fmt::Display for ParseReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "Строк в блоке: {}\nПустых: {}\nРазобрано: {}\nПропущено из-за ошибок: {}",
+      self.raw_rows, self.empty_rows, self.parsed_rows, self.skipped_rows
+    )?;
+    if let Some(error) = &self.first_error {
+      write!(f, "\nПервая ошибка: {}", error)?;
+    }
+    Ok(())
+  }
+}
+
+/// Retries of the transient Google Sheets API failures (network errors, 429 and 5xx responses)
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+  pub max_retries: u32,
+  /// Delay before the first retry, doubled for every next one
+  pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_retries: 3,
+      backoff: Duration::from_millis(500),
+    }
+  }
+}
+
+impl RetryPolicy {
+  fn delay(&self, attempt: u32) -> Duration {
+    self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+  }
+
+  /// Auth, parse and other client errors fail fast
+  fn is_transient(err: &google_sheets4::Error) -> bool {
+    let is_transient_status = |code: u64| code == 429 || (500..600).contains(&code);
+    match err {
+      google_sheets4::Error::HttpError(_) | google_sheets4::Error::Io(_) => true,
+      google_sheets4::Error::Failure(response) => is_transient_status(response.status().as_u16() as u64),
+      google_sheets4::Error::BadRequest(body) => body
+        .pointer("/error/code")
+        .and_then(|code| code.as_u64())
+        .is_some_and(is_transient_status),
+      _ => false,
+    }
+  }
+}
 
 async fn create_hub(service_key: &str) -> Result<Sheets<hyper_rustls::HttpsConnector<HttpConnector>>, Error> {
   let connector = hyper_rustls::HttpsConnector::with_native_roots();
@@ -34,25 +104,117 @@ async fn auth(service_key: &str) -> Result<Authenticator<google_sheets4::hyper_r
   Ok(authenticator)
 }
 
+/// Source of the current month dashboard, lets the data fetcher run against canned data
+pub trait SheetsDataSource: Send + Sync {
+  fn fetch_dashboard(&self) -> BoxFuture<'_, Result<Dashboard, Error>>;
+}
+
+impl SheetsDataSource for AsyncSheetsHub {
+  fn fetch_dashboard(&self) -> BoxFuture<'_, Result<Dashboard, Error>> {
+    Box::pin(AsyncSheetsHub::fetch_dashboard(self))
+  }
+}
+
 pub struct AsyncSheetsHub {
   hub: Sheets<hyper_rustls::HttpsConnector<HttpConnector>>,
   spreadsheet_id: String,
+  retry: RetryPolicy,
+  layout: SheetLayout,
+  /// Sheet read instead of the current month one
+  title_override: Option<String>,
+  /// Drop the unparsable rows instead of failing the whole fetch
+  skip_parse_errors: bool,
 }
 
 impl AsyncSheetsHub {
+  /// Upper bound of the participant tables requested with a single API call
+  const TABLES_PER_REQUEST: i32 = 20;
+
   pub async fn new(service_key: &str, spreadsheet_id: &str) -> Result<AsyncSheetsHub, Error> {
     let hub = create_hub(service_key).await?;
 
     Ok(AsyncSheetsHub {
       hub,
       spreadsheet_id: spreadsheet_id.to_string(),
+      retry: RetryPolicy::default(),
+      layout: SheetLayout::default(),
+      title_override: None,
+      skip_parse_errors: true,
     })
   }
 
+  pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+    self.retry = retry;
+    self
+  }
+
+  pub fn with_layout(mut self, layout: SheetLayout) -> Self {
+    self.layout = layout;
+    self
+  }
+
+  pub fn with_title_override(mut self, title: Option<String>) -> Self {
+    self.title_override = title;
+    self
+  }
+
+  pub fn with_skip_parse_errors(mut self, skip_parse_errors: bool) -> Self {
+    self.skip_parse_errors = skip_parse_errors;
+    self
+  }
+
+  /// Title of the sheet holding the current data: the configured one, otherwise the current month's
+  pub fn current_title(&self) -> String {
+    match &self.title_override {
+      Some(title) => {
+        debug!("[AsyncHub] Using the sheet title '{}' (overridden by the config)", title);
+        title.clone()
+      }
+      None => {
+        let title = helpers::derive_title_name();
+        debug!("[AsyncHub] Using the sheet title '{}' (derived from the current month, no override)", title);
+        title
+      }
+    }
+  }
+
+  pub fn layout(&self) -> &SheetLayout {
+    &self.layout
+  }
+
   pub async fn fetch_dashboard(&self) -> Result<Dashboard, Error> {
+    self.fetch_dashboard_by_title(&self.current_title()).await
+  }
+
+  /// Fetches the monthly sheets of the last `months` months up to the current one, oldest first.
+  /// Every dashboard is keyed by the first day of its month, the sheets which failed to fetch are skipped.
+  pub async fn fetch_dashboard_range(&self, months: u32) -> Vec<(NaiveDate, Dashboard)> {
+    let today = helpers::current_time_local().date_naive();
+    let Some(current) = today.with_day(1) else {
+      return Vec::new();
+    };
+    let mut dashboards = Vec::new();
+    for (i, back) in (0..months).rev().enumerate() {
+      let Some(first_day) = current.checked_sub_months(Months::new(back)) else {
+        continue;
+      };
+      let title = helpers::derive_title_name_for(&first_day);
+      info!("[AsyncHub] Fetching sheet '{}' ({}/{})", title, i + 1, months);
+      match self.fetch_dashboard_by_title(&title).await {
+        Ok(dashboard) => dashboards.push((first_day, dashboard)),
+        Err(err) => warn!("[AsyncHub] Sheet '{}' is skipped: {}", title, err),
+      }
+    }
+    dashboards
+  }
+
+  /// Fetches the dashboard from the sheet with the given title, e.g. a previous month one
+  pub async fn fetch_dashboard_by_title(&self, title: &str) -> Result<Dashboard, Error> {
     // Fetch titles to identify actual sheet_id corresponding to
     // relevant dashboard data.
-    debug!("[AsyncHub] Start fetching dashboard data...");
+    debug!("[AsyncHub] Start fetching dashboard data from sheet '{}'...", title);
+    let started_at = Instant::now();
+    let mut round_trips: u32 = 1;
     let sheets = self
       .fetch_spreadsheet(false)
       .await?
@@ -60,44 +222,110 @@ impl AsyncSheetsHub {
       .ok_or(Error::InvalidFetchedData(EmptySheets))?;
     debug!("[AsyncHub] Fetched {:} sheet(s)", sheets.len());
 
-    // Looking for sheet_id for derived title
-    let derived_title = helpers::derive_title_name();
+    // Looking for sheet_id for the title
     let sheet_id =
-      helpers::get_sheet_id_by_title(&sheets, &derived_title).ok_or(Error::InvalidFetchedData(NotFoundSheetId(derived_title.clone())))?;
+      helpers::get_sheet_id_by_title(&sheets, title).ok_or_else(|| Error::InvalidFetchedData(NotFoundSheetId(title.to_string())))?;
+
+    // Grid ranges beyond the sheet are rejected by the API, so the batches are limited by its width
+    let column_count = sheets
+      .iter()
+      .filter_map(|sheet| sheet.properties.as_ref())
+      .find(|props| props.sheet_id == Some(sheet_id))
+      .and_then(|props| props.grid_properties.as_ref())
+      .and_then(|grid| grid.column_count);
 
     let mut tables: Vec<ScoreTable> = Vec::new();
-    let mut request = RequestFactory::new(sheet_id).construct_score_table_request(true);
+    let mut request = RequestFactory::new(sheet_id, &self.layout).construct_score_table_request(true);
+
+    // The participants are counted by their name cells first, so that exactly that many tables are requested.
+    // Without the sheet width the batches go on until a block without the name is met.
+    let mut remaining = match column_count {
+      Some(columns) => {
+        round_trips += 1;
+        let counted = self.count_tables(&request, request.tables_fitting(columns)).await?;
+        debug!("[AsyncHub] {} participant(s) counted by the name cells", counted);
+        Some(counted)
+      }
+      None => None,
+    };
 
     trace!("[AsyncHub] Score table parsing loop has started ...");
     loop {
-      match self.fetch_score_table(sheet_id, &request, true).await {
-        Ok(score_table) => {
-          info!(
-            "[AsyncHub] New score table parsed for person with name '{}'",
-            score_table.person().name()
-          );
-          tables.push(score_table)
-        }
-        Err(Error::InvalidFetchedData(EmptyPersonNameCell)) => {
-          warn!("[AsyncHub] Empty person name cell was reached - finish parsing loop");
-          break;
-        }
-        Err(err) => {
-          error!("[AsyncHub] Error has occured while obtaining new score table {:#?}", err);
-          return Err(err);
+      let count = remaining
+        .unwrap_or(AsyncSheetsHub::TABLES_PER_REQUEST)
+        .min(AsyncSheetsHub::TABLES_PER_REQUEST);
+      if count == 0 {
+        break;
+      }
+      round_trips += 1;
+      let batch_started_at = Instant::now();
+      let (batch, reached_end) = self.fetch_score_tables(sheet_id, &request, count, self.skip_parse_errors).await.map_err(|err| {
+        error!("[AsyncHub] Error has occured while obtaining new score tables {:#?}", err);
+        err
+      })?;
+      info!(
+        "[AsyncHub] {} score table(s) parsed in {} ms: {}",
+        batch.len(),
+        batch_started_at.elapsed().as_millis(),
+        join(batch.iter().map(|table| table.person().name()), ", ")
+      );
+      tables.extend(batch);
+      if reached_end {
+        // Safety net: a counted table turned out to have no name, e.g. the sheet was edited in between
+        if remaining.is_some() {
+          warn!("[AsyncHub] Empty person name cell was reached before all counted tables were parsed");
         }
-      };
-      request.next_table_request();
+        break;
+      }
+      remaining = remaining.map(|left| left - count);
+      request.skip_tables(count);
     }
     trace!(
       "[AsyncHub] Score table parsing loop has finished. Parsed data for {} persons",
       tables.len()
     );
 
-    Ok(Dashboard::from(tables))
+    let duration = started_at.elapsed();
+    // One by one, every table and the block after the last one are requested separately after the sheets list
+    info!(
+      "[AsyncHub] Sheet '{}' fetched in {} ms: {} table(s), {} API round-trip(s) instead of {}",
+      title,
+      duration.as_millis(),
+      tables.len(),
+      round_trips,
+      tables.len() + 2
+    );
+    let mut dashboard = Dashboard::from(tables);
+    dashboard.set_fetch_stats(FetchStats { duration, round_trips });
+    dashboard.set_sheet_title(title.to_string());
+    Ok(dashboard)
+  }
+
+  /// Re-fetches the participant's block (zero-based, in the order of the sheet) of the current month sheet,
+  /// reporting how its rows were parsed
+  pub async fn diagnose_block(&self, block_index: usize) -> Result<(ScoreTable, ParseReport), Error> {
+    let title = self.current_title();
+    debug!("[AsyncHub] Diagnosing block #{} of sheet '{}'...", block_index, title);
+    let sheets = self
+      .fetch_spreadsheet(false)
+      .await?
+      .sheets
+      .ok_or(Error::InvalidFetchedData(EmptySheets))?;
+    let sheet_id =
+      helpers::get_sheet_id_by_title(&sheets, &title).ok_or_else(|| Error::InvalidFetchedData(NotFoundSheetId(title.to_string())))?;
+    let mut request = RequestFactory::new(sheet_id, &self.layout).construct_score_table_request(true);
+    for _ in 0..block_index {
+      request.next_table_request();
+    }
+    self.fetch_score_table_with_report(sheet_id, &request, true).await
   }
 
-  async fn fetch_score_table(&self, sheet_id: i32, request: &ScoreTableRequest, skip_parse_errors: bool) -> Result<ScoreTable, Error> {
+  async fn fetch_score_table_with_report(
+    &self,
+    sheet_id: i32,
+    request: &ScoreTableRequest,
+    skip_parse_errors: bool,
+  ) -> Result<(ScoreTable, ParseReport), Error> {
     debug!("[AsyncHub] Start fetching a person table from sheet_id={}...", sheet_id);
     let sheets = self
       .fetch_spreadsheet_with_data_filter(request.build())
@@ -122,7 +350,84 @@ impl AsyncSheetsHub {
       return Err(Error::InvalidFetchedData(EmptyGridData));
     };
     let row_data: &Vec<RowData> = row_data.ok_or(Error::InvalidFetchedData(EmptyRowData))?;
+    let (table, report) = AsyncSheetsHub::parse_score_table(row_data, skip_parse_errors)?;
+    debug!(
+      "[AsyncHub] Finish fetching a person({:?}) table with size={} from sheet_id={}",
+      table.person(),
+      table.records_count(),
+      sheet_id
+    );
+    Ok((table, report))
+  }
+
+  /// Amount of consecutive tables (up to `max`) starting from the requested one which have the person name
+  async fn count_tables(&self, request: &ScoreTableRequest, max: i32) -> Result<i32, Error> {
+    if max <= 0 {
+      return Ok(0);
+    }
+    let spreadsheet = self.fetch_spreadsheet_with_data_filter(request.build_name_row(max)).await?;
+    let names: &[CellData] = spreadsheet
+      .sheets
+      .iter()
+      .flatten()
+      .filter_map(|sheet| sheet.data.as_ref())
+      .flatten()
+      .filter_map(|grid| grid.row_data.as_ref())
+      .flatten()
+      .find_map(|row| row.values.as_deref())
+      .unwrap_or_default();
+    let count = (0..max)
+      .take_while(|&table| {
+        names
+          .get(request.name_cell_index(table))
+          .and_then(|cell| cell.formatted_value.as_deref())
+          .is_some_and(|name| !name.trim().is_empty())
+      })
+      .count();
+    Ok(count as i32)
+  }
+
+  /// Fetches `count` consecutive tables starting from the requested one with a single API call.
+  /// Tables are returned in the sheet order up to the first one without the person name,
+  /// along with the flag telling whether such a table was reached.
+  async fn fetch_score_tables(
+    &self,
+    sheet_id: i32,
+    request: &ScoreTableRequest,
+    count: i32,
+    skip_parse_errors: bool,
+  ) -> Result<(Vec<ScoreTable>, bool), Error> {
+    debug!("[AsyncHub] Start fetching {} person tables from sheet_id={}...", count, sheet_id);
+    let sheets = self
+      .fetch_spreadsheet_with_data_filter(request.build_batch(count))
+      .await?
+      .sheets
+      .ok_or(Error::InvalidFetchedData(EmptySheets))?;
 
+    let [sheet] = &sheets[..] else {
+      return Err(Error::InvalidFetchedData(InvalidVectorSize));
+    };
+    let mut grid_data_vec: Vec<&GridData> = sheet.data.iter().flatten().collect();
+    grid_data_vec.sort_by_key(|grid| grid.start_column);
+
+    let mut tables = Vec::new();
+    for grid_data in grid_data_vec {
+      // An untouched block comes back without rows at all
+      let Some(row_data) = grid_data.row_data.as_ref() else {
+        return Ok((tables, true));
+      };
+      match AsyncSheetsHub::parse_score_table(row_data, skip_parse_errors) {
+        Ok((table, _)) => tables.push(table),
+        Err(Error::InvalidFetchedData(EmptyPersonNameCell | EmptyCellData)) => return Ok((tables, true)),
+        Err(err) => return Err(err),
+      }
+    }
+    let reached_end = tables.len() < count as usize;
+    Ok((tables, reached_end))
+  }
+
+  /// Parses the rows of a participant's block, the first one holds the person name
+  fn parse_score_table(row_data: &[RowData], skip_parse_errors: bool) -> Result<(ScoreTable, ParseReport), Error> {
     let table: Vec<Result<_, Error>> = row_data
       .iter()
       .map(|d| d.values.as_ref().ok_or(Error::InvalidFetchedData(EmptyCellData)))
@@ -133,9 +438,10 @@ impl AsyncSheetsHub {
     let name = match table_iter.next() {
       Some(cell_vec) => {
         if let [first_cell, ..] = &cell_vec?[..] {
-          match &first_cell.formatted_value {
-            Some(value) => value.trim().to_owned(),
-            None => return Err(Error::InvalidFetchedData(EmptyPersonNameCell)),
+          // A whitespace-only (e.g. non-breaking space) name ends the data as well as an empty cell
+          match first_cell.formatted_value.as_deref().map(str::trim) {
+            Some(value) if !value.is_empty() => value.to_owned(),
+            _ => return Err(Error::InvalidFetchedData(EmptyPersonNameCell)),
           }
         } else {
           return Err(Error::InvalidFetchedData(EmptyCellData));
@@ -147,29 +453,43 @@ impl AsyncSheetsHub {
     // Create person
     let person = Person::new(name);
     let mut records: Vec<ScoreTableRecord> = Vec::new();
+    let mut report = ParseReport::default();
 
     // Starts from the second row
     trace!("[AsyncHub] Collecting table records for newly created {:?}", person);
     for row in table_iter {
-      let new_record = ScoreTableRecord::from_vec(row?).or_else(|err| {
-        error!("[AsyncHub] Parse error (skipped ? {}): {}", skip_parse_errors, err);
-        if skip_parse_errors {
-          Ok(ScoreTableRecord::default())
-        } else {
-          Err(err)
+      report.raw_rows += 1;
+      let row = match row {
+        Ok(cells) if !ScoreTableRecord::is_empty_row(cells) => cells,
+        Ok(_) | Err(Error::InvalidFetchedData(EmptyCellData)) => {
+          trace!("[AsyncHub] Empty row is skipped");
+          report.empty_rows += 1;
+          continue;
         }
-      })?;
+        Err(err) => return Err(err),
+      };
+      // Unparsable row is dropped entirely, so that no record with a made up date gets into the table
+      let new_record = match ScoreTableRecord::from_vec(row) {
+        Ok(record) => record,
+        Err(err) => {
+          error!("[AsyncHub] Parse error (skipped ? {}): {}", skip_parse_errors, err);
+          if skip_parse_errors {
+            report.skipped_rows += 1;
+            report.first_error.get_or_insert_with(|| format!("строка {}: {}", report.raw_rows, err));
+            continue;
+          }
+          return Err(err.into());
+        }
+      };
+      report.parsed_rows += 1;
       trace!("[AsyncHub] New score table record parsed {:?}", new_record);
       records.push(new_record);
     }
 
-    debug!(
-      "[AsyncHub] Finish fetching a person({:?}) table with size={} from sheet_id={}",
-      person,
-      records.len(),
-      sheet_id
-    );
-    Ok(ScoreTable::new(person, records))
+    if report.skipped_rows > 0 {
+      warn!("[AsyncHub] {} unparsable row(s) of {:?} are skipped", report.skipped_rows, person);
+    }
+    Ok((ScoreTable::new(person, records).with_skipped_rows(report.skipped_rows), report))
   }
 
   async fn fetch_spreadsheet(&self, include_grid_data: bool) -> Result<Spreadsheet, Error> {
@@ -177,21 +497,134 @@ impl AsyncSheetsHub {
       "[AsyncHub] Start fetching spreadsheet (include_grid_data={:})...",
       include_grid_data
     );
-    let request = self
-      .hub
-      .spreadsheets()
-      .get(&self.spreadsheet_id)
-      .include_grid_data(include_grid_data);
-    let (_body, spreadsheet) = request.doit().await?;
+    let (_body, spreadsheet) = self
+      .with_retries(|| {
+        self
+          .hub
+          .spreadsheets()
+          .get(&self.spreadsheet_id)
+          .include_grid_data(include_grid_data)
+          .doit()
+      })
+      .await?;
     debug!("[AsyncHub] Finish fetching spreadsheet");
     Ok(spreadsheet)
   }
 
   async fn fetch_spreadsheet_with_data_filter(&self, filter: GetSpreadsheetByDataFilterRequest) -> Result<Spreadsheet, Error> {
     debug!("[AsyncHub] Start fetching spreadsheet with filter data request...");
-    let request = self.hub.spreadsheets().get_by_data_filter(filter, &self.spreadsheet_id);
-    let (_body, spreadsheet) = request.doit().await?;
+    let (_body, spreadsheet) = self
+      .with_retries(|| self.hub.spreadsheets().get_by_data_filter(filter.clone(), &self.spreadsheet_id).doit())
+      .await?;
     debug!("[AsyncHub] Finish fetching spreadsheet");
     Ok(spreadsheet)
   }
+
+  async fn with_retries<T, F, Fut>(&self, mut call: F) -> Result<T, Error>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, google_sheets4::Error>>,
+  {
+    let mut attempt = 0;
+    loop {
+      match call().await {
+        Ok(value) => return Ok(value),
+        Err(err) if attempt < self.retry.max_retries && RetryPolicy::is_transient(&err) => {
+          let delay = self.retry.delay(attempt);
+          attempt += 1;
+          warn!(
+            "[AsyncHub] Transient API error: {}. Retry {}/{} in {:?}",
+            err, attempt, self.retry.max_retries, delay
+          );
+          tokio::time::sleep(delay).await;
+        }
+        Err(err) => return Err(err.into()),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use google_sheets4::api::{CellData, ExtendedValue, RowData};
+
+  use super::*;
+
+  fn name_row(name: &str) -> RowData {
+    RowData {
+      values: Some(vec![CellData {
+        formatted_value: Some(name.to_string()),
+        ..Default::default()
+      }]),
+    }
+  }
+
+  fn cells_row(values: &[&str]) -> RowData {
+    let cell = |value: &&str| CellData {
+      formatted_value: Some(value.to_string()),
+      ..Default::default()
+    };
+    RowData {
+      values: Some(values.iter().map(cell).collect()),
+    }
+  }
+
+  /// Row of 15.01.2024 (the serial date) with the sport score only
+  fn filled_row() -> RowData {
+    let mut row = cells_row(&["", "1", "", "", "", "", "", "1", "10%"]);
+    row.values.as_mut().unwrap()[0] = CellData {
+      effective_value: Some(ExtendedValue {
+        number_value: Some(45306.),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    row
+  }
+
+  #[test]
+  fn whitespace_only_name_ends_the_data() {
+    let result = AsyncSheetsHub::parse_score_table(&[name_row(" \u{a0}\t")], true);
+    assert!(matches!(result, Err(Error::InvalidFetchedData(EmptyPersonNameCell))));
+  }
+
+  #[test]
+  fn name_is_trimmed() {
+    let (table, _) = AsyncSheetsHub::parse_score_table(&[name_row("\u{a0}Борис ")], true).unwrap();
+    assert_eq!(table.person().name(), "Борис");
+  }
+
+  #[test]
+  fn unparsable_rows_are_counted_on_the_table() {
+    let rows = [name_row("Борис"), name_row("не дата")];
+    let (table, report) = AsyncSheetsHub::parse_score_table(&rows, true).unwrap();
+    assert_eq!((table.records_count(), table.skipped_rows(), report.skipped_rows), (0, 1, 1));
+
+    assert!(AsyncSheetsHub::parse_score_table(&rows, false).is_err());
+  }
+
+  #[test]
+  fn trailing_empty_rows_are_skipped() {
+    // The total and percent formulas are evaluated on the untouched rows too
+    let rows = [
+      name_row("Анна"),
+      filled_row(),
+      cells_row(&["", "", "", "", "", "", "", "0", "0%"]),
+      cells_row(&[" ", "\u{a0}"]),
+      RowData { values: None },
+    ];
+    let (table, report) = AsyncSheetsHub::parse_score_table(&rows, false).unwrap();
+    assert_eq!(table.records_count(), 1);
+    assert_eq!((report.raw_rows, report.parsed_rows, report.empty_rows, report.skipped_rows), (4, 1, 3, 0));
+  }
+
+  #[test]
+  fn skipped_row_leaves_no_made_up_dates() {
+    let rows = [name_row("Анна"), name_row("не дата"), filled_row(), cells_row(&["15.01.2024", "много"])];
+    let (table, report) = AsyncSheetsHub::parse_score_table(&rows, true).unwrap();
+
+    let dates: Vec<_> = table.records().iter().map(|rec| *rec.date()).collect();
+    assert_eq!(dates, [NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()]);
+    assert_eq!(report.skipped_rows, 2);
+  }
 }
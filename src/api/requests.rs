@@ -1,19 +1,22 @@
 use google_sheets4::api::{DataFilter, GetSpreadsheetByDataFilterRequest, GridRange};
 use log::trace;
 
+use crate::dashboard::score_table::entities::Category;
+
 // Build requests for a particular sheet in Google Spreadsheet
-pub struct RequestFactory {
+pub struct RequestFactory<'a> {
   sheet_id: i32,
+  layout: &'a SheetLayout,
 }
 
-impl RequestFactory {
-  pub fn new(sheet_id: i32) -> Self {
-    RequestFactory { sheet_id }
+impl<'a> RequestFactory<'a> {
+  pub fn new(sheet_id: i32, layout: &'a SheetLayout) -> Self {
+    RequestFactory { sheet_id, layout }
   }
 
   pub fn construct_score_table_request(&self, include_grid_data: bool) -> ScoreTableRequest {
     trace!("[RequestFactory] Building new ScoreTableRequest request");
-    let req = ScoreTableRequest::new(self.sheet_id, include_grid_data);
+    let req = ScoreTableRequest::new(self.sheet_id, self.layout, include_grid_data);
     trace!("[RequestFactory] ScoreTableRequest data {:#?}", req);
     req
   }
@@ -25,23 +28,20 @@ pub struct ScoreTableRequest {
   end_column_index: i32,
   start_row_index: i32,
   end_row_index: i32,
+  /// Distance between the first columns of the neighbouring participant blocks
+  column_offset: i32,
   sheet_id: i32,
   include_grid_data: bool,
 }
 
 impl ScoreTableRequest {
-  const COLUMN_OFFSET: i32 = 10;
-  const INITIAL_START_COLUMN_INDEX: i32 = 1;
-  const INITIAL_END_COLUMN_INDEX: i32 = 10;
-  const INITIAL_START_ROW_INDEX: i32 = 3;
-  const INITIAL_END_ROW_INDEX: i32 = 35;
-
-  fn new(sheet_id: i32, include_grid_data: bool) -> Self {
+  fn new(sheet_id: i32, layout: &SheetLayout, include_grid_data: bool) -> Self {
     Self {
-      start_column_index: ScoreTableRequest::INITIAL_START_COLUMN_INDEX,
-      end_column_index: ScoreTableRequest::INITIAL_END_COLUMN_INDEX,
-      start_row_index: ScoreTableRequest::INITIAL_START_ROW_INDEX,
-      end_row_index: ScoreTableRequest::INITIAL_END_ROW_INDEX,
+      start_column_index: layout.start_column_index,
+      end_column_index: layout.start_column_index + layout.columns_per_block,
+      start_row_index: layout.start_row_index,
+      end_row_index: layout.end_row_index,
+      column_offset: layout.block_width,
       sheet_id,
       include_grid_data,
     }
@@ -49,29 +49,191 @@ impl ScoreTableRequest {
 
   /// Applies an offset to the current request coordinates
   pub fn next_table_request(&mut self) {
-    self.start_column_index += ScoreTableRequest::COLUMN_OFFSET;
-    self.end_column_index += ScoreTableRequest::COLUMN_OFFSET;
+    self.skip_tables(1);
+  }
+
+  /// Applies an offset of `count` tables to the current request coordinates
+  pub fn skip_tables(&mut self, count: i32) {
+    self.start_column_index += self.column_offset * count;
+    self.end_column_index += self.column_offset * count;
     trace!("[ScoreTableRequest] Updated ScoreTableRequest data {:#?}", self);
   }
 
+  /// Amount of tables, starting from the current one, which fit into the sheet having `column_count` columns
+  pub fn tables_fitting(&self, column_count: i32) -> i32 {
+    if column_count < self.end_column_index {
+      return 0;
+    }
+    (column_count - self.end_column_index) / self.column_offset + 1
+  }
+
   pub fn build(&self) -> GetSpreadsheetByDataFilterRequest {
+    self.build_batch(1)
+  }
+
+  /// Request of `count` consecutive tables starting from the current one, one data filter per table
+  pub fn build_batch(&self, count: i32) -> GetSpreadsheetByDataFilterRequest {
+    let data_filters = (0..count)
+      .map(|i| DataFilter {
+        a1_range: None,
+        developer_metadata_lookup: None,
+        grid_range: Some(self.grid_range(i * self.column_offset)),
+      })
+      .collect();
+
+    GetSpreadsheetByDataFilterRequest {
+      data_filters: Some(data_filters),
+      include_grid_data: Some(self.include_grid_data),
+    }
+  }
+
+  /// Request of the name cells only (the first row) of `count` consecutive tables starting from the current one
+  pub fn build_name_row(&self, count: i32) -> GetSpreadsheetByDataFilterRequest {
     let grid_range = GridRange {
-      end_column_index: Some(self.end_column_index),
-      end_row_index: Some(self.end_row_index),
+      end_column_index: Some(self.start_column_index + self.column_offset * (count - 1) + 1),
+      end_row_index: Some(self.start_row_index + 1),
       sheet_id: Some(self.sheet_id),
       start_column_index: Some(self.start_column_index),
       start_row_index: Some(self.start_row_index),
     };
 
-    let data_filter = DataFilter {
-      a1_range: None,
-      developer_metadata_lookup: None,
-      grid_range: Some(grid_range),
-    };
-
     GetSpreadsheetByDataFilterRequest {
-      data_filters: Some(vec![data_filter]),
-      include_grid_data: Some(self.include_grid_data),
+      data_filters: Some(vec![DataFilter {
+        a1_range: None,
+        developer_metadata_lookup: None,
+        grid_range: Some(grid_range),
+      }]),
+      include_grid_data: Some(true),
+    }
+  }
+
+  /// Position of the table's name cell within the name row requested by `build_name_row`
+  pub fn name_cell_index(&self, table: i32) -> usize {
+    (table * self.column_offset) as usize
+  }
+
+  fn grid_range(&self, column_offset: i32) -> GridRange {
+    GridRange {
+      end_column_index: Some(self.end_column_index + column_offset),
+      end_row_index: Some(self.end_row_index),
+      sheet_id: Some(self.sheet_id),
+      start_column_index: Some(self.start_column_index + column_offset),
+      start_row_index: Some(self.start_row_index),
+    }
+  }
+}
+
+/// Layout of the sheet expected by the parser: participants are placed side by side in
+/// equally wide blocks, each block holds the name row followed by one row per day.
+#[derive(Debug, Clone)]
+pub struct SheetLayout {
+  pub start_column_index: i32,
+  pub block_width: i32,
+  pub columns_per_block: i32,
+  pub start_row_index: i32,
+  pub end_row_index: i32,
+}
+
+impl Default for SheetLayout {
+  fn default() -> Self {
+    Self {
+      start_column_index: 1,
+      block_width: SheetLayout::DEFAULT_BLOCK_WIDTH,
+      columns_per_block: 9,
+      start_row_index: 3,
+      end_row_index: 35,
+    }
+  }
+}
+
+impl SheetLayout {
+  pub const DEFAULT_BLOCK_WIDTH: i32 = 10;
+  /// Date, the categories, total and percent columns
+  pub const MIN_COLUMNS_PER_BLOCK: i32 = Category::ALL.len() as i32 + 3;
+
+  /// Layout where the first participant's block occupies the A1 notation `range` (e.g. `B4:J35`),
+  /// the next blocks follow every `block_width` columns (the range width by default)
+  pub fn from_a1_range(range: &str, block_width: Option<i32>) -> Result<SheetLayout, String> {
+    let (start, end) = range
+      .split_once(':')
+      .ok_or_else(|| format!("`{range}` is not a range of two cells"))?;
+    let (start_column, start_row) = SheetLayout::parse_a1_cell(start)?;
+    let (end_column, end_row) = SheetLayout::parse_a1_cell(end)?;
+    if end_column < start_column || end_row <= start_row {
+      return Err(format!("`{range}` ends before it starts or holds the name row only"));
+    }
+    let columns_per_block = end_column - start_column + 1;
+    if columns_per_block < SheetLayout::MIN_COLUMNS_PER_BLOCK {
+      return Err(format!(
+        "`{range}` is {columns_per_block} columns wide, at least {} are expected",
+        SheetLayout::MIN_COLUMNS_PER_BLOCK
+      ));
+    }
+    let block_width = block_width.unwrap_or(columns_per_block);
+    if block_width < columns_per_block {
+      return Err(format!("block width {block_width} is less than the range width {columns_per_block}"));
+    }
+    Ok(SheetLayout {
+      start_column_index: start_column,
+      block_width,
+      columns_per_block,
+      start_row_index: start_row,
+      // The end row is inclusive in A1 notation and exclusive in the grid range
+      end_row_index: end_row + 1,
+    })
+  }
+
+  /// Zero-based column and row indexes of an A1 notation cell (`B4` -> (1, 3))
+  fn parse_a1_cell(cell: &str) -> Result<(i32, i32), String> {
+    let cell = cell.trim().to_ascii_uppercase();
+    let digits_at = cell
+      .find(|c: char| c.is_ascii_digit())
+      .ok_or_else(|| format!("`{cell}` has no row number"))?;
+    let (letters, digits) = cell.split_at(digits_at);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+      return Err(format!("`{cell}` has no valid column letters"));
+    }
+    let column = letters.bytes().fold(0, |acc, b| acc * 26 + i32::from(b - b'A' + 1)) - 1;
+    let row: i32 = digits.parse().map_err(|_| format!("`{cell}` has no valid row number"))?;
+    if row < 1 {
+      return Err(format!("`{cell}` has no valid row number"));
+    }
+    Ok((column, row - 1))
+  }
+
+  /// Converts zero-based column index into A1 notation letters (0 -> A, 26 -> AA)
+  pub fn column_letter(index: i32) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+      let rem = (n - 1) % 26;
+      letters.push((b'A' + rem as u8) as char);
+      n = (n - 1) / 26;
+    }
+    letters.iter().rev().collect()
+  }
+}
+
+impl std::fmt::Display for SheetLayout {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let col = |offset: i32| SheetLayout::column_letter(self.start_column_index + offset);
+    // A1 rows are one-based while the grid indexes are zero-based
+    let name_row = self.start_row_index + 1;
+    writeln!(f, "📐 Ожидаемая структура листа (первый участник):")?;
+    writeln!(f, "Имя участника: ячейка {}{}", col(0), name_row)?;
+    writeln!(f, "Строки с днями: {}–{}", name_row + 1, self.end_row_index)?;
+    writeln!(f, "{}: дата", col(0))?;
+    for (i, category) in Category::ALL.iter().enumerate() {
+      writeln!(f, "{}: {} {} ({})", col(i as i32 + 1), category.emoji(), category.label(), category.key())?;
     }
+    writeln!(f, "{}: итого", col(Category::ALL.len() as i32 + 1))?;
+    writeln!(f, "{}: процент", col(Category::ALL.len() as i32 + 2))?;
+    write!(
+      f,
+      "Блок участника занимает {} столбцов, следующий участник начинается через {} столбцов (со столбца {})",
+      self.columns_per_block,
+      self.block_width,
+      col(self.block_width)
+    )
   }
 }
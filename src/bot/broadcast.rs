@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use log::{error, info, warn};
+use teloxide::{
+  payloads::{EditMessageTextSetters, SendMessageSetters},
+  requests::Requester,
+  types::{ChatId, InlineKeyboardMarkup, Message, MessageId, ParseMode},
+  ApiError, Bot, RequestError,
+};
+
+use crate::helpers;
+
+/// Pause between consecutive sends to stay below Telegram's ~30 messages/sec limit
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default)]
+pub struct BroadcastReport {
+  pub delivered: usize,
+  pub failed: usize,
+}
+
+impl std::fmt::Display for BroadcastReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "доставлено: {}, ошибок: {}", self.delivered, self.failed)
+  }
+}
+
+/// Sends the text to every chat one by one. A failed send doesn't abort the loop,
+/// flood-control errors are retried once after the delay requested by Telegram.
+pub async fn broadcast(bot: &Bot, chats: &[ChatId], text: &str, parse_mode: Option<ParseMode>) -> BroadcastReport {
+  let mut report = BroadcastReport::default();
+  for (i, chat_id) in chats.iter().enumerate() {
+    if i > 0 {
+      tokio::time::sleep(SEND_INTERVAL).await;
+    }
+    let result = match send(bot, *chat_id, text, parse_mode).await {
+      Err(RequestError::RetryAfter(delay)) => {
+        warn!("[Broadcast] Flood control hit for chat_id={}, retrying in {:?}", chat_id, delay);
+        tokio::time::sleep(delay).await;
+        send(bot, *chat_id, text, parse_mode).await
+      }
+      other => other,
+    };
+    match result {
+      Ok(()) => {
+        info!("[Broadcast] Sent message to chat_id={}", chat_id);
+        report.delivered += 1;
+      }
+      Err(err) => {
+        warn!("[Broadcast] Unable to send message to chat_id={} due to {:?}", chat_id, err);
+        report.failed += 1;
+      }
+    }
+  }
+  report
+}
+
+async fn send(bot: &Bot, chat_id: ChatId, text: &str, parse_mode: Option<ParseMode>) -> Result<(), RequestError> {
+  match parse_mode {
+    Some(ParseMode::MarkdownV2) => send_markdown(bot, chat_id, text).await?,
+    Some(mode) => bot.send_message(chat_id, text).parse_mode(mode).await?,
+    None => bot.send_message(chat_id, text).await?,
+  };
+  Ok(())
+}
+
+/// Sends MarkdownV2 text. If Telegram can't parse the entities (an escaping bug), the same content
+/// is sent once more as plain text, so the user still gets the message.
+pub async fn send_markdown(bot: &Bot, chat_id: ChatId, text: &str) -> Result<Message, RequestError> {
+  match bot.send_message(chat_id, text).parse_mode(ParseMode::MarkdownV2).await {
+    Err(err) if is_parse_entities_error(&err) => {
+      error!(
+        "[Sender] MarkdownV2 parsing failed for chat_id={} ({}), falling back to plain text. Text: {:?}",
+        chat_id, err, text
+      );
+      bot.send_message(chat_id, helpers::strip_markdown_v2(text)).await
+    }
+    result => result,
+  }
+}
+
+/// Same as `send_markdown`, the text exceeding Telegram's limit is sent in several consecutive messages
+pub async fn send_markdown_chunks(bot: &Bot, chat_id: ChatId, text: &str) -> Result<(), RequestError> {
+  for chunk in helpers::split_message(text, helpers::TELEGRAM_MESSAGE_LIMIT) {
+    send_markdown(bot, chat_id, &chunk).await?;
+  }
+  Ok(())
+}
+
+/// Same as `send_markdown`, with the inline keyboard attached to the message
+pub async fn send_markdown_with_keyboard(
+  bot: &Bot,
+  chat_id: ChatId,
+  text: &str,
+  keyboard: InlineKeyboardMarkup,
+) -> Result<Message, RequestError> {
+  match bot
+    .send_message(chat_id, text)
+    .parse_mode(ParseMode::MarkdownV2)
+    .reply_markup(keyboard.clone())
+    .await
+  {
+    Err(err) if is_parse_entities_error(&err) => {
+      error!(
+        "[Sender] MarkdownV2 parsing failed for chat_id={} ({}), falling back to plain text. Text: {:?}",
+        chat_id, err, text
+      );
+      bot
+        .send_message(chat_id, helpers::strip_markdown_v2(text))
+        .reply_markup(keyboard)
+        .await
+    }
+    result => result,
+  }
+}
+
+/// Replaces the text of the sent message with MarkdownV2 one, falling back to plain text like `send_markdown`.
+/// Unchanged text isn't considered an error.
+pub async fn edit_markdown(bot: &Bot, chat_id: ChatId, message_id: MessageId, text: &str) -> Result<(), RequestError> {
+  let result = match bot
+    .edit_message_text(chat_id, message_id, text)
+    .parse_mode(ParseMode::MarkdownV2)
+    .await
+  {
+    Err(err) if is_parse_entities_error(&err) => {
+      error!(
+        "[Sender] MarkdownV2 parsing failed for chat_id={} ({}), falling back to plain text. Text: {:?}",
+        chat_id, err, text
+      );
+      bot
+        .edit_message_text(chat_id, message_id, helpers::strip_markdown_v2(text))
+        .await
+    }
+    result => result,
+  };
+  match result {
+    Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+    Err(err) => Err(err),
+  }
+}
+
+/// The chat won't ever accept messages from the bot: blocked by the user, kicked out of the group, etc.
+/// "Chat not found" isn't one of them, it's also returned for a mistyped id or a chat migrated to a supergroup.
+pub fn is_unreachable_chat_error(err: &RequestError) -> bool {
+  matches!(
+    err,
+    RequestError::Api(
+      ApiError::BotBlocked
+        | ApiError::BotKicked
+        | ApiError::BotKickedFromSupergroup
+        | ApiError::UserDeactivated
+    )
+  )
+}
+
+pub fn is_parse_entities_error(err: &RequestError) -> bool {
+  match err {
+    RequestError::Api(ApiError::CantParseEntities) => true,
+    RequestError::Api(ApiError::Unknown(description)) => description.contains("can't parse entities"),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::fake::FakeTelegram;
+
+  #[test]
+  fn parse_entities_errors_are_recognized() {
+    assert!(is_parse_entities_error(&RequestError::Api(ApiError::CantParseEntities)));
+    let unknown = ApiError::Unknown("Bad Request: can't parse entities: Character '!' is reserved".to_string());
+    assert!(is_parse_entities_error(&RequestError::Api(unknown)));
+    assert!(!is_parse_entities_error(&RequestError::Api(ApiError::BotBlocked)));
+  }
+
+  #[tokio::test]
+  async fn unparsed_markdown_is_sent_again_as_plain_text() {
+    let telegram = FakeTelegram::start();
+    telegram.reject_markdown();
+
+    send_markdown(&telegram.bot(), ChatId(1), "*Анна* набрала 70\\%!").await.unwrap();
+    assert_eq!(telegram.take_texts(), ["*Анна* набрала 70\\%!", "Анна набрала 70%!"]);
+  }
+}
@@ -0,0 +1,141 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use chrono::NaiveDate;
+use log::trace;
+
+use crate::dashboard::Dashboard;
+
+use super::hooks::{FetchHook, Outgoing};
+
+type SummaryKey = (NaiveDate, u64);
+
+struct SummaryCacheState {
+  /// Bumped on every dashboard replacement, so the entries of the replaced dashboard are never hit
+  generation: u64,
+  /// Most recently used entry is at the front
+  entries: VecDeque<(SummaryKey, Vec<String>)>,
+}
+
+/// Bounded LRU cache of the summary lines by date for the current dashboard.
+/// Registered as a fetch hook to be invalidated once the fetcher replaces the dashboard.
+pub struct SummaryCache {
+  capacity: usize,
+  state: Mutex<SummaryCacheState>,
+}
+
+impl SummaryCache {
+  /// Zero capacity disables the cache
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      state: Mutex::new(SummaryCacheState {
+        generation: 0,
+        entries: VecDeque::with_capacity(capacity),
+      }),
+    }
+  }
+
+  /// Returns cached summary for the date or computes it with `summary` and keeps the result
+  pub fn get_or_insert_with<E>(
+    &self,
+    date: &NaiveDate,
+    summary: impl FnOnce() -> Result<Vec<String>, E>,
+  ) -> Result<Vec<String>, E> {
+    if self.capacity == 0 {
+      return summary();
+    }
+    let key = (*date, self.state.lock().unwrap().generation);
+    if let Some(lines) = self.lookup(&key) {
+      trace!("[SummaryCache] Hit for {:?}", key);
+      return Ok(lines);
+    }
+
+    trace!("[SummaryCache] Miss for {:?}", key);
+    let lines = summary()?;
+    let mut state = self.state.lock().unwrap();
+    // Dashboard might be replaced while the summary was computed
+    if state.generation == key.1 {
+      state.entries.push_front((key, lines.clone()));
+      state.entries.truncate(self.capacity);
+    }
+    Ok(lines)
+  }
+
+  fn lookup(&self, key: &SummaryKey) -> Option<Vec<String>> {
+    let mut state = self.state.lock().unwrap();
+    let position = state.entries.iter().position(|(k, _)| k == key)?;
+    let entry = state.entries.remove(position)?;
+    let lines = entry.1.clone();
+    state.entries.push_front(entry);
+    Some(lines)
+  }
+}
+
+impl FetchHook for SummaryCache {
+  fn name(&self) -> &str {
+    "SummaryCache"
+  }
+
+  fn on_update(&self, _previous: &Dashboard, _latest: &Dashboard) -> Vec<Outgoing> {
+    let mut state = self.state.lock().unwrap();
+    state.generation += 1;
+    state.entries.clear();
+    Vec::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{cell::Cell, convert::Infallible};
+
+  use super::*;
+
+  fn summary(cache: &SummaryCache, date: &NaiveDate, computed: &Cell<usize>) -> Vec<String> {
+    let lines = cache.get_or_insert_with(date, || {
+      computed.set(computed.get() + 1);
+      Ok::<_, Infallible>(vec![format!("#{}", computed.get())])
+    });
+    lines.unwrap()
+  }
+
+  #[test]
+  fn repeated_request_is_a_hit() {
+    let cache = SummaryCache::new(2);
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let computed = Cell::new(0);
+
+    assert_eq!(summary(&cache, &date, &computed), vec!["#1"]);
+    assert_eq!(summary(&cache, &date, &computed), vec!["#1"]);
+    assert_eq!(computed.get(), 1);
+  }
+
+  #[test]
+  fn dashboard_update_invalidates_entries() {
+    let cache = SummaryCache::new(2);
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let computed = Cell::new(0);
+
+    summary(&cache, &date, &computed);
+    cache.on_update(&Dashboard::new(), &Dashboard::new());
+    assert_eq!(summary(&cache, &date, &computed), vec!["#2"]);
+    assert_eq!(computed.get(), 2);
+  }
+
+  #[test]
+  fn least_recently_used_entry_is_evicted() {
+    let cache = SummaryCache::new(2);
+    let dates: Vec<_> = (1..=3).map(|day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap()).collect();
+    let computed = Cell::new(0);
+
+    summary(&cache, &dates[0], &computed);
+    summary(&cache, &dates[1], &computed);
+    summary(&cache, &dates[0], &computed);
+    summary(&cache, &dates[2], &computed);
+    assert_eq!(computed.get(), 3);
+    // The 2nd date was used least recently, the 1st one is still cached
+    summary(&cache, &dates[0], &computed);
+    assert_eq!(computed.get(), 3);
+    summary(&cache, &dates[1], &computed);
+    assert_eq!(computed.get(), 4);
+  }
+}
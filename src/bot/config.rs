@@ -1,36 +1,313 @@
-use config::Config;
+use std::{collections::HashMap, net::SocketAddr};
+
+use chrono::NaiveTime;
+use config::{Config, ConfigError};
 use log::info;
 use serde::{Deserialize, Serialize};
 use teloxide::types::ChatId;
+use url::Url;
+
+use crate::{
+  api::{requests::SheetLayout, RetryPolicy},
+  dashboard::score_table::entities::{Category, CategoryAppearance, DisplayRounding, NameSanitization, RoundingMode},
+  helpers::{self, i18n::Locale},
+};
 
 use super::error::CongratulatorError;
 
+/// Env variable holding the path to an optional config file (TOML, YAML, JSON, ...).
+/// Values from the environment take precedence over the file ones.
+const CONFIG_FILE_ENV: &str = "APP_CONFIG_FILE";
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CongratulatorConfig {
   bot_token: String,
   spreadsheet_id: String,
-  notify_chat_id: i64,
+  sheet_title_override: Option<String>,
+  skip_parse_errors: Option<bool>,
+  /// Comma-separated, a single id is accepted as well
+  notify_chat_id: String,
   api_service_key_json_data: String,
-  api_data_fetch_task_interval_min: u32
+  api_data_fetch_task_interval_min: u32,
+  api_fetch_max_retries: Option<u32>,
+  api_fetch_backoff_ms: Option<u64>,
+  refresh_cooldown_sec: Option<u64>,
+  sheet_block_range: Option<String>,
+  sheet_block_width: Option<i32>,
+  #[serde(default)]
+  categories: HashMap<String, CategoryAppearance>,
+  fill_deadline: Option<String>,
+  notify_time: Option<String>,
+  summary_time: Option<String>,
+  timezone_offset_hours: Option<i32>,
+  default_locale: Option<Locale>,
+  admin_chat_ids: Option<String>,
+  allowed_chat_ids: Option<String>,
+  broadcast_confirm_threshold: Option<usize>,
+  #[serde(default)]
+  command_aliases: HashMap<String, String>,
+  #[serde(default)]
+  telegram_usernames: HashMap<String, String>,
+  remind_only_missing: Option<bool>,
+  celebration_percent: Option<i32>,
+  celebrate_participants: Option<bool>,
+  group_celebration_percent: Option<i32>,
+  celebrate_group: Option<bool>,
+  improvement_delta_percent: Option<i32>,
+  improvement_chat_ids: Option<String>,
+  combined_evening_message: Option<bool>,
+  yesterday_from_previous_month: Option<bool>,
+  dialogue_storage_file: Option<String>,
+  notes_file: Option<String>,
+  dashboard_cache_path: Option<String>,
+  anonymize_month_table: Option<bool>,
+  watches_file: Option<String>,
+  summary_cache_size: Option<usize>,
+  year_overview_months: Option<u32>,
+  daily_max_score: Option<f64>,
+  checkbox_categories: Option<String>,
+  /// Comma-separated labels of the category columns 1..=6, an empty one keeps the default
+  category_labels: Option<String>,
+  name_sanitization: Option<NameSanitization>,
+  score_decimals: Option<usize>,
+  percent_decimals: Option<usize>,
+  percent_rounding: Option<RoundingMode>,
+  telegram_mode: Option<TelegramMode>,
+  webhook_url: Option<String>,
+  webhook_bind_address: Option<String>,
+  health_check_port: Option<u16>,
+  health_check_staleness_min: Option<u32>,
 }
 
+/// How the updates are received from Telegram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelegramMode {
+  Polling,
+  Webhook,
+}
+
+/// Address the webhook listener binds to unless configured otherwise
+const DEFAULT_WEBHOOK_BIND_ADDRESS: &str = "0.0.0.0:8443";
+
 impl CongratulatorConfig {
   pub fn load_from_env() -> Result<CongratulatorConfig, CongratulatorError> {
     info!("[Config] Application config is getting loaded from env");
-    let serialized = Config::builder().add_source(config::Environment::default()).build()?;
+    let mut builder = Config::builder();
+    if let Ok(path) = std::env::var(CONFIG_FILE_ENV) {
+      info!("[Config] Config file '{}' is added as a source", path);
+      builder = builder.add_source(config::File::with_name(&path));
+    }
+    let serialized = builder.add_source(config::Environment::default()).build()?;
     let deserialized = serialized.try_deserialize::<Self>()?;
+    deserialized.validate()?;
     info!("[Config] Application config has been loaded");
     Ok(deserialized)
   }
 
+  fn validate(&self) -> Result<(), ConfigError> {
+    if let Some(key) = self.categories.keys().find(|k| Category::from_key(k).is_none()) {
+      return Err(ConfigError::Message(format!("Unknown category key `{key}` in categories section")));
+    }
+    if let Some(key) = self.checkbox_category_keys().find(|k| Category::from_key(k).is_none()) {
+      return Err(ConfigError::Message(format!("Unknown category key `{key}` in checkbox_categories")));
+    }
+    if self.category_labels().len() > Category::COUNT {
+      return Err(ConfigError::Message(format!(
+        "category_labels lists more than {} labels, one per category column",
+        Category::COUNT
+      )));
+    }
+    self.sheet_layout().map_err(|err| ConfigError::Message(format!("Invalid sheet layout: {err}")))?;
+    if self.telegram_mode == Some(TelegramMode::Webhook) && self.webhook_url.is_none() {
+      return Err(ConfigError::Message("telegram_mode is webhook, but webhook_url is not set".to_string()));
+    }
+    if let Some(url) = &self.webhook_url {
+      Url::parse(url).map_err(|err| ConfigError::Message(format!("Invalid webhook_url `{url}`: {err}")))?;
+    }
+    if let Some(address) = &self.webhook_bind_address {
+      address
+        .parse::<SocketAddr>()
+        .map_err(|err| ConfigError::Message(format!("Invalid webhook_bind_address `{address}`: {err}")))?;
+    }
+    if let Some(offset) = self.timezone_offset_hours {
+      if !(-12..=14).contains(&offset) {
+        return Err(ConfigError::Message(format!("Invalid timezone_offset_hours `{offset}` (expected -12..=14)")));
+      }
+    }
+    if let Some(deadline) = &self.fill_deadline {
+      helpers::parse_time_hm(deadline)
+        .map_err(|err| ConfigError::Message(format!("Invalid fill_deadline `{deadline}` (expected HH:MM): {err}")))?;
+    }
+    for (name, time) in [("notify_time", &self.notify_time), ("summary_time", &self.summary_time)] {
+      if let Some(time) = time {
+        helpers::parse_time_hm(time)
+          .map_err(|err| ConfigError::Message(format!("Invalid {name} `{time}` (expected HH:MM): {err}")))?;
+      }
+    }
+    // Parsed percentages are clamped into 0..=100, a higher threshold would never be reached
+    for (name, percent) in [
+      ("celebration_percent", self.celebration_percent),
+      ("group_celebration_percent", self.group_celebration_percent),
+    ] {
+      if let Some(percent) = percent.filter(|p| !(0..=100).contains(p)) {
+        return Err(ConfigError::Message(format!("Invalid {name} `{percent}` (expected 0..=100)")));
+      }
+    }
+    if let Some(months) = self.year_overview_months {
+      if !(1..=12).contains(&months) {
+        return Err(ConfigError::Message(format!("Invalid year_overview_months `{months}` (expected 1..=12)")));
+      }
+    }
+    match parse_chat_ids(&self.notify_chat_id) {
+      Ok(ids) if ids.is_empty() => return Err(ConfigError::Message("notify_chat_id is empty".to_string())),
+      Ok(_) => {}
+      Err(err) => {
+        let ids = &self.notify_chat_id;
+        return Err(ConfigError::Message(format!("Invalid notify_chat_id `{ids}`: {err}")));
+      }
+    }
+    if let Some(ids) = &self.admin_chat_ids {
+      parse_chat_ids(ids).map_err(|err| ConfigError::Message(format!("Invalid admin_chat_ids `{ids}`: {err}")))?;
+    }
+    if let Some(ids) = &self.improvement_chat_ids {
+      parse_chat_ids(ids).map_err(|err| ConfigError::Message(format!("Invalid improvement_chat_ids `{ids}`: {err}")))?;
+    }
+    if let Some(ids) = &self.allowed_chat_ids {
+      parse_chat_ids(ids).map_err(|err| ConfigError::Message(format!("Invalid allowed_chat_ids `{ids}`: {err}")))?;
+    }
+    Ok(())
+  }
+
+  pub fn categories(&self) -> &HashMap<String, CategoryAppearance> {
+    &self.categories
+  }
+
+  /// Webhook when the URL is set, unless the polling is chosen explicitly
+  pub fn telegram_mode(&self) -> TelegramMode {
+    match (self.telegram_mode, &self.webhook_url) {
+      (Some(mode), _) => mode,
+      (None, Some(_)) => TelegramMode::Webhook,
+      (None, None) => TelegramMode::Polling,
+    }
+  }
+
+  /// Public URL and local bind address for the webhook mode, `None` means long polling
+  pub fn webhook(&self) -> Option<(Url, SocketAddr)> {
+    if self.telegram_mode() != TelegramMode::Webhook {
+      return None;
+    }
+    let url = Url::parse(self.webhook_url.as_deref()?).ok()?;
+    let address = self
+      .webhook_bind_address
+      .as_deref()
+      .unwrap_or(DEFAULT_WEBHOOK_BIND_ADDRESS)
+      .parse()
+      .ok()?;
+    Some((url, address))
+  }
+
+  /// Port of the readiness endpoint, `None` if it's disabled (default)
+  pub fn health_check_port(&self) -> Option<u16> {
+    self.health_check_port
+  }
+
+  /// Age of the last successful fetch after which the bot isn't ready, 3 fetch intervals by default
+  pub fn health_check_staleness(&self) -> std::time::Duration {
+    let minutes = self
+      .health_check_staleness_min
+      .unwrap_or(self.api_data_fetch_task_interval_min.saturating_mul(3));
+    std::time::Duration::from_secs(u64::from(minutes) * 60)
+  }
+
+  /// Rounding of the displayed scores (1 decimal by default) and computed percentages
+  pub fn display_rounding(&self) -> DisplayRounding {
+    let default = DisplayRounding::default();
+    DisplayRounding {
+      score_decimals: self.score_decimals.unwrap_or(default.score_decimals),
+      percent_decimals: self.percent_decimals.unwrap_or(default.percent_decimals),
+      percent_mode: self.percent_rounding.unwrap_or(default.percent_mode),
+    }
+  }
+
+  /// How names with MarkdownV2 formatting characters are displayed (escaped by default)
+  pub fn name_sanitization(&self) -> NameSanitization {
+    self.name_sanitization.unwrap_or_default()
+  }
+
+  /// Categories whose sheet columns are checkboxes rather than numbers
+  pub fn checkbox_categories(&self) -> Vec<Category> {
+    self.checkbox_category_keys().filter_map(Category::from_key).collect()
+  }
+
+  /// Labels of the category columns in order, empty ones keep the default labels
+  pub fn category_labels(&self) -> Vec<String> {
+    match self.category_labels.as_deref() {
+      Some(labels) => labels.split(',').map(|label| label.trim().to_string()).collect(),
+      None => Vec::new(),
+    }
+  }
+
+  fn checkbox_category_keys(&self) -> impl Iterator<Item = &str> {
+    self
+      .checkbox_categories
+      .as_deref()
+      .unwrap_or_default()
+      .split(',')
+      .map(str::trim)
+      .filter(|k| !k.is_empty())
+  }
+
   pub fn fetch_data_interval_min(&self) -> u32 {
     self.api_data_fetch_task_interval_min
   }
 
+  /// Retries of the transient API failures, 3 retries starting at 500 ms by default
+  pub fn fetch_retry_policy(&self) -> RetryPolicy {
+    let default = RetryPolicy::default();
+    RetryPolicy {
+      max_retries: self.api_fetch_max_retries.unwrap_or(default.max_retries),
+      backoff: self.api_fetch_backoff_ms.map_or(default.backoff, std::time::Duration::from_millis),
+    }
+  }
+
+  /// Where the participant blocks are placed in the month sheet, `B4:J35` every 10 columns by default
+  pub fn sheet_layout(&self) -> Result<SheetLayout, String> {
+    match (&self.sheet_block_range, self.sheet_block_width) {
+      (Some(range), width) => SheetLayout::from_a1_range(range, width),
+      (None, Some(width)) => {
+        let layout = SheetLayout::default();
+        if width < layout.columns_per_block {
+          return Err(format!("block width {width} is less than the block columns {}", layout.columns_per_block));
+        }
+        Ok(SheetLayout {
+          block_width: width,
+          ..layout
+        })
+      }
+      (None, None) => Ok(SheetLayout::default()),
+    }
+  }
+
+  /// Minimal period between the on-demand /refresh fetches, 60 seconds by default
+  pub fn refresh_cooldown(&self) -> std::time::Duration {
+    std::time::Duration::from_secs(self.refresh_cooldown_sec.unwrap_or(60))
+  }
+
   pub fn spreadsheet_id(&self) -> &str {
     &self.spreadsheet_id
   }
 
+  /// Sheet to read instead of the current month one (e.g. a single all-time sheet)
+  pub fn sheet_title_override(&self) -> Option<&str> {
+    self.sheet_title_override.as_deref().map(str::trim).filter(|t| !t.is_empty())
+  }
+
+  /// Whether the unparsable rows are dropped (and reported in /status) instead of failing the fetch
+  pub fn skip_parse_errors(&self) -> bool {
+    self.skip_parse_errors.unwrap_or(true)
+  }
+
   pub fn api_service_key(&self) -> &str {
     &self.api_service_key_json_data
   }
@@ -39,7 +316,205 @@ impl CongratulatorConfig {
     &self.bot_token
   }
 
-  pub fn notify_chat_id(&self) -> ChatId {
-    ChatId(self.notify_chat_id)
+  /// Offset of the participants' timezone from UTC, hours (UTC+3 by default)
+  pub fn timezone_offset_hours(&self) -> i32 {
+    self.timezone_offset_hours.unwrap_or(helpers::DEFAULT_TIMEZONE_OFFSET_HOURS)
+  }
+
+  /// Language of the bot messages (Russian by default)
+  pub fn default_locale(&self) -> Locale {
+    self.default_locale.unwrap_or_default()
+  }
+
+  /// Daily deadline (participants' local time) by which participants are expected to fill the table
+  pub fn fill_deadline(&self) -> Option<NaiveTime> {
+    self.fill_deadline.as_deref().and_then(|t| helpers::parse_time_hm(t).ok())
+  }
+
+  /// Time (participants' local time) of the daily reminder, 21:00 by default
+  pub fn notify_time(&self) -> NaiveTime {
+    Self::scheduled_time(self.notify_time.as_deref(), 21)
+  }
+
+  /// Time (participants' local time) of the daily summary, 23:00 by default
+  pub fn summary_time(&self) -> NaiveTime {
+    Self::scheduled_time(self.summary_time.as_deref(), 23)
+  }
+
+  fn scheduled_time(time: Option<&str>, default_hour: u32) -> NaiveTime {
+    time
+      .and_then(|t| helpers::parse_time_hm(t).ok())
+      .unwrap_or_else(|| NaiveTime::from_hms_opt(default_hour, 0, 0).unwrap())
+  }
+
+  /// Chats receiving the scheduled notifications, summaries and announcements
+  pub fn notify_chat_ids(&self) -> Vec<ChatId> {
+    parse_chat_ids(&self.notify_chat_id).unwrap_or_default()
+  }
+
+  /// Mapping of alias to canonical command name, both without leading '/'
+  pub fn command_aliases(&self) -> HashMap<String, String> {
+    self
+      .command_aliases
+      .iter()
+      .map(|(alias, canonical)| {
+        (
+          alias.trim_start_matches('/').to_lowercase(),
+          canonical.trim_start_matches('/').to_lowercase(),
+        )
+      })
+      .collect()
+  }
+
+  /// Mapping of participant name to Telegram username mentioned in the reminder
+  pub fn telegram_usernames(&self) -> HashMap<String, String> {
+    self.telegram_usernames.clone()
+  }
+
+  /// Whether the reminder is skipped when everyone has filled the table today (disabled by default)
+  pub fn remind_only_missing(&self) -> bool {
+    self.remind_only_missing.unwrap_or(false)
+  }
+
+  /// Threshold for congratulating a participant, `None` if disabled (enabled with 100% by default)
+  pub fn participant_celebration_percent(&self) -> Option<i32> {
+    self
+      .celebrate_participants
+      .unwrap_or(true)
+      .then(|| self.celebration_percent.unwrap_or(100))
+  }
+
+  /// Threshold of the group average for congratulating everyone, `None` if disabled (default)
+  pub fn group_celebration_percent(&self) -> Option<i32> {
+    self
+      .celebrate_group
+      .unwrap_or(false)
+      .then(|| self.group_celebration_percent.unwrap_or(100))
+  }
+
+  /// Jump of today's percentage between two fetches worth congratulating, `None` if disabled (default)
+  pub fn improvement_delta_percent(&self) -> Option<i32> {
+    self.improvement_delta_percent
+  }
+
+  /// Chats receiving the improvement congratulations, the notified chats by default
+  pub fn improvement_chat_ids(&self) -> Vec<ChatId> {
+    match self.improvement_chat_ids.as_deref().and_then(|ids| parse_chat_ids(ids).ok()) {
+      Some(ids) if !ids.is_empty() => ids,
+      _ => self.notify_chat_ids(),
+    }
+  }
+
+  /// JSON file keeping in-progress dialogues across restarts (in-memory storage if not set)
+  pub fn dialogue_storage_file(&self) -> Option<&str> {
+    self.dialogue_storage_file.as_deref()
+  }
+
+  /// JSON file keeping the last fetched dashboard, used at startup if Sheets is unreachable (no cache if not set)
+  pub fn dashboard_cache_path(&self) -> Option<&str> {
+    self.dashboard_cache_path.as_deref()
+  }
+
+  /// JSON file keeping admin notes attached to the dates (in-memory only if not set)
+  pub fn notes_file(&self) -> Option<&str> {
+    self.notes_file.as_deref()
+  }
+
+  /// JSON file keeping the participants watched by the chats (in-memory only if not set)
+  pub fn watches_file(&self) -> Option<&str> {
+    self.watches_file.as_deref()
+  }
+
+  /// Amount of the dates whose summaries are cached until the next fetch (0 disables the cache)
+  pub fn summary_cache_size(&self) -> usize {
+    self.summary_cache_size.unwrap_or(16)
+  }
+
+  /// Upper limit of the month sheets fetched by /yearoverview
+  pub fn year_overview_months(&self) -> u32 {
+    self.year_overview_months.unwrap_or(12)
+  }
+
+  /// Day total corresponding to 100%
+  pub fn daily_max_score(&self) -> Option<f64> {
+    self.daily_max_score.filter(|max| *max > 0.)
+  }
+
+  /// Whether the evening summary and the reminder are sent as one message (disabled by default)
+  pub fn combined_evening_message(&self) -> bool {
+    self.combined_evening_message.unwrap_or(false)
+  }
+
+  /// Whether the yesterday summary on the 1st of the month is built from the previous month sheet (enabled by default)
+  pub fn yesterday_from_previous_month(&self) -> bool {
+    self.yesterday_from_previous_month.unwrap_or(true)
+  }
+
+  /// Whether the /monthtable rows stay numbered only, without the legend mapping them to names, and inline mode
+  /// shows the user's own participant (see `telegram_usernames`) only. Enabled by default.
+  pub fn anonymize_month_table(&self) -> bool {
+    self.anonymize_month_table.unwrap_or(true)
+  }
+
+  /// Chats (or users, for private chats) permitted to run admin commands
+  pub fn admin_chat_ids(&self) -> Vec<ChatId> {
+    self
+      .admin_chat_ids
+      .as_deref()
+      .and_then(|ids| parse_chat_ids(ids).ok())
+      .unwrap_or_default()
+  }
+
+  /// Chats permitted to run the data commands along with the notified and admin ones, `None` means every chat
+  pub fn allowed_chat_ids(&self) -> Option<Vec<ChatId>> {
+    let ids = parse_chat_ids(self.allowed_chat_ids.as_deref()?).ok()?;
+    Some(ids.into_iter().chain(self.notify_chat_ids()).chain(self.admin_chat_ids()).collect())
+  }
+
+  /// Broadcast to more chats than this requires an explicit confirmation
+  pub fn broadcast_confirm_threshold(&self) -> usize {
+    self.broadcast_confirm_threshold.unwrap_or(5)
+  }
+
+  /// Config holding the mandatory fields only, with the `extra` ones set on top
+  #[cfg(test)]
+  pub fn for_tests(extra: serde_json::Value) -> CongratulatorConfig {
+    let mut fields = serde_json::json!({
+      "bot_token": "token",
+      "spreadsheet_id": "spreadsheet",
+      "notify_chat_id": "1",
+      "api_service_key_json_data": "{}",
+      "api_data_fetch_task_interval_min": 5,
+    });
+    if let (Some(fields), serde_json::Value::Object(extra)) = (fields.as_object_mut(), extra) {
+      fields.extend(extra);
+    }
+    serde_json::from_value(fields).expect("valid test config")
+  }
+}
+
+/// Parses comma-separated list of chat ids, e.g. "123,-456"
+fn parse_chat_ids(ids: &str) -> Result<Vec<ChatId>, std::num::ParseIntError> {
+  ids
+    .split(',')
+    .map(str::trim)
+    .filter(|id| !id.is_empty())
+    .map(|id| id.parse().map(ChatId))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn celebration_thresholds_are_within_percent_range() {
+    assert!(CongratulatorConfig::for_tests(json!({ "celebration_percent": 100 })).validate().is_ok());
+    for field in ["celebration_percent", "group_celebration_percent"] {
+      assert!(CongratulatorConfig::for_tests(json!({ field: 150 })).validate().is_err(), "{field}");
+      assert!(CongratulatorConfig::for_tests(json!({ field: -1 })).validate().is_err(), "{field}");
+    }
   }
 }
@@ -0,0 +1,31 @@
+use std::{
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+/// Lets an action run at most once per period
+pub struct Cooldown {
+  period: Duration,
+  last_run: Mutex<Option<Instant>>,
+}
+
+impl Cooldown {
+  pub fn new(period: Duration) -> Self {
+    Self {
+      period,
+      last_run: Mutex::new(None),
+    }
+  }
+
+  /// Marks the action as started if the period has elapsed, otherwise returns the time left
+  pub fn try_start(&self) -> Result<(), Duration> {
+    let mut last_run = self.last_run.lock().unwrap();
+    if let Some(elapsed) = last_run.map(|t| t.elapsed()) {
+      if elapsed < self.period {
+        return Err(self.period - elapsed);
+      }
+    }
+    *last_run = Some(Instant::now());
+    Ok(())
+  }
+}
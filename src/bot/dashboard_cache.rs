@@ -0,0 +1,111 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicU64, Ordering},
+};
+
+use log::debug;
+use tokio::sync::Mutex;
+
+use crate::dashboard::Dashboard;
+
+use super::storage::{self, JsonFileStorageError};
+
+/// Serialized dashboard waiting to be written to the cache file
+pub struct CacheSnapshot {
+  sequence: u64,
+  content: Vec<u8>,
+}
+
+/// On-disk copy of the last fetched dashboard, so the bot can start from it when Sheets is unreachable.
+/// Written both by the fetch hook and on shutdown, the snapshots reach the file in the order they were taken.
+pub struct DashboardCache {
+  path: PathBuf,
+  /// Sequence number of the last taken snapshot
+  taken: AtomicU64,
+  /// Sequence number of the snapshot in the file, held across the write so the writes don't overlap
+  written: Mutex<u64>,
+}
+
+impl DashboardCache {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self {
+      path: path.into(),
+      taken: AtomicU64::new(0),
+      written: Mutex::new(0),
+    }
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Serializes the dashboard, cheap enough to be done under the dashboard lock
+  pub fn snapshot(&self, dashboard: &Dashboard) -> Result<CacheSnapshot, JsonFileStorageError> {
+    Ok(CacheSnapshot {
+      sequence: self.taken.fetch_add(1, Ordering::SeqCst) + 1,
+      content: serde_json::to_vec(dashboard)?,
+    })
+  }
+
+  /// Returns `false` if a later snapshot is already in the file, so this one is dropped
+  pub async fn write(&self, snapshot: CacheSnapshot) -> Result<bool, JsonFileStorageError> {
+    let mut written = self.written.lock().await;
+    if *written > snapshot.sequence {
+      debug!("[DashboardCache] Snapshot #{} is dropped, #{} is already written", snapshot.sequence, *written);
+      return Ok(false);
+    }
+    storage::write_atomically(&self.path, &snapshot.content).await?;
+    *written = snapshot.sequence;
+    Ok(true)
+  }
+
+  pub async fn save(&self, dashboard: &Dashboard) -> Result<bool, JsonFileStorageError> {
+    self.write(self.snapshot(dashboard)?).await
+  }
+
+  /// Cached dashboard of the sheet with the given title, `None` if the cache holds another sheet
+  pub async fn load(&self, sheet_title: &str) -> Result<Option<Dashboard>, JsonFileStorageError> {
+    let dashboard: Dashboard = storage::read_json(&self.path).await?;
+    if dashboard.sheet_title() != Some(sheet_title) {
+      debug!(
+        "[DashboardCache] Cache holds the sheet {:?} instead of '{}'",
+        dashboard.sheet_title(),
+        sheet_title
+      );
+      return Ok(None);
+    }
+    Ok(Some(dashboard))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::storage::tests::temp_path;
+
+  fn dashboard(title: &str) -> Dashboard {
+    let mut dashboard = Dashboard::from(Vec::new());
+    dashboard.set_sheet_title(title.to_string());
+    dashboard
+  }
+
+  #[tokio::test]
+  async fn cache_of_another_sheet_is_rejected() {
+    let cache = DashboardCache::new(temp_path("dashboard-cache-title"));
+    assert!(cache.save(&dashboard("Январь 24")).await.unwrap());
+
+    assert!(cache.load("Январь 24").await.unwrap().is_some());
+    assert!(cache.load("Февраль 24").await.unwrap().is_none());
+  }
+
+  #[tokio::test]
+  async fn older_snapshot_does_not_overwrite_newer_one() {
+    let cache = DashboardCache::new(temp_path("dashboard-cache-order"));
+    let older = cache.snapshot(&dashboard("Январь 24")).unwrap();
+    let newer = cache.snapshot(&dashboard("Февраль 24")).unwrap();
+
+    assert!(cache.write(newer).await.unwrap());
+    assert!(!cache.write(older).await.unwrap());
+    assert!(cache.load("Февраль 24").await.unwrap().is_some());
+  }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::api;
+use crate::{api, bot::storage::JsonFileStorageError};
 
 #[derive(Error, Debug)]
 pub enum CongratulatorError {
@@ -10,6 +10,8 @@ pub enum CongratulatorError {
   TeloxideRequestError(#[from] teloxide::RequestError),
   #[error(transparent)]
   ConfigError(#[from] config::ConfigError),
+  #[error(transparent)]
+  DialogueStorageError(#[from] JsonFileStorageError),
   #[error("Empty (None) callback data received")]
   EmptyCallbackData,
   #[error("Dashboard is empty")]
@@ -0,0 +1,98 @@
+use std::{
+  convert::Infallible,
+  net::SocketAddr,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use hyper::{
+  service::{make_service_fn, service_fn},
+  Body, Request, Response, Server,
+};
+use serde_json::{json, Value};
+use teloxide::Bot;
+use url::Url;
+
+type Calls = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Local Bot API answering every request with a success, the called methods are recorded
+pub struct FakeTelegram {
+  address: SocketAddr,
+  calls: Calls,
+  reject_markdown: Arc<AtomicBool>,
+}
+
+impl FakeTelegram {
+  pub fn start() -> Self {
+    let calls = Calls::default();
+    let reject_markdown = Arc::new(AtomicBool::new(false));
+    let (recorded, rejecting) = (calls.clone(), reject_markdown.clone());
+    let make_service = make_service_fn(move |_| {
+      let (calls, reject) = (recorded.clone(), rejecting.clone());
+      let service = service_fn(move |req| FakeTelegram::handle(calls.clone(), reject.clone(), req));
+      async move { Ok::<_, Infallible>(service) }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let address = server.local_addr();
+    tokio::spawn(server);
+    Self {
+      address,
+      calls,
+      reject_markdown,
+    }
+  }
+
+  /// Every following MarkdownV2 message is answered with the entities parsing error
+  pub fn reject_markdown(&self) {
+    self.reject_markdown.store(true, Ordering::Relaxed);
+  }
+
+  pub fn bot(&self) -> Bot {
+    Bot::new("test-token").set_api_url(Url::parse(&format!("http://{}", self.address)).unwrap())
+  }
+
+  /// Methods called since the previous take along with their payloads
+  pub fn take_calls(&self) -> Vec<(String, Value)> {
+    self.calls.lock().unwrap().drain(..).collect()
+  }
+
+  /// Names of the methods called since the previous take, e.g. `SendMessage`
+  pub fn take_methods(&self) -> Vec<String> {
+    self.take_calls().into_iter().map(|(method, _)| method).collect()
+  }
+
+  /// Texts of the messages sent since the previous take
+  pub fn take_texts(&self) -> Vec<String> {
+    let calls = self.take_calls();
+    calls.into_iter().filter_map(|(_, payload)| payload["text"].as_str().map(String::from)).collect()
+  }
+
+  async fn handle(
+    calls: Calls,
+    reject_markdown: Arc<AtomicBool>,
+    req: Request<Body>,
+  ) -> Result<Response<Body>, Infallible> {
+    let method = req.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+    let payload: Value = serde_json::from_slice(&body).unwrap_or_default();
+    let markdown = payload["parse_mode"] == "MarkdownV2";
+    if markdown && reject_markdown.load(Ordering::Relaxed) {
+      calls.lock().unwrap().push((method, payload));
+      let error = json!({ "ok": false, "error_code": 400, "description": "Bad Request: can't parse entities" });
+      return Ok(Response::new(Body::from(error.to_string())));
+    }
+    let result = match method.as_str() {
+      "SendMessage" | "SendDocument" | "SendPhoto" | "EditMessageText" => json!({
+        "message_id": 1,
+        "date": 0,
+        "chat": { "id": payload["chat_id"], "type": "private", "first_name": "Анна" },
+        "text": payload["text"],
+      }),
+      _ => json!(true),
+    };
+    calls.lock().unwrap().push((method, payload));
+    Ok(Response::new(Body::from(json!({ "ok": true, "result": result }).to_string())))
+  }
+}
@@ -0,0 +1,101 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+
+use hyper::{
+  service::{make_service_fn, service_fn},
+  Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{debug, error, info};
+use serde::Serialize;
+
+use crate::helpers;
+
+use super::{tasks::LastFetch, LockedDashboard};
+
+/// Path answered by the health-check server, any other one is 404
+const HEALTH_PATH: &str = "/health";
+
+/// Body of the health-check response, e.g.
+/// `{"ready":true,"last_fetch":"2024-01-31T20:00:00.123Z","participants":12}`.
+/// `last_fetch` is `null` until the first successful fetch.
+#[derive(Serialize)]
+struct HealthReport {
+  ready: bool,
+  last_fetch: Option<String>,
+  participants: usize,
+}
+
+/// Readiness probe for the deployments: 200 when the dashboard is initialized
+/// and the last successful fetch is not older than `staleness`, otherwise 503
+#[derive(Clone)]
+pub struct HealthCheck {
+  dashboard: Arc<LockedDashboard>,
+  last_fetch: LastFetch,
+  staleness: Duration,
+}
+
+impl HealthCheck {
+  pub fn new(dashboard: Arc<LockedDashboard>, last_fetch: LastFetch, staleness: Duration) -> Self {
+    Self {
+      dashboard,
+      last_fetch,
+      staleness,
+    }
+  }
+
+  /// Spawns the server listening on all interfaces at `port`
+  pub fn spawn(self, port: u16) {
+    let address = SocketAddr::from(([0, 0, 0, 0], port));
+    tokio::spawn(async move {
+      let make_service = make_service_fn(move |_| {
+        let health = self.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| health.clone().handle(req))) }
+      });
+      let server = match Server::try_bind(&address) {
+        Ok(builder) => builder.serve(make_service),
+        Err(err) => {
+          error!("[HealthCheck] Unable to bind {}: {}", address, err);
+          return;
+        }
+      };
+      info!("[HealthCheck] Listening on {}{}", address, HEALTH_PATH);
+      if let Err(err) = server.await {
+        error!("[HealthCheck] Server has stopped: {}", err);
+      }
+    });
+  }
+
+  async fn handle(self, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != HEALTH_PATH {
+      return Ok(Self::response(StatusCode::NOT_FOUND, Body::empty()));
+    }
+    let report = self.report().await;
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    debug!("[HealthCheck] Responding {} to the probe", status);
+    let body = serde_json::to_string(&report).unwrap_or_default();
+    Ok(Self::response(status, Body::from(body)))
+  }
+
+  async fn report(&self) -> HealthReport {
+    let (initialized, participants) = {
+      let dashboard = self.dashboard.read().await;
+      (
+        dashboard.tables().is_some(),
+        dashboard.participants().map_or(0, |persons| persons.len()),
+      )
+    };
+    let last_fetch = *self.last_fetch.read().await;
+    let now = helpers::current_time_utc();
+    let fresh = last_fetch.is_some_and(|at| (now - at).to_std().unwrap_or_default() <= self.staleness);
+    HealthReport {
+      ready: initialized && fresh,
+      last_fetch: last_fetch.map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+      participants,
+    }
+  }
+
+  fn response(status: StatusCode, body: Body) -> Response<Body> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+  }
+}
@@ -0,0 +1,479 @@
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use chrono::{NaiveDate, NaiveTime};
+use log::{debug, error, info};
+use teloxide::types::{ChatId, MessageId};
+
+use crate::{
+  dashboard::{diff::DashboardDiff, score_table::entities::DisplayRounding, Dashboard},
+  helpers::{self, current_time_local},
+};
+
+use super::{dashboard_cache::DashboardCache, watches::Watches};
+
+/// MarkdownV2 message produced by a hook
+#[derive(Debug)]
+pub struct Outgoing {
+  pub chat_id: ChatId,
+  pub text: String,
+  /// Already sent message to be edited instead of sending a new one
+  pub edit: Option<MessageId>,
+}
+
+/// Reacts on every dashboard replacement done by the data fetcher.
+/// Called under the dashboard WRITE lock, so it should only compute messages, the fetcher sends them afterwards.
+pub trait FetchHook: Sync + Send {
+  fn name(&self) -> &str;
+  fn on_update(&self, previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing>;
+
+  /// Called when the chat has blocked (or kicked) the bot, so the hook can stop producing messages for it
+  fn on_unreachable(&self, _chat_id: ChatId) {}
+}
+
+/// Congratulates participants (and optionally the whole group) who crossed the celebration threshold today
+pub struct CelebrationHook {
+  chat_ids: Vec<ChatId>,
+  participant_threshold: Option<i32>,
+  group_threshold: Option<i32>,
+}
+
+impl CelebrationHook {
+  /// `None` threshold disables the corresponding celebration type
+  pub fn new(chat_ids: Vec<ChatId>, participant_threshold: Option<i32>, group_threshold: Option<i32>) -> Self {
+    Self {
+      chat_ids,
+      participant_threshold,
+      group_threshold,
+    }
+  }
+
+  fn crossed(previous: Option<f64>, latest: f64, threshold: i32) -> bool {
+    let threshold = threshold as f64;
+    previous.is_none_or(|p| p < threshold) && latest >= threshold
+  }
+}
+
+impl FetchHook for CelebrationHook {
+  fn name(&self) -> &str {
+    "CelebrationHook"
+  }
+
+  fn on_update(&self, previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing> {
+    let today = current_time_local().date_naive();
+    let mut texts = Vec::new();
+
+    if let Some(threshold) = self.participant_threshold {
+      let diff = DashboardDiff::between(previous, latest);
+      for change in diff.changes_on(&today) {
+        let previous_value = change.previous.map(|p| p.value() as f64);
+        if CelebrationHook::crossed(previous_value, change.latest.value() as f64, threshold) {
+          debug!("[{}] {:?} crossed {}%", self.name(), change.person, threshold);
+          texts.push(format!(
+            "🎉 *{}* преодолел\\(а\\) отметку {}% и набрал\\(а\\) {} {}",
+            helpers::escape_markdown_v2(&change.person.display_name()),
+            threshold,
+            helpers::escape_markdown_v2(&change.latest.to_string()),
+            change.latest.emoji()
+          ));
+        }
+      }
+    }
+
+    if let (Some(threshold), Some(latest_avg)) = (self.group_threshold, latest.average_percent(&today)) {
+      if CelebrationHook::crossed(previous.average_percent(&today), latest_avg, threshold) {
+        debug!("[{}] Group average {:.1} crossed {}%", self.name(), latest_avg, threshold);
+        texts.push(format!(
+          "🥳 Средний результат группы сегодня превысил {}% и составляет *{}%*",
+          threshold,
+          helpers::escape_markdown_v2(&DisplayRounding::percent(latest_avg))
+        ));
+      }
+    }
+
+    texts
+      .iter()
+      .flat_map(|text| {
+        self.chat_ids.iter().map(|&chat_id| Outgoing {
+          chat_id,
+          text: text.clone(),
+          edit: None,
+        })
+      })
+      .collect()
+  }
+}
+
+/// Congratulates participants whose today's percentage jumped by more than the threshold since the previous fetch,
+/// at most once a day per participant
+pub struct ImprovementHook {
+  chat_ids: Vec<ChatId>,
+  threshold: i32,
+  /// Participants already congratulated, with the day of the congratulation
+  notified: Mutex<HashMap<String, NaiveDate>>,
+}
+
+impl ImprovementHook {
+  pub fn new(chat_ids: Vec<ChatId>, threshold: i32) -> Self {
+    Self {
+      chat_ids,
+      threshold,
+      notified: Mutex::new(HashMap::new()),
+    }
+  }
+}
+
+impl FetchHook for ImprovementHook {
+  fn name(&self) -> &str {
+    "ImprovementHook"
+  }
+
+  fn on_update(&self, previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing> {
+    let today = current_time_local().date_naive();
+    let mut notified = self.notified.lock().unwrap();
+    notified.retain(|_, date| *date == today);
+
+    let diff = DashboardDiff::between(previous, latest);
+    let mut texts = Vec::new();
+    for change in diff.changes_on(&today) {
+      let Some(previous) = change.previous else {
+        continue;
+      };
+      let delta = change.latest.value() - previous.value();
+      if delta <= self.threshold || notified.contains_key(change.person.name()) {
+        continue;
+      }
+      debug!("[{}] {:?} improved by {}%", self.name(), change.person, delta);
+      notified.insert(change.person.name().to_string(), today);
+      texts.push(format!(
+        "🚀 *{}* улучшил\\(а\\) сегодняшний результат на {}% и теперь набирает {} {}",
+        helpers::escape_markdown_v2(&change.person.display_name()),
+        delta,
+        helpers::escape_markdown_v2(&change.latest.to_string()),
+        change.latest.emoji()
+      ));
+    }
+
+    texts
+      .iter()
+      .flat_map(|text| {
+        self.chat_ids.iter().map(|&chat_id| Outgoing {
+          chat_id,
+          text: text.clone(),
+          edit: None,
+        })
+      })
+      .collect()
+  }
+}
+
+/// Standings messages kept up to date per chat, with their last rendered text
+#[derive(Default)]
+pub struct StandingsBoards {
+  boards: Mutex<HashMap<ChatId, (MessageId, String)>>,
+}
+
+impl StandingsBoards {
+  /// Replaces the chat's board if there was one
+  pub fn register(&self, chat_id: ChatId, message_id: MessageId, text: String) {
+    self.boards.lock().unwrap().insert(chat_id, (message_id, text));
+  }
+
+  /// Returns `false` if the chat had no board
+  pub fn remove(&self, chat_id: ChatId) -> bool {
+    self.boards.lock().unwrap().remove(&chat_id).is_some()
+  }
+}
+
+/// Edits the registered standings messages once the standings text changes
+pub struct StandingsHook {
+  boards: Arc<StandingsBoards>,
+}
+
+impl StandingsHook {
+  pub fn new(boards: Arc<StandingsBoards>) -> Self {
+    Self { boards }
+  }
+}
+
+impl FetchHook for StandingsHook {
+  fn name(&self) -> &str {
+    "StandingsHook"
+  }
+
+  fn on_unreachable(&self, chat_id: ChatId) {
+    if self.boards.remove(chat_id) {
+      info!("[{}] Standings board of chat_id={} is unregistered since the chat is unreachable", self.name(), chat_id);
+    }
+  }
+
+  fn on_update(&self, _previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing> {
+    let mut boards = self.boards.boards.lock().unwrap();
+    if boards.is_empty() {
+      return Vec::new();
+    }
+    let now = current_time_local();
+    let standings = latest.standings(&now.date_naive());
+    let text = helpers::format_standings_msg(&standings, &now);
+    boards
+      .iter_mut()
+      // The update time is the only difference for unchanged standings, it's not worth an edit
+      .filter(|(_, (_, last_text))| strip_update_time(last_text) != strip_update_time(&text))
+      .map(|(chat_id, (message_id, last_text))| {
+        debug!("[{}] Standings for chat_id={} have changed", self.name(), chat_id);
+        *last_text = text.clone();
+        Outgoing {
+          chat_id: *chat_id,
+          text: text.clone(),
+          edit: Some(*message_id),
+        }
+      })
+      .collect()
+  }
+}
+
+fn strip_update_time(text: &str) -> &str {
+  text.rsplit_once("\n\n").map_or(text, |(standings, _)| standings)
+}
+
+/// Notifies the watchers once the watched participant fills today's record,
+/// and once per day if the participant hasn't filled it by the deadline
+pub struct WatchHook {
+  watches: Arc<Watches>,
+  deadline: Option<NaiveTime>,
+  last_deadline_check: Mutex<Option<NaiveDate>>,
+}
+
+impl WatchHook {
+  /// `None` deadline disables the missed notifications
+  pub fn new(watches: Arc<Watches>, deadline: Option<NaiveTime>) -> Self {
+    Self {
+      watches,
+      deadline,
+      last_deadline_check: Mutex::new(None),
+    }
+  }
+
+  /// Whether the deadline check is due at `now`, it's done by the first fetch after the deadline
+  fn deadline_check_due(&self, now: &chrono::NaiveDateTime) -> bool {
+    let Some(deadline) = self.deadline else {
+      return false;
+    };
+    let mut last_check = self.last_deadline_check.lock().unwrap();
+    if now.time() < deadline || *last_check == Some(now.date()) {
+      return false;
+    }
+    *last_check = Some(now.date());
+    true
+  }
+
+  fn notify_watchers(&self, name: &str, text: &str, messages: &mut Vec<Outgoing>) {
+    for chat_id in self.watches.watchers_of(name) {
+      messages.push(Outgoing {
+        chat_id,
+        text: text.to_string(),
+        edit: None,
+      });
+    }
+  }
+}
+
+impl FetchHook for WatchHook {
+  fn name(&self) -> &str {
+    "WatchHook"
+  }
+
+  fn on_unreachable(&self, chat_id: ChatId) {
+    if self.watches.remove_chat(chat_id) {
+      info!("[{}] Watches of chat_id={} are removed since the chat is unreachable", self.name(), chat_id);
+    }
+  }
+
+  fn on_update(&self, previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing> {
+    let now = current_time_local().naive_utc();
+    let today = now.date();
+    let mut messages = Vec::new();
+
+    // Only the records filled for the first time are announced, not the later corrections
+    let diff = DashboardDiff::between(previous, latest);
+    for change in diff.changes_on(&today).filter(|c| c.previous.is_none()) {
+      debug!("[{}] {:?} has filled today's record", self.name(), change.person);
+      let text = format!(
+        "👀 *{}* заполнил\\(а\\) таблицу: {} {}",
+        helpers::escape_markdown_v2(&change.person.display_name()),
+        helpers::escape_markdown_v2(&change.latest.to_string()),
+        change.latest.emoji()
+      );
+      self.notify_watchers(change.person.name(), &text, &mut messages);
+    }
+
+    if self.deadline_check_due(&now) {
+      for person in latest.missed(&today).unwrap_or_default() {
+        debug!("[{}] {:?} has missed the deadline", self.name(), person);
+        let text = format!(
+          "⏰ *{}* не заполнил\\(а\\) таблицу к дедлайну",
+          helpers::escape_markdown_v2(&person.display_name())
+        );
+        self.notify_watchers(person.name(), &text, &mut messages);
+      }
+    }
+    messages
+  }
+}
+
+/// Writes every freshly fetched dashboard to the cache file, so the bot can start from it when Sheets is unreachable
+pub struct DashboardCacheHook {
+  cache: Arc<DashboardCache>,
+}
+
+impl DashboardCacheHook {
+  pub fn new(cache: Arc<DashboardCache>) -> Self {
+    Self { cache }
+  }
+}
+
+impl FetchHook for DashboardCacheHook {
+  fn name(&self) -> &str {
+    "DashboardCacheHook"
+  }
+
+  fn on_update(&self, _previous: &Dashboard, latest: &Dashboard) -> Vec<Outgoing> {
+    // Serialized under the lock, the file is written in background
+    match self.cache.snapshot(latest) {
+      Ok(snapshot) => {
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+          match cache.write(snapshot).await {
+            Ok(_) => debug!("[DashboardCacheHook] Dashboard is cached to {:?}", cache.path()),
+            Err(err) => error!("[DashboardCacheHook] Unable to write the dashboard cache {:?}: {}", cache.path(), err),
+          }
+        });
+      }
+      Err(err) => error!("[{}] Unable to serialize the dashboard: {}", self.name(), err),
+    }
+    Vec::new()
+  }
+}
+
+/// Counts the dashboard updates, reported on shutdown
+#[derive(Default)]
+pub struct FetchCounter {
+  count: AtomicUsize,
+}
+
+impl FetchCounter {
+  pub fn count(&self) -> usize {
+    self.count.load(Ordering::Relaxed)
+  }
+}
+
+impl FetchHook for FetchCounter {
+  fn name(&self) -> &str {
+    "FetchCounter"
+  }
+
+  fn on_update(&self, _previous: &Dashboard, _latest: &Dashboard) -> Vec<Outgoing> {
+    self.count.fetch_add(1, Ordering::Relaxed);
+    Vec::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+
+  use super::*;
+  use crate::{
+    dashboard::fake::{date, empty_record, record, table},
+    helpers::clock::{set_clock, FixedClock},
+  };
+
+  /// 15:00 of 2024-01-15 in the default timezone
+  fn pin_clock() -> NaiveDate {
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap())));
+    date(2024, 1, 15)
+  }
+
+  #[test]
+  fn participant_is_celebrated_when_crossing_the_threshold() {
+    let today = pin_clock();
+    let hook = CelebrationHook::new(vec![ChatId(1), ChatId(2)], Some(80), None);
+    let dashboard = |percent| Dashboard::from(vec![table("Анна", vec![record(today, percent)])]);
+
+    let messages = hook.on_update(&dashboard(70), &dashboard(80));
+    assert_eq!(messages.iter().map(|m| m.chat_id).collect::<Vec<_>>(), [ChatId(1), ChatId(2)]);
+    assert!(messages[0].text.contains("Анна") && messages[0].text.contains("80%"));
+    // First fill of the day above the threshold is a crossing as well
+    let empty = Dashboard::from(vec![table("Анна", vec![empty_record(today)])]);
+    assert_eq!(hook.on_update(&empty, &dashboard(90)).len(), 2);
+
+    assert!(hook.on_update(&dashboard(85), &dashboard(95)).is_empty(), "already above the threshold");
+    assert!(hook.on_update(&dashboard(60), &dashboard(79)).is_empty(), "below the threshold");
+  }
+
+  #[test]
+  fn group_is_celebrated_when_the_average_crosses_the_threshold() {
+    let today = pin_clock();
+    let hook = CelebrationHook::new(vec![ChatId(1)], None, Some(60));
+    let dashboard = |anna, boris| {
+      Dashboard::from(vec![table("Анна", vec![record(today, anna)]), table("Борис", vec![record(today, boris)])])
+    };
+
+    let messages = hook.on_update(&dashboard(50, 50), &dashboard(70, 60));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].text, "🥳 Средний результат группы сегодня превысил 60% и составляет *65\\.0%*");
+    assert!(hook.on_update(&dashboard(70, 60), &dashboard(80, 60)).is_empty());
+    assert!(hook.on_update(&dashboard(50, 50), &dashboard(60, 50)).is_empty());
+  }
+
+  async fn watch_hook(deadline: Option<NaiveTime>) -> WatchHook {
+    let watches = Arc::new(Watches::load(None).await.unwrap());
+    watches.add(ChatId(1), "Анна").await.unwrap();
+    watches.add(ChatId(1), "Борис").await.unwrap();
+    WatchHook::new(watches, deadline)
+  }
+
+  #[tokio::test]
+  async fn watch_hook_announces_first_fill_only() {
+    let today = pin_clock();
+    let hook = watch_hook(None).await;
+    let empty = Dashboard::from(vec![table("Анна", vec![empty_record(today)])]);
+    let filled = Dashboard::from(vec![table("Анна", vec![record(today, 60)])]);
+    let corrected = Dashboard::from(vec![table("Анна", vec![record(today, 70)])]);
+
+    let messages = hook.on_update(&empty, &filled);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].chat_id, ChatId(1));
+    assert!(messages[0].text.contains("Анна"));
+    assert!(hook.on_update(&filled, &corrected).is_empty());
+  }
+
+  #[tokio::test]
+  async fn watch_hook_reports_missed_deadline_once_a_day() {
+    let today = pin_clock();
+    let hook = watch_hook(NaiveTime::from_hms_opt(14, 0, 0)).await;
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(today, 60)]),
+      table("Борис", vec![empty_record(today)]),
+    ]);
+
+    let messages = hook.on_update(&dashboard, &dashboard);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].text.contains("Борис"));
+    assert!(hook.on_update(&dashboard, &dashboard).is_empty());
+  }
+
+  #[tokio::test]
+  async fn watch_hook_skips_deadline_before_it_comes() {
+    let today = pin_clock();
+    let hook = watch_hook(NaiveTime::from_hms_opt(16, 0, 0)).await;
+    let dashboard = Dashboard::from(vec![table("Борис", vec![empty_record(today)])]);
+
+    assert!(hook.on_update(&dashboard, &dashboard).is_empty());
+  }
+}
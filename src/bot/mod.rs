@@ -1,36 +1,88 @@
+pub mod broadcast;
+pub mod cache;
 pub mod config;
+pub mod cooldown;
+pub mod dashboard_cache;
+pub mod hooks;
 pub mod error;
+#[cfg(test)]
+pub mod fake;
+pub mod health;
+pub mod notes;
+pub mod pause;
+pub mod storage;
 pub mod tasks;
+pub mod watches;
 
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
 use itertools::free::join;
 use log::{debug, error, info, trace, warn};
-use std::sync::Arc;
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 use teloxide::{
   dispatching::{
-    dialogue::{self, InMemStorage},
+    dialogue::{self, ErasedStorage, InMemStorage, Storage},
     DefaultKey, UpdateFilterExt, UpdateHandler,
   },
+  update_listeners::webhooks,
   prelude::*,
   types::ParseMode,
-  types::{InlineKeyboardButton, InlineKeyboardMarkup},
+  types::{
+    InlineKeyboardButton, InlineKeyboardMarkup, InlineQueryResult, InlineQueryResultArticle, InputFile, InputMessageContent,
+    InputMessageContentText, MediaKind, MessageCommon, MessageKind,
+  },
   utils::command::BotCommands,
 };
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::{
   api::AsyncSheetsHub,
-  bot::{error::CongratulatorError as Error, tasks::TaskManager},
-  dashboard::{Dashboard, DashboardError},
-  helpers::{self, current_time_utc_msk, PeriodicTimeUtc},
+  bot::{
+    cache::SummaryCache,
+    cooldown::Cooldown,
+    dashboard_cache::DashboardCache,
+    error::CongratulatorError as Error,
+    health::HealthCheck,
+    hooks::{
+      CelebrationHook, DashboardCacheHook, FetchCounter, FetchHook, ImprovementHook, StandingsBoards, StandingsHook,
+      WatchHook,
+    },
+    notes::DayNotes,
+    pause::PauseSwitch,
+    storage::JsonFileStorage,
+    tasks::TaskManager,
+    watches::Watches,
+  },
+  dashboard::{
+    analyzer::{participant_totals, yearly_averages},
+    chart,
+    score_table::entities::{Category, Person, Scores},
+    Dashboard, DashboardError,
+  },
+  helpers::{self, current_time_local, i18n, PeriodicTimeUtc},
 };
 
 use self::config::CongratulatorConfig;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub enum State {
   #[default]
   Default,
+  ConfirmBroadcast {
+    text: String,
+  },
+  /// The first participant of /comparetwo is selected, waiting for the second one
+  AwaitingSecondPerson {
+    first: String,
+  },
+  /// The participant is selected, waiting for the date of the record to show
+  AwaitingDate {
+    person: String,
+  },
 }
 
 #[derive(BotCommands, Clone, Debug)]
@@ -40,6 +92,8 @@ enum Command {
   Help,
   #[command(description = "just roll the dice")]
   Dice,
+  #[command(description = "pick the winner of the day, the higher today's percentage the higher the chance")]
+  PickWinner,
   #[command(description = "show list of participants")]
   Participants,
   #[command(description = "show scores of a participant")]
@@ -50,9 +104,172 @@ enum Command {
   YesterdaySummary,
   #[command(description = "show enabled notifications")]
   EnabledNotifications,
+  #[command(description = "show the schedule of every periodic task")]
+  Schedule,
+  #[command(description = "list participants who haven't filled the table today")]
+  Missing,
+  #[command(description = "show time left until the fill deadline")]
+  Deadline,
+  #[command(description = "show daily ranks of a participant over the week")]
+  RankHistory(String),
+  #[command(description = "estimate days needed to reach a target average: <name> <target%> [daily%]")]
+  ReachGoal(String),
+  #[command(description = "show the group's participation rate over the last two weeks")]
+  Participation,
+  #[command(description = "show top improvers of the week")]
+  Improvers,
+  #[command(description = "show group's per-category totals for today")]
+  GroupCategories,
+  #[command(description = "show who leads every category today")]
+  CategoryLeaders,
+  #[command(description = "show current time and what the bot treats as today")]
+  Now,
+  #[command(description = "preview today's percentage with hypothetical scores: /whatif <name> sport=5 ...")]
+  WhatIf(String),
+  #[command(description = "rank participants by amount of filled days this month")]
+  MostConsistent,
+  #[command(description = "show month-to-date averages and whether the latest day is above them")]
+  Trends,
+  #[command(description = "show year-to-date average of everyone with the monthly ones")]
+  YearOverview,
+  #[command(description = "rank participants by percentage for the date (today by default): /leaderboard [ДД.ММ.ГГГГ]")]
+  Leaderboard(String),
+  #[command(description = "show participant's aggregates for the month: /personstats <name>")]
+  PersonStats(String),
+  #[command(description = "show participant's scores on the date typed after the selection")]
+  ScoreOn,
+  #[command(description = "compare the latest filled records of two participants")]
+  CompareTwo,
+  #[command(description = "export the current month data as JSON document")]
+  ExportJson,
+  #[command(description = "fetch the latest data from the table right away")]
+  Refresh,
+  #[command(description = "list participants' current streaks of consecutive filled days")]
+  Streaks,
+  #[command(description = "list participants who beat their personal best today")]
+  Records,
+  #[command(description = "render the month as a participants by days heatmap")]
+  MonthTable,
+  #[command(description = "get notified when the participant fills the table or misses the deadline: /watch <name>")]
+  Watch(String),
+  #[command(description = "stop watching the participant: /unwatch <name>")]
+  Unwatch(String),
+}
+
+#[derive(BotCommands, Clone, Debug)]
+#[command(rename_rule = "lowercase", description = "These admin commands are supported:")]
+enum AdminCommand {
+  #[command(description = "send an announcement to all notify chats")]
+  Broadcast(String),
+  #[command(description = "show performance numbers of the last dashboard fetch")]
+  Perf,
+  #[command(description = "export participant's data for the whole year as CSV")]
+  Year(String),
+  #[command(description = "show the sheet layout expected by the bot")]
+  Layout,
+  #[command(description = "attach a note to the date: /note DD.MM.YYYY <text|clear>")]
+  Note(String),
+  #[command(description = "post auto-updating month standings in this chat, /standingsboard off to stop")]
+  StandingsBoard(String),
+  #[command(description = "show filled days and average percentage of everyone over the year")]
+  AllTime,
+  #[command(description = "compare sheet totals with computed ones, /verify all to list everyone")]
+  Verify(String),
+  #[command(description = "suppress all scheduled sends for a while, e.g. /pause 2h")]
+  Pause(String),
+  #[command(description = "resume scheduled sends right away")]
+  Resume,
+  #[command(description = "show the bot status")]
+  Status,
+  #[command(description = "re-fetch participant's block and report how its rows are parsed")]
+  Diagnose(String),
+}
+
+const BROADCAST_CONFIRM: &str = "broadcast_confirm";
+const BROADCAST_CANCEL: &str = "broadcast_cancel";
+
+/// Effective alias -> canonical command mapping, aliases colliding with real commands are dropped
+#[derive(Clone, Default)]
+struct CommandAliases(HashMap<String, String>);
+
+impl CommandAliases {
+  fn new(aliases: HashMap<String, String>) -> Self {
+    let commands: Vec<String> = Command::bot_commands()
+      .into_iter()
+      .chain(AdminCommand::bot_commands())
+      .map(|c| c.command.trim_start_matches('/').to_string())
+      .collect();
+    let aliases = aliases
+      .into_iter()
+      .filter(|(alias, canonical)| {
+        if commands.contains(alias) {
+          warn!("[Congratulator] Alias '{}' collides with existing command and is ignored", alias);
+          return false;
+        }
+        if !commands.contains(canonical) {
+          warn!("[Congratulator] Alias '{}' refers to unknown command '{}'", alias, canonical);
+        }
+        true
+      })
+      .collect();
+    Self(aliases)
+  }
 }
 
-type CongratulatorDialogue = Dialogue<State, InMemStorage<State>>;
+/// Amount of the latest filled days rendered in the sparkline of the score message
+const SPARKLINE_DAYS: usize = 14;
+
+/// Telegram rejects an inline query answer holding more results
+const INLINE_RESULTS_LIMIT: usize = 50;
+
+/// Amount of days shown by /participation
+const PARTICIPATION_DAYS: u32 = 14;
+
+/// Callback data prefix of the "month stats" button shown with the selected person's scores
+const PERSON_STATS_PREFIX: &str = "stats:";
+
+/// Callback data prefix of the "CSV export" button shown with the selected person's scores
+const PERSON_CSV_PREFIX: &str = "csv:";
+
+/// Callback data prefix of the "another day" button shown with the selected person's scores
+const PERSON_DATE_PREFIX: &str = "date:";
+
+/// Callback data prefix of the "all filled days" button shown with the selected person's scores
+const PERSON_DATES_PREFIX: &str = "days:";
+
+/// Callback data prefix of the first participant selected for /comparetwo
+const COMPARE_PREFIX: &str = "cmp:";
+
+/// Reply to a tap on a participant who has left the table since the keyboard was sent
+const STALE_PERSON_MSG: &str = "Этот пользователь больше не в таблице, попробуйте /scores снова";
+
+/// Telegram's limit for the document sent by a bot
+const DOCUMENT_SIZE_LIMIT: usize = 50 * 1024 * 1024;
+
+/// Telegram's limit for the photo caption length
+const PHOTO_CAPTION_LIMIT: usize = 1024;
+
+/// Year export fetches every month sheet, so it's allowed once per this period
+const YEAR_EXPORT_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+struct YearExportCooldown(Cooldown);
+
+/// Debounce of the on-demand dashboard refreshes
+struct RefreshCooldown(Cooldown);
+
+/// All-time summary fetches every month sheet, so its result is reused for this period
+const ALLTIME_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Last all-time message with the moment it was built
+#[derive(Default)]
+struct AllTimeCache(std::sync::Mutex<Option<(Instant, String)>>);
+
+/// Last /yearoverview message with the moment it was built, reused for `ALLTIME_CACHE_TTL`
+#[derive(Default)]
+struct YearOverviewCache(std::sync::Mutex<Option<(Instant, String)>>);
+
+type CongratulatorStorage = ErasedStorage<State>;
+type CongratulatorDialogue = Dialogue<State, CongratulatorStorage>;
 type CongratulatorHandlerError = Box<dyn std::error::Error + Send + Sync>;
 type CongratulatorHandlerResult = Result<(), CongratulatorHandlerError>;
 type LockedDashboard = RwLock<Dashboard>;
@@ -60,51 +277,130 @@ type LockedDashboard = RwLock<Dashboard>;
 #[allow(dead_code)]
 pub struct Congratulator<'a> {
   bot: Bot,
+  webhook: Option<webhooks::Options>,
   dispatcher: Dispatcher<Bot, CongratulatorHandlerError, DefaultKey>,
   dashboard: Arc<LockedDashboard>,
   task_manager: Arc<TaskManager<'a>>,
+  dashboard_cache: Option<Arc<DashboardCache>>,
+  fetch_counter: Arc<FetchCounter>,
+  started_at: Instant,
 }
 
 impl<'a> Congratulator<'a> {
   pub async fn new(cfg: CongratulatorConfig) -> Result<Congratulator<'a>, Error> {
     info!("[Congratulator] Bot is getting created");
 
+    Congratulator::install_settings(&cfg);
+
+    let cfg = Arc::new(cfg);
+    let aliases = CommandAliases::new(cfg.command_aliases());
+
     // Create Hub to fetch the data
-    let hub = Arc::new(AsyncSheetsHub::new(cfg.api_service_key(), cfg.spreadsheet_id()).await?);
+    let hub = Arc::new(Congratulator::create_hub(&cfg).await?);
 
     // Create shared data - the Dashboard
-    let dashboard = Arc::new(RwLock::new(hub.fetch_dashboard().await?));
+    let dashboard_cache = cfg.dashboard_cache_path().map(|path| Arc::new(DashboardCache::new(path)));
+    let dashboard = Arc::new(RwLock::new(Congratulator::initial_dashboard(&hub, dashboard_cache.as_deref()).await?));
 
     // Create Bot instance
     let bot = Bot::new(cfg.bot_token_str());
 
+    // Load admin notes attached to the dates
+    let notes = Arc::new(DayNotes::load(cfg.notes_file()).await?);
+
+    // Load participants watched by the chats
+    let watches = Arc::new(Watches::load(cfg.watches_file()).await?);
+
     // Create task manager
-    let mut task_manager = TaskManager::new(bot.clone(), dashboard.clone());
+    let pause = Arc::new(PauseSwitch::default());
+    let mut task_manager = TaskManager::new(bot.clone(), dashboard.clone(), notes.clone(), pause.clone());
 
     // Create periodic task that will fetch the data periodically
     // Schedule every amount of minutes specified in API_DATA_FETCH_TASK_INTERVAL_MIN env variable
-    let fetcher = task_manager.create_data_fetcher_task(hub.clone());
+    let celebration = CelebrationHook::new(
+      cfg.notify_chat_ids(),
+      cfg.participant_celebration_percent(),
+      cfg.group_celebration_percent(),
+    );
+    let standings_boards = Arc::new(StandingsBoards::default());
+    let standings = StandingsHook::new(standings_boards.clone());
+    let watch = WatchHook::new(watches.clone(), cfg.fill_deadline());
+    let summary_cache = Arc::new(SummaryCache::new(cfg.summary_cache_size()));
+    let fetch_counter = Arc::new(FetchCounter::default());
+    let mut hooks: Vec<Arc<dyn FetchHook>> = vec![
+      Arc::new(celebration),
+      Arc::new(standings),
+      Arc::new(watch),
+      summary_cache.clone(),
+      fetch_counter.clone(),
+    ];
+    if let Some(threshold) = cfg.improvement_delta_percent() {
+      hooks.push(Arc::new(ImprovementHook::new(cfg.improvement_chat_ids(), threshold)));
+    }
+    if let Some(cache) = &dashboard_cache {
+      hooks.push(Arc::new(DashboardCacheHook::new(cache.clone())));
+    }
+    let fetcher = task_manager.create_data_fetcher_task(hub.clone(), hooks);
 
-    // Create periodic tasks that send a particular message at some time
-    let notifier = task_manager.create_notifier_task("Fill in the table 📋".to_string(), cfg.notify_chat_id());
+    // The cached dashboard isn't a result of a fetch, so it doesn't count as one
+    if dashboard.read().await.fetch_stats().is_some() {
+      *task_manager.last_fetch().write().await = Some(helpers::current_time_utc());
+    }
+    if let Some(port) = cfg.health_check_port() {
+      HealthCheck::new(dashboard.clone(), task_manager.last_fetch(), cfg.health_check_staleness()).spawn(port);
+    }
 
-    // Create periodic task that send /todaysummary at some time
-    let sender = task_manager.create_summary_sender_task(cfg.notify_chat_id());
+    let reminder = i18n::tr(i18n::Text::Reminder).to_string();
 
     // Schedule periodic tasks
     task_manager.schedule_task(fetcher, PeriodicTimeUtc::every_min_time_utc(cfg.fetch_data_interval_min()));
-    task_manager.schedule_task(notifier, PeriodicTimeUtc::every_day_time_utc(18, 0, 0)); // 21:00 UTC+3
-    task_manager.schedule_task(sender, PeriodicTimeUtc::every_day_time_utc(20, 0, 0)); // 23:00 UTC+3
+    if cfg.combined_evening_message() {
+      // Create periodic task that send /todaysummary together with the reminder at some time
+      let sender = task_manager.create_combined_evening_task(reminder, cfg.notify_chat_ids());
+      task_manager.schedule_task(sender, Congratulator::every_day_at(cfg.summary_time()));
+    } else {
+      // Create periodic tasks that send a particular message at some time
+      let notifier = task_manager.create_notifier_task(
+        reminder,
+        cfg.notify_chat_ids(),
+        cfg.remind_only_missing(),
+        cfg.telegram_usernames(),
+      );
+
+      // Create periodic task that send /todaysummary at some time
+      let sender = task_manager.create_summary_sender_task(cfg.notify_chat_ids());
+
+      task_manager.schedule_task(notifier, Congratulator::every_day_at(cfg.notify_time()));
+      task_manager.schedule_task(sender, Congratulator::every_day_at(cfg.summary_time()));
+    }
 
     // Wrap TM to Arc
     let arc_task_manager = Arc::from(task_manager);
 
+    // Dialogues are kept in memory unless a storage file is configured
+    let storage: Arc<CongratulatorStorage> = match cfg.dialogue_storage_file() {
+      Some(path) => JsonFileStorage::<State>::open(path).await?.erase(),
+      None => InMemStorage::<State>::new().erase(),
+    };
+
     bot.set_my_commands(Command::bot_commands()).await?;
     let dispatcher = Dispatcher::builder(bot.clone(), Congratulator::schema())
       .dependencies(dptree::deps![
-        InMemStorage::<State>::new(),
+        storage,
         dashboard.clone(),
-        arc_task_manager.clone()
+        arc_task_manager.clone(),
+        cfg.clone(),
+        aliases,
+        hub.clone(),
+        notes,
+        watches,
+        summary_cache,
+        standings_boards,
+        pause,
+        Arc::new(YearExportCooldown(Cooldown::new(YEAR_EXPORT_COOLDOWN))),
+        Arc::new(RefreshCooldown(Cooldown::new(cfg.refresh_cooldown()))),
+        Arc::new(AllTimeCache::default()),
+        Arc::new(YearOverviewCache::default())
       ])
       .default_handler(|upd| async move {
         warn!("[Congratulator] Unhandled update: {:?}", upd);
@@ -117,20 +413,129 @@ impl<'a> Congratulator<'a> {
 
     let congratulator = Congratulator {
       bot,
+      webhook: cfg.webhook().map(|(url, address)| webhooks::Options::new(address, url)),
       dispatcher,
       dashboard,
       task_manager: arc_task_manager,
+      dashboard_cache,
+      fetch_counter,
+      started_at: Instant::now(),
     };
 
     info!("[Congratulator] Bot successfully created");
     Ok(congratulator)
   }
 
+  /// Fetches the dashboard once and prints every participant's latest record to stdout,
+  /// neither the bot nor the dispatcher is created
+  pub async fn dry_run(cfg: CongratulatorConfig) -> Result<(), Error> {
+    info!("[Congratulator] Dry run: fetching the dashboard once");
+    Congratulator::install_settings(&cfg);
+    let dashboard = Congratulator::create_hub(&cfg).await?.fetch_dashboard().await?;
+    let persons = dashboard.participants().unwrap_or_default();
+    println!("Participants: {}", persons.len());
+    for person in persons {
+      match dashboard.last_filled_score_table_record(person) {
+        Some(record) => println!(
+          "{}: {} {}% (total {})",
+          person.name(),
+          record.date().format("%d.%m.%Y"),
+          record.percent(),
+          Scores::display(record.total_score())
+        ),
+        None => println!("{}: no filled days", person.name()),
+      }
+    }
+    Ok(())
+  }
+
+  /// Installs the global settings consulted by the parser and the formatters
+  fn install_settings(cfg: &CongratulatorConfig) {
+    // Custom category emoji and labels
+    if !Category::set_appearance_overrides(cfg.categories(), &cfg.category_labels()) {
+      warn!("[Congratulator] Category overrides were already installed");
+    }
+    if !helpers::install_timezone_offset(cfg.timezone_offset_hours()) {
+      warn!("[Congratulator] Timezone offset was already installed");
+    }
+    if !cfg.default_locale().install() {
+      warn!("[Congratulator] Locale was already installed");
+    }
+    if !cfg.display_rounding().install() {
+      warn!("[Congratulator] Display rounding was already installed");
+    }
+    if !cfg.name_sanitization().install() {
+      warn!("[Congratulator] Name sanitization was already installed");
+    }
+    if !Category::set_checkbox_categories(cfg.checkbox_categories()) {
+      warn!("[Congratulator] Checkbox categories were already installed");
+    }
+  }
+
+  async fn create_hub(cfg: &CongratulatorConfig) -> Result<AsyncSheetsHub, Error> {
+    Ok(
+      AsyncSheetsHub::new(cfg.api_service_key(), cfg.spreadsheet_id())
+        .await?
+        .with_retry_policy(cfg.fetch_retry_policy())
+        .with_layout(cfg.sheet_layout().unwrap_or_default())
+        .with_title_override(cfg.sheet_title_override().map(str::to_string))
+        .with_skip_parse_errors(cfg.skip_parse_errors()),
+    )
+  }
+
+  /// Daily schedule at the participants' local `time`
+  fn every_day_at(time: NaiveTime) -> PeriodicTimeUtc {
+    PeriodicTimeUtc::every_day_local_time(time.hour(), time.minute(), 0)
+  }
+
+  /// Dispatches updates received through the webhook if it's configured, otherwise through long polling.
+  ///
+  /// The webhook listener serves plain HTTP on the bind address: TLS is expected to be terminated
+  /// by a reverse proxy (or load balancer) which forwards the public HTTPS URL to that address.
+  /// Telegram accepts only ports 443, 80, 88 and 8443 in the public URL.
   pub async fn listen(&mut self) {
+    if let Some(options) = self.webhook.take() {
+      info!("[Congratulator] Setting up the webhook at {}", options.url);
+      match webhooks::axum(self.bot.clone(), options).await {
+        Ok(listener) => {
+          info!("[Congratulator] Bot is starting dispatching events received through the webhook...");
+          self
+            .dispatcher
+            .dispatch_with_listener(
+              listener,
+              LoggingErrorHandler::with_custom_text("[Congratulator] Error has occurred in the webhook listener"),
+            )
+            .await;
+          return;
+        }
+        Err(err) => error!("[Congratulator] Unable to set up the webhook, falling back to long polling: {}", err),
+      }
+    }
     info!("[Congratulator] Bot is starting dispatching events...");
     self.dispatcher.dispatch().await
   }
 
+  /// Stops the periodic tasks, saves the last dashboard to the cache file (if configured)
+  /// and logs the final stats. Intended to be called once `listen()` has returned.
+  pub async fn shutdown(self) {
+    info!("[Congratulator] Shutting down ...");
+    self.task_manager.finalize_tasks();
+    if let Some(cache) = &self.dashboard_cache {
+      let dashboard = self.dashboard.read().await;
+      if dashboard.tables().is_some() {
+        match cache.save(&dashboard).await {
+          Ok(_) => info!("[Congratulator] Dashboard is saved to {:?}", cache.path()),
+          Err(err) => error!("[Congratulator] Unable to save the dashboard to {:?}: {}", cache.path(), err),
+        }
+      }
+    }
+    info!(
+      "[Congratulator] Bot has been running for {} seconds, dashboard updated {} times",
+      self.started_at.elapsed().as_secs(),
+      self.fetch_counter.count()
+    );
+  }
+
   pub async fn initialized(&self) -> bool {
     self.dashboard.read().await.tables().is_some()
   }
@@ -147,6 +552,25 @@ impl<'a> Congratulator<'a> {
     Ok(())
   }
 
+  async fn pick_winner(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][PickWinner] Start handling PickWinner (chat_id={})", chat_id);
+    let text = {
+      let dashboard = locked_dashboard.read().await;
+      let today = current_time_local().date_naive();
+      match dashboard.weighted_pick(&today, &mut rand::thread_rng()) {
+        Some(winner) => match dashboard.find_filled_score_table_record(winner, &today) {
+          Some(record) => format!("🎲 Барабан крутится... Победитель дня — {} с {}! 🎉", winner.name(), record.percent()),
+          None => format!("🎲 Сегодня таблицу еще никто не заполнял, так что удача выбрала просто так: {} 🍀", winner.name()),
+        },
+        None => "Участники не найдены 🤷".to_string(),
+      }
+    };
+    bot.send_message(chat_id, text).await?;
+    info!("[Congratulator][PickWinner] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
   async fn participants(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
     let chat_id = msg.chat.id;
     let dashboard = locked_dashboard.read().await;
@@ -155,7 +579,9 @@ impl<'a> Congratulator<'a> {
       Some(names) => join(&names, "\n"),
       None => "There are no participants found".to_string(),
     };
-    bot.send_message(chat_id, msg).await?;
+    for chunk in helpers::split_message(&msg, helpers::TELEGRAM_MESSAGE_LIMIT) {
+      bot.send_message(chat_id, chunk).await?;
+    }
     Ok(())
   }
 
@@ -170,18 +596,14 @@ impl<'a> Congratulator<'a> {
           .iter()
           .map(|person| InlineKeyboardButton::callback(person.name(), person.name()));
         bot
-          .send_message(
-            msg.chat.id,
-            "Могу показать последнюю статистику для какого\\-нибудь *конкретного* \
-            пользователя из списка ниже\\. Чьи цифры будем смотреть?",
-          )
+          .send_message(msg.chat.id, i18n::tr(i18n::Text::ScoresPrompt))
           .parse_mode(ParseMode::MarkdownV2)
           .reply_markup(InlineKeyboardMarkup::new([choices]))
           .await?;
       }
       None => {
         warn!("[Congratulator][Scores] The participants were not found");
-        bot.send_message(chat_id, "Список пользователей пуст 😩😭").await?;
+        bot.send_message(chat_id, i18n::tr(i18n::Text::EmptyParticipants)).await?;
       }
     }
 
@@ -192,9 +614,9 @@ impl<'a> Congratulator<'a> {
   async fn show_enabled_notifications(bot: Bot, msg: Message, task_manager: Arc<TaskManager<'_>>) -> CongratulatorHandlerResult {
     let chat_id = msg.chat.id;
     info!("[Congratulator][Notifications] Start handling Notifications (chat_id={})", chat_id);
-    let descriptions: Vec<_> = task_manager
-      .tasks(tasks::PeriodcTaskType::Notifier)
-      .iter()
+    let descriptions: Vec<_> = [tasks::PeriodcTaskType::Notifier, tasks::PeriodcTaskType::Summary]
+      .into_iter()
+      .flat_map(|task_type| task_manager.tasks(task_type))
       .filter_map(|t| t.description())
       .collect();
     let msg = if descriptions.is_empty() {
@@ -207,150 +629,1899 @@ impl<'a> Congratulator<'a> {
     Ok(())
   }
 
-  async fn today_summary(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
-    Congratulator::summary(bot, msg, locked_dashboard, &current_time_utc_msk().date_naive()).await
+  async fn schedule(bot: Bot, msg: Message, task_manager: Arc<TaskManager<'_>>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Schedule] Start handling Schedule (chat_id={})", chat_id);
+    let entries: Vec<_> = tasks::PeriodcTaskType::ALL
+      .into_iter()
+      .flat_map(|task_type| task_manager.tasks(task_type))
+      .map(|task| {
+        let when = task.when().map_or("не запланировано".to_string(), |when| when.to_string());
+        let entry = format!("• {} ({}): {}", task.name(), task.task_type().label(), when);
+        match task.description() {
+          Some(description) => format!("{}\n  {}", entry, description),
+          None => entry,
+        }
+      })
+      .collect();
+    let msg = if entries.is_empty() {
+      "Список активных заданий пуст".to_string()
+    } else {
+      format!("🗓 Расписание заданий:\n{}", join(entries, "\n"))
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Schedule] Finished handling (chat_id={})", chat_id);
+    Ok(())
   }
 
-  async fn yesterday_summary(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
-    if let Some(yesterday) = current_time_utc_msk().date_naive().pred_opt() {
-      Congratulator::summary(bot, msg, locked_dashboard, &yesterday).await?
-    } else {
-      error!("Unable to handle YesterdaySummary: can't derive the date for yesterday");
+  async fn today_summary(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    notes: Arc<DayNotes>,
+    cache: Arc<SummaryCache>,
+    hub: Arc<AsyncSheetsHub>,
+    task_manager: Arc<TaskManager<'_>>,
+  ) -> CongratulatorHandlerResult {
+    // Without a single successful fetch there is nothing to summarize, so one more attempt is made right away
+    if locked_dashboard.read().await.tables().is_none() {
+      info!("[Congratulator][Summary] Dashboard is not initialized, attempting an on-demand fetch");
+      match task_manager.refresh_dashboard(hub).await {
+        Ok(()) => info!("[Congratulator][Summary] On-demand fetch has succeeded"),
+        Err(err) => warn!("[Congratulator][Summary] On-demand fetch has failed: {}", err),
+      }
     }
-    Ok(())
+    Congratulator::summary(bot, msg, locked_dashboard, notes, cache, &current_time_local().date_naive()).await
   }
 
-  async fn summary(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>, by_date: &NaiveDate) -> CongratulatorHandlerResult {
+  async fn yesterday_summary(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    notes: Arc<DayNotes>,
+    cache: Arc<SummaryCache>,
+    hub: Arc<AsyncSheetsHub>,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let Some((yesterday, other_sheet)) = helpers::local_yesterday() else {
+      error!("Unable to handle YesterdaySummary: can't derive the date for yesterday");
+      return Ok(());
+    };
+    let Some(title) = other_sheet else {
+      return Congratulator::summary(bot, msg, locked_dashboard, notes, cache, &yesterday).await;
+    };
+
+    // Yesterday belongs to the previous month which is kept on another sheet
     let chat_id = msg.chat.id;
-    let dashboard = locked_dashboard.read().await;
+    let previous_month = if cfg.yesterday_from_previous_month() {
+      debug!("[Congratulator][Summary] Fetching previous month sheet '{}'", title);
+      hub
+        .fetch_dashboard_by_title(&title)
+        .await
+        .map_err(|err| warn!("[Congratulator][Summary] Previous month is unavailable: {}", err))
+        .ok()
+    } else {
+      None
+    };
+    match previous_month {
+      Some(dashboard) => Congratulator::send_summary(&bot, chat_id, dashboard.summary(&yesterday), &notes, &yesterday).await,
+      None => {
+        bot.send_message(chat_id, "Данные за прошлый месяц на другом листе 📄").await?;
+        Ok(())
+      }
+    }
+  }
+
+  async fn summary(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    notes: Arc<DayNotes>,
+    cache: Arc<SummaryCache>,
+    by_date: &NaiveDate,
+  ) -> CongratulatorHandlerResult {
+    let summary = {
+      let dashboard = locked_dashboard.read().await;
+      cache.get_or_insert_with(by_date, || dashboard.summary(by_date))
+    };
+    Congratulator::send_summary(&bot, msg.chat.id, summary, &notes, by_date).await
+  }
+
+  async fn send_summary(
+    bot: &Bot,
+    chat_id: ChatId,
+    summary: Result<Vec<String>, DashboardError>,
+    notes: &DayNotes,
+    by_date: &NaiveDate,
+  ) -> CongratulatorHandlerResult {
     info!(
       "[Congratulator][Summary] Start handling Summary (chat_id={}) for date='{}'",
       chat_id, by_date
     );
 
-    match dashboard.summary(by_date) {
+    match summary {
       Ok(summary) => {
-        let msg = helpers::format_summary_msg(&summary, by_date);
-        bot.send_message(chat_id, msg).parse_mode(ParseMode::MarkdownV2).await?;
+        let note = notes.get(by_date).await;
+        let msg = helpers::format_summary_msg(&summary, by_date, note.as_deref());
+        broadcast::send_markdown_chunks(bot, chat_id, &msg).await?;
+      }
+      Err(DashboardError::NotInitialized) => {
+        warn!("[Congratulator][Summary] The dashboard is not initialized");
+        bot.send_message(chat_id, "Данные таблицы еще не загружены, попробуйте позже ⏳").await?;
       }
-      Err(DashboardError::EmptyParticipants) => {
-        warn!("[Congratulator][Summary] The participants were not found");
-        bot.send_message(chat_id, "Список пользователей пуст 😩😭").await?;
+      Err(err) => {
+        warn!("[Congratulator][Summary] {}", err);
+        bot.send_message(chat_id, i18n::tr(i18n::Text::EmptyParticipants)).await?;
       }
     }
     info!("[Congratulator][Summary] Finished handling (chat_id={})", chat_id);
     Ok(())
   }
 
-  async fn unhandled_message(_bot: Bot, msg: Message) -> CongratulatorHandlerResult {
-    warn!("Called unhandled_message() callback with msg={:?}", msg);
+  async fn deadline(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Deadline] Start handling Deadline (chat_id={})", chat_id);
+    let Some(deadline) = cfg.fill_deadline() else {
+      warn!("[Congratulator][Deadline] Fill deadline is not configured");
+      bot.send_message(chat_id, "Дедлайн заполнения таблицы не настроен 🤷").await?;
+      return Ok(());
+    };
+
+    let now = current_time_local().naive_utc();
+    let today = now.date();
+    let msg = if now.time() < deadline {
+      let remaining = today.and_time(deadline) - now;
+      format!(
+        "⏳ До дедлайна ({}) осталось {}, не забудьте заполнить таблицу 📋",
+        deadline.format("%H:%M"),
+        helpers::format_duration_ru(&remaining)
+      )
+    } else {
+      let dashboard = locked_dashboard.read().await;
+      match dashboard.missed(&today) {
+        Some(missed) if missed.is_empty() => {
+          format!("⌛ Дедлайн ({}) прошел, все заполнили таблицу 🎉", deadline.format("%H:%M"))
+        }
+        Some(missed) => format!(
+          "⌛ Дедлайн ({}) прошел. Не заполнили таблицу:\n{}",
+          deadline.format("%H:%M"),
+          join(missed.iter().map(|p| p.name()), "\n")
+        ),
+        None => {
+          warn!("[Congratulator][Deadline] The participants were not found");
+          i18n::tr(i18n::Text::EmptyParticipants).to_string()
+        }
+      }
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Deadline] Finished handling (chat_id={})", chat_id);
     Ok(())
   }
 
-  async fn my_chat_member_update_handler(bot: Bot, update: ChatMemberUpdated) -> CongratulatorHandlerResult {
-    let ChatMemberUpdated {
-      chat,
-      from,
-      new_chat_member,
-      ..
-    } = &update;
-    let chat_id = chat.id;
+  async fn missing(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Missing] Start handling Missing (chat_id={})", chat_id);
+    let msg = {
+      let dashboard = locked_dashboard.read().await;
+      if dashboard.tables().is_none() {
+        warn!("[Congratulator][Missing] The participants were not found");
+        i18n::tr(i18n::Text::EmptyParticipants).to_string()
+      } else {
+        match dashboard.not_filled_today().as_slice() {
+          [] => "Сегодня все заполнили таблицу 🎉".to_string(),
+          missed => format!(
+            "📋 Сегодня еще не заполнили таблицу:\n{}",
+            join(missed.iter().map(|p| p.name()), "\n")
+          ),
+        }
+      }
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Missing] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
 
-    info!("[Congratulator][ChatMemberUpdated] Start handling update (chat_id={})", chat_id);
-    let msg = if new_chat_member.is_member() {
-      format!("Благодаря *{}* я теперь в этом чатике\\. Большое спасибо\\!", from.full_name())
-    } else {
-      debug!("[Congratulator][ChatMemberUpdated] The update was unhandled {:?}", update);
-      return Ok(());
+  async fn rank_history(bot: Bot, msg: Message, name: String, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][RankHistory] Start handling RankHistory (chat_id={})", chat_id);
+    let msg = match dashboard.get_person_by_name(name.trim()) {
+      Some(person) => {
+        let ranks = dashboard.daily_ranks(person, &current_time_local().date_naive());
+        helpers::format_rank_history_msg(&ranks, person)
+      }
+      None => {
+        warn!("[Congratulator][RankHistory] Person '{}' was not found", name);
+        "Использование: /rankhistory <имя участника из /participants>".to_string()
+      }
     };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][RankHistory] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
 
-    bot.send_message(chat_id, msg).parse_mode(ParseMode::MarkdownV2).await?;
+  async fn reach_goal(bot: Bot, msg: Message, args: String, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][ReachGoal] Start handling ReachGoal (chat_id={})", chat_id);
+
+    // Trailing numeric tokens are the target and the optional daily assumption, the rest is the name
+    let mut tokens: Vec<&str> = args.split_whitespace().collect();
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+      match tokens.last().and_then(|t| t.trim_end_matches('%').parse::<f64>().ok()) {
+        Some(number) => {
+          numbers.insert(0, number);
+          tokens.pop();
+        }
+        None => break,
+      }
+    }
+    let name = tokens.join(" ");
+    let (target, daily_percent) = match numbers[..] {
+      [target] => (target, 100.),
+      [target, daily] => (target, daily),
+      _ => (f64::NAN, f64::NAN),
+    };
+
+    let msg = match dashboard.get_person_by_name(&name) {
+      Some(person) if target.is_finite() && target > 0. => {
+        let today = current_time_local().date_naive();
+        let today_filled = dashboard.find_filled_score_table_record(person, &today).is_some();
+        let days_left = (helpers::last_day_of_month(&today) - today).num_days() as u32 + u32::from(!today_filled);
+        let estimate = dashboard.estimate_days_to_goal(person, target, daily_percent, days_left);
+        debug!("[Congratulator][ReachGoal] Estimate for {:?}: {:?}", person, estimate);
+        helpers::format_goal_estimate_msg(&estimate, person, target, daily_percent)
+      }
+      _ => "Использование: /reachgoal <имя участника> <цель %> [результат в день %]".to_string(),
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][ReachGoal] Finished handling (chat_id={})", chat_id);
     Ok(())
   }
 
-  async fn receive_user_selected(
+  async fn participation(
     bot: Bot,
-    dialog: CongratulatorDialogue,
-    callback_query: CallbackQuery,
+    msg: Message,
     locked_dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
   ) -> CongratulatorHandlerResult {
-    let chat_id = dialog.chat_id();
+    let chat_id = msg.chat.id;
     let dashboard = locked_dashboard.read().await;
-    info!(
-      "[Congratulator][ReceiveSelectedUser] Handling state from User={:?} (chat_id={})",
-      callback_query.from, chat_id
-    );
-
-    let callback_data: String = callback_query.data.ok_or_else(|| {
-      error!("[Congratulator][ReceiveSelectedUser] Received None in callback data");
-      Error::EmptyCallbackData
-    })?;
+    info!("[Congratulator][Participation] Start handling Participation (chat_id={})", chat_id);
 
-    let person = dashboard.get_person_by_name(&callback_data[..]).ok_or_else(|| {
-      error!("[Congratulator][ReceiveSelectedUser] Person was not found");
-      Error::PersonNotFound
-    })?;
-
-    debug!("[Congratulator][ReceiveSelectedUser] Selected person = {:?}", person);
-    match dashboard.last_filled_score_table_record(person) {
-      Some(last_record) => {
-        trace!("[Congratulator][ReceiveSelectedUser] Found {:?}", last_record);
-        bot
-          .send_message(chat_id, helpers::format_user_score_msg(last_record, person))
-          .parse_mode(ParseMode::MarkdownV2)
-          .await?;
-      }
-      None => {
-        warn!(
-          "[Congratulator][ReceiveSelectedUser] Last score record was not found for {:?}",
-          person
-        );
-        bot
-          .send_message(
-            chat_id,
-            format!("*{}* не заполнил\\(а\\) *ни одного* дня за последний месяц 😢", person.name()),
-          )
-          .parse_mode(ParseMode::MarkdownV2)
-          .await?;
-      }
+    // Today isn't over until the deadline, so it would look like a low participation day
+    let now = current_time_local().naive_utc();
+    let mut end_date = now.date();
+    if cfg.fill_deadline().is_some_and(|deadline| now.time() < deadline) {
+      end_date = end_date.pred_opt().unwrap_or(end_date);
     }
 
-    bot.answer_callback_query(callback_query.id).send().await?;
-    info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+    let rates = dashboard.participation_rates(&end_date, PARTICIPATION_DAYS);
+    let msg = if rates.is_empty() {
+      warn!("[Congratulator][Participation] The participants were not found");
+      i18n::tr(i18n::Text::EmptyParticipants).to_string()
+    } else {
+      helpers::format_participation_msg(&rates)
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Participation] Finished handling (chat_id={})", chat_id);
     Ok(())
   }
 
-  fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
-    use dptree::case;
+  async fn improvers(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    hub: Arc<AsyncSheetsHub>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Improvers] Start handling Improvers (chat_id={})", chat_id);
+    let today = current_time_local().date_naive();
+    let last_monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64 + 7);
 
-    let command_handler = teloxide::filter_command::<Command, _>()
-      .branch(case![Command::Help].endpoint(Congratulator::help))
-      .branch(case![Command::Dice].endpoint(Congratulator::dice))
-      .branch(case![Command::Participants].endpoint(Congratulator::participants))
-      .branch(case![Command::Scores].endpoint(Congratulator::scores))
-      .branch(case![Command::TodaySummary].endpoint(Congratulator::today_summary))
-      .branch(case![Command::YesterdaySummary].endpoint(Congratulator::yesterday_summary))
-      .branch(case![Command::EnabledNotifications].endpoint(Congratulator::show_enabled_notifications));
+    // Last week may begin in the previous month which is kept on another sheet
+    let previous_month = if last_monday.month() != today.month() {
+      let title = helpers::derive_title_name_for(&last_monday);
+      debug!("[Congratulator][Improvers] Fetching previous month sheet '{}'", title);
+      hub
+        .fetch_dashboard_by_title(&title)
+        .await
+        .map_err(|err| warn!("[Congratulator][Improvers] Previous month is unavailable: {}", err))
+        .ok()
+    } else {
+      None
+    };
 
-    let updates_handler = Update::filter_my_chat_member().branch(dptree::endpoint(Congratulator::my_chat_member_update_handler));
+    let dashboard = locked_dashboard.read().await;
+    let combined = previous_month.as_ref().map(|previous| dashboard.with_previous_month(previous));
+    let dashboard = combined.as_ref().unwrap_or(&dashboard);
 
-    let message_handler = Update::filter_message()
-      .branch(command_handler)
-      .branch(dptree::endpoint(Congratulator::unhandled_message));
+    let mut improvements: Vec<_> = dashboard
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| dashboard.weekly_improvement(p, &today).map(|delta| (p, delta)))
+      .collect();
+    improvements.sort_by(|(a_person, a), (b_person, b)| b.total_cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
 
-    let callback_query_handler =
-      Update::filter_callback_query().branch(case![State::Default].endpoint(Congratulator::receive_user_selected));
+    bot.send_message(chat_id, helpers::format_improvers_msg(&improvements)).await?;
+    info!("[Congratulator][Improvers] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
 
-    dialogue::enter::<Update, InMemStorage<State>, State, _>()
-      .branch(updates_handler)
-      .branch(message_handler)
-      .branch(callback_query_handler)
+  async fn group_categories(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][GroupCategories] Start handling GroupCategories (chat_id={})", chat_id);
+
+    let today = current_time_local().date_naive();
+    let totals = dashboard.group_category_totals(&today);
+    bot
+      .send_message(chat_id, helpers::format_group_categories_msg(&totals, &today))
+      .await?;
+    info!("[Congratulator][GroupCategories] Finished handling (chat_id={})", chat_id);
+    Ok(())
   }
-}
 
-impl<'a> Drop for Congratulator<'a> {
-  fn drop(&mut self) {
-    debug!("[Congratulator] Dropping ...");
-    self.task_manager.finalize_tasks();
+  async fn category_leaders(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][CategoryLeaders] Start handling CategoryLeaders (chat_id={})", chat_id);
+
+    let today = current_time_local().date_naive();
+    let leaders = dashboard.category_leaders(&today);
+    bot
+      .send_message(chat_id, helpers::format_category_leaders_msg(&leaders, &today))
+      .await?;
+    info!("[Congratulator][CategoryLeaders] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn now(bot: Bot, msg: Message, cfg: Arc<CongratulatorConfig>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Now] Start handling Now (chat_id={})", chat_id);
+    let msg = helpers::format_time_handling_msg(&helpers::current_time_utc(), cfg.fill_deadline());
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Now] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn note(bot: Bot, msg: Message, args: String, notes: Arc<DayNotes>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Note] Start handling Note (chat_id={})", chat_id);
+
+    let (date, text) = match args.trim().split_once(char::is_whitespace) {
+      Some((date, text)) => (date, text.trim()),
+      None => (args.trim(), ""),
+    };
+    let Ok(date) = NaiveDate::parse_from_str(date, "%d.%m.%Y") else {
+      bot
+        .send_message(chat_id, "Использование: /note ДД.ММ.ГГГГ <текст заметки | clear>")
+        .await?;
+      return Ok(());
+    };
+
+    let reply = match text {
+      "" => match notes.get(&date).await {
+        Some(note) => format!("📝 Заметка на {}: {}", date.format("%d.%m.%Y"), note),
+        None => format!("Заметки на {} нет", date.format("%d.%m.%Y")),
+      },
+      "clear" => match notes.clear(&date).await {
+        Ok(true) => format!("🗑 Заметка на {} удалена", date.format("%d.%m.%Y")),
+        Ok(false) => format!("Заметки на {} нет", date.format("%d.%m.%Y")),
+        Err(err) => {
+          error!("[Congratulator][Note] Unable to remove the note: {}", err);
+          "Не удалось удалить заметку 😩".to_string()
+        }
+      },
+      text => match notes.set(date, text.to_string()).await {
+        Ok(()) => format!("📝 Заметка на {} сохранена", date.format("%d.%m.%Y")),
+        Err(err) => {
+          error!("[Congratulator][Note] Unable to save the note: {}", err);
+          "Не удалось сохранить заметку 😩".to_string()
+        }
+      },
+    };
+    bot.send_message(chat_id, reply).await?;
+    info!("[Congratulator][Note] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn what_if(
+    bot: Bot,
+    msg: Message,
+    args: String,
+    locked_dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][WhatIf] Start handling WhatIf (chat_id={})", chat_id);
+
+    let usage = format!(
+      "Использование: /whatif <имя участника> <категория>=<значение> ...\nКатегории: {}",
+      join(Category::ALL.iter().map(|c| c.key()), ", ")
+    );
+    let msg = match helpers::parse_score_overrides(&args) {
+      Err(invalid) => format!("Не удалось разобрать «{}»\n{}", invalid, usage),
+      Ok((_, overrides)) if overrides.is_empty() => usage,
+      Ok((name, overrides)) => match dashboard.get_person_by_name(&name) {
+        None => format!("Участник «{}» не найден 🤷", name),
+        Some(person) => {
+          let today = current_time_local().date_naive();
+          let current = dashboard
+            .find_table(person)
+            .and_then(|table| table.by_date(&today))
+            .map(|rec| rec.scores().clone())
+            .unwrap_or_default();
+          // Without the configured max it's derived from the last filled day as total / percent
+          let max = cfg.daily_max_score().or_else(|| {
+            dashboard
+              .last_filled_score_table_record(person)
+              .filter(|rec| rec.percent().value() > 0)
+              .and_then(|rec| rec.total_score().map(|total| total * 100. / rec.percent().value() as f64))
+          });
+          match max {
+            Some(max) => helpers::format_whatif_msg(person, &current.with_overrides(&overrides), max),
+            None => "Не удалось определить максимальный результат за день 🤷".to_string(),
+          }
+        }
+      },
+    };
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][WhatIf] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  /// Month title followed by the legend mapping the row numbers to names, unless the table is anonymized
+  fn month_table_caption(title: &str, names: Option<&[String]>) -> String {
+    match names {
+      Some(names) => {
+        let legend = names.iter().enumerate().map(|(i, name)| format!("{} — {}", i + 1, name));
+        format!("🗓 {}\n{}", title, join(legend, "\n"))
+      }
+      None => format!("🗓 {}", title),
+    }
+  }
+
+  async fn month_table(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][MonthTable] Start handling MonthTable (chat_id={})", chat_id);
+
+    let today = current_time_local().date_naive();
+    let first_day = today.with_day(1).unwrap_or(today);
+    let days = helpers::last_day_of_month(&today).day();
+    let (image, names) = {
+      let dashboard = locked_dashboard.read().await;
+      let names = (!cfg.anonymize_month_table()).then(|| {
+        dashboard.participants_names().unwrap_or_default().iter().map(|n| n.to_string()).collect::<Vec<_>>()
+      });
+      (chart::render_month_table(&dashboard, &first_day, days), names)
+    };
+    match image {
+      Ok(png) => {
+        let title = helpers::derive_title_name_for(&today);
+        let caption = Congratulator::month_table_caption(&title, names.as_deref());
+        let photo = bot.send_photo(chat_id, InputFile::memory(png).file_name("month.png"));
+        if caption.chars().count() <= PHOTO_CAPTION_LIMIT {
+          photo.caption(caption).await?;
+        } else {
+          // Legend of a big group doesn't fit into the caption
+          photo.await?;
+          bot.send_message(chat_id, caption).await?;
+        }
+      }
+      Err(err) => {
+        error!("[Congratulator][MonthTable] Unable to render the table: {}", err);
+        bot.send_message(chat_id, "Не удалось построить таблицу 😩").await?;
+      }
+    }
+    info!("[Congratulator][MonthTable] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn watch(
+    bot: Bot,
+    msg: Message,
+    name: String,
+    locked_dashboard: Arc<LockedDashboard>,
+    watches: Arc<Watches>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let name = name.trim();
+    info!("[Congratulator][Watch] Start handling Watch for '{}' (chat_id={})", name, chat_id);
+
+    let reply = if name.is_empty() {
+      let watched = watches.watched_by(chat_id);
+      if watched.is_empty() {
+        "Использование: /watch <имя участника>".to_string()
+      } else {
+        format!("👀 Отслеживаемые участники: {}", join(watched, ", "))
+      }
+    } else if locked_dashboard.read().await.get_person_by_name(name).is_none() {
+      format!("Участник «{}» не найден", name)
+    } else {
+      match watches.add(chat_id, name).await {
+        Ok(true) => format!("👀 Буду сообщать, когда {} заполнит таблицу или пропустит дедлайн", name),
+        Ok(false) => format!("{} уже отслеживается", name),
+        Err(err) => {
+          error!("[Congratulator][Watch] Unable to save the watch: {}", err);
+          "Не удалось сохранить подписку 😩".to_string()
+        }
+      }
+    };
+    bot.send_message(chat_id, reply).await?;
+    info!("[Congratulator][Watch] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn unwatch(bot: Bot, msg: Message, name: String, watches: Arc<Watches>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let name = name.trim();
+    info!("[Congratulator][Unwatch] Start handling Unwatch for '{}' (chat_id={})", name, chat_id);
+    let reply = match watches.remove(chat_id, name).await {
+      Ok(true) => format!("🔕 {} больше не отслеживается", name),
+      Ok(false) => format!("{} не отслеживается", name),
+      Err(err) => {
+        error!("[Congratulator][Unwatch] Unable to remove the watch: {}", err);
+        "Не удалось удалить подписку 😩".to_string()
+      }
+    };
+    bot.send_message(chat_id, reply).await?;
+    info!("[Congratulator][Unwatch] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn records(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][Records] Start handling Records (chat_id={})", chat_id);
+    let records = dashboard.new_personal_bests(&current_time_local().date_naive());
+    bot.send_message(chat_id, helpers::format_personal_bests_msg(&records)).await?;
+    info!("[Congratulator][Records] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn trends(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][Trends] Start handling Trends (chat_id={})", chat_id);
+    let trends = dashboard.trends();
+    bot.send_message(chat_id, helpers::format_trends_msg(&trends)).await?;
+    info!("[Congratulator][Trends] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn most_consistent(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][MostConsistent] Start handling MostConsistent (chat_id={})", chat_id);
+    let counts = dashboard.filled_day_counts();
+    if counts.is_empty() {
+      warn!("[Congratulator][MostConsistent] The participants were not found");
+    }
+    bot.send_message(chat_id, helpers::format_most_consistent_msg(&counts)).await?;
+    info!("[Congratulator][MostConsistent] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn leaderboard(bot: Bot, msg: Message, date: String, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Leaderboard] Start handling Leaderboard (chat_id={})", chat_id);
+    let date = match date.trim() {
+      "" => current_time_local().date_naive(),
+      date => match NaiveDate::parse_from_str(date, "%d.%m.%Y") {
+        Ok(date) => date,
+        Err(_) => {
+          bot.send_message(chat_id, "Использование: /leaderboard [ДД.ММ.ГГГГ]").await?;
+          return Ok(());
+        }
+      },
+    };
+    let msg = {
+      let dashboard = locked_dashboard.read().await;
+      helpers::format_leaderboard_msg(&dashboard.ranking_by_date(&date), &date)
+    };
+    broadcast::send_markdown(&bot, chat_id, &msg).await?;
+    info!("[Congratulator][Leaderboard] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn person_stats(bot: Bot, msg: Message, name: String, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let name = name.trim();
+    info!("[Congratulator][PersonStats] Start handling PersonStats for '{}' (chat_id={})", name, chat_id);
+    if name.is_empty() {
+      bot.send_message(chat_id, "Использование: /personstats <имя участника>").await?;
+      return Ok(());
+    }
+    let text = {
+      let dashboard = locked_dashboard.read().await;
+      Congratulator::monthly_stats_text(&dashboard, name)
+    };
+    match text {
+      Ok(text) => {
+        broadcast::send_markdown(&bot, chat_id, &text).await?;
+      }
+      Err(_) => {
+        bot.send_message(chat_id, format!("Участник «{}» не найден 🤷", name)).await?;
+      }
+    }
+    info!("[Congratulator][PersonStats] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  /// Participant the callback data refers to, with or without one of the person buttons' prefixes
+  fn selected_person<'d>(dashboard: &'d Dashboard, callback_data: &str) -> Result<&'d Person, Error> {
+    let name = [PERSON_STATS_PREFIX, PERSON_CSV_PREFIX, PERSON_DATE_PREFIX, PERSON_DATES_PREFIX, COMPARE_PREFIX]
+      .iter()
+      .find_map(|prefix| callback_data.strip_prefix(prefix))
+      .unwrap_or(callback_data);
+    dashboard.get_person_by_name(name).ok_or_else(|| {
+      warn!("[Congratulator][ReceiveSelectedUser] Person '{}' is no longer in the table", name);
+      Error::PersonNotFound
+    })
+  }
+
+  /// MarkdownV2 month aggregates of the participant
+  fn monthly_stats_text(dashboard: &Dashboard, name: &str) -> Result<String, Error> {
+    let person = dashboard.get_person_by_name(name).ok_or_else(|| {
+      error!("[Congratulator][PersonStats] Person '{}' was not found", name);
+      Error::PersonNotFound
+    })?;
+    Ok(match dashboard.monthly_stats(person) {
+      Some(stats) => helpers::format_monthly_stats_msg(&stats, person),
+      None => helpers::format_no_filled_days_msg(person),
+    })
+  }
+
+  async fn score_on(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][ScoreOn] Start handling ScoreOn (chat_id={})", chat_id);
+    match dashboard.participants() {
+      Some(persons) => {
+        let choices = persons.iter().map(|person| {
+          InlineKeyboardButton::callback(person.name(), format!("{}{}", PERSON_DATE_PREFIX, person.name()))
+        });
+        bot
+          .send_message(chat_id, "Чей результат показать?")
+          .reply_markup(InlineKeyboardMarkup::new([choices]))
+          .await?;
+      }
+      None => {
+        warn!("[Congratulator][ScoreOn] The participants were not found");
+        bot.send_message(chat_id, i18n::tr(i18n::Text::EmptyParticipants)).await?;
+      }
+    }
+    info!("[Congratulator][ScoreOn] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn compare_two(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][CompareTwo] Start handling CompareTwo (chat_id={})", chat_id);
+    match dashboard.participants() {
+      Some(persons) if persons.len() >= 2 => {
+        let choices = persons.iter().map(|person| {
+          InlineKeyboardButton::callback(person.name(), format!("{}{}", COMPARE_PREFIX, person.name()))
+        });
+        bot
+          .send_message(chat_id, "Кого сравниваем? Выберите первого участника")
+          .reply_markup(InlineKeyboardMarkup::new([choices]))
+          .await?;
+      }
+      _ => {
+        warn!("[Congratulator][CompareTwo] Not enough participants to compare");
+        bot.send_message(chat_id, "Для сравнения нужны хотя бы двое участников 🤷").await?;
+      }
+    }
+    info!("[Congratulator][CompareTwo] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn receive_compare_second(
+    bot: Bot,
+    dialog: CongratulatorDialogue,
+    first: String,
+    callback_query: CallbackQuery,
+    locked_dashboard: Arc<LockedDashboard>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = dialog.chat_id();
+    info!("[Congratulator][CompareTwo] Handling the second participant (chat_id={})", chat_id);
+    bot.answer_callback_query(callback_query.id).await?;
+    dialog.update(State::Default).await?;
+    let second = callback_query.data.ok_or_else(|| {
+      error!("[Congratulator][CompareTwo] Received None in callback data");
+      Error::EmptyCallbackData
+    })?;
+
+    let text = {
+      let dashboard = locked_dashboard.read().await;
+      match (dashboard.get_person_by_name(&first), dashboard.get_person_by_name(&second)) {
+        (Some(a), Some(b)) => match dashboard.compare(a, b) {
+          Some(comparison) => helpers::format_comparison_msg(&comparison),
+          None => helpers::escape_markdown_v2("У обоих участников в этом месяце нет заполненных дней 🤷"),
+        },
+        _ => {
+          warn!("[Congratulator][CompareTwo] '{}' or '{}' was not found", first, second);
+          helpers::escape_markdown_v2("Участник не найден, попробуйте /comparetwo еще раз")
+        }
+      }
+    };
+    broadcast::send_markdown(&bot, chat_id, &text).await?;
+    info!("[Congratulator][CompareTwo] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn export_json(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][ExportJson] Start handling ExportJson (chat_id={})", chat_id);
+    let json = {
+      let dashboard = locked_dashboard.read().await;
+      serde_json::to_vec_pretty(&helpers::dashboard_to_json(&dashboard))?
+    };
+    if json.len() > DOCUMENT_SIZE_LIMIT {
+      warn!("[Congratulator][ExportJson] Export of {} bytes exceeds the document limit", json.len());
+      bot.send_message(chat_id, "Выгрузка слишком большая для отправки в Telegram 😩").await?;
+    } else {
+      let title = helpers::derive_title_name_for(&current_time_local().date_naive());
+      let file = InputFile::memory(json).file_name(format!("{}.json", title));
+      bot.send_document(chat_id, file).await?;
+    }
+    info!("[Congratulator][ExportJson] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn refresh(
+    bot: Bot,
+    msg: Message,
+    hub: Arc<AsyncSheetsHub>,
+    task_manager: Arc<TaskManager<'_>>,
+    cooldown: Arc<RefreshCooldown>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Refresh] Start handling Refresh (chat_id={})", chat_id);
+    if let Err(left) = cooldown.0.try_start() {
+      warn!("[Congratulator][Refresh] Refresh is on cooldown for {:?}", left);
+      bot
+        .send_message(chat_id, format!("Данные недавно обновлялись, попробуйте через {} с", left.as_secs()))
+        .await?;
+      return Ok(());
+    }
+
+    let reply = match task_manager.refresh_dashboard(hub).await {
+      Ok(()) => "🔄 Данные обновлены".to_string(),
+      Err(err) => format!("Не удалось обновить данные: {}", err),
+    };
+    bot.send_message(chat_id, reply).await?;
+    info!("[Congratulator][Refresh] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn streaks(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][Streaks] Start handling Streaks (chat_id={})", chat_id);
+    let streaks = dashboard.current_streaks(&current_time_local().date_naive());
+    if streaks.is_empty() {
+      warn!("[Congratulator][Streaks] The participants were not found");
+    }
+    bot.send_message(chat_id, helpers::format_streaks_msg(&streaks)).await?;
+    info!("[Congratulator][Streaks] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn standings_board(
+    bot: Bot,
+    msg: Message,
+    args: String,
+    locked_dashboard: Arc<LockedDashboard>,
+    boards: Arc<StandingsBoards>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][StandingsBoard] Start handling StandingsBoard (chat_id={})", chat_id);
+
+    if args.trim().eq_ignore_ascii_case("off") {
+      let reply = if boards.remove(chat_id) {
+        "Турнирная таблица больше не будет обновляться"
+      } else {
+        "В этом чате нет обновляемой турнирной таблицы 🤷"
+      };
+      bot.send_message(chat_id, reply).await?;
+      return Ok(());
+    }
+
+    let now = current_time_local();
+    let text = helpers::format_standings_msg(&locked_dashboard.read().await.standings(&now.date_naive()), &now);
+    let sent = broadcast::send_markdown(&bot, chat_id, &text).await?;
+    if let Err(err) = bot.pin_chat_message(chat_id, sent.id).disable_notification(true).await {
+      warn!("[Congratulator][StandingsBoard] Unable to pin the standings: {}", err);
+    }
+    boards.register(chat_id, sent.id, text);
+    info!("[Congratulator][StandingsBoard] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  fn is_admin(msg: Message, cfg: Arc<CongratulatorConfig>) -> bool {
+    let admins = cfg.admin_chat_ids();
+    admins.contains(&msg.chat.id) || msg.from().is_some_and(|user| admins.contains(&ChatId::from(user.id)))
+  }
+
+  fn is_allowed_chat(msg: Message, cfg: Arc<CongratulatorConfig>) -> bool {
+    cfg.allowed_chat_ids().is_none_or(|allowed| allowed.contains(&msg.chat.id))
+  }
+
+  /// The chat of the message holding the tapped button, the user's private chat if the message is unknown
+  fn is_allowed_callback(query: CallbackQuery, cfg: Arc<CongratulatorConfig>) -> bool {
+    let chat_id = query.message.as_ref().map_or(ChatId::from(query.from.id), |msg| msg.chat.id);
+    cfg.allowed_chat_ids().is_none_or(|allowed| allowed.contains(&chat_id))
+  }
+
+  /// Inline queries aren't bound to a chat, so the user's private chat has to be allowed
+  fn is_allowed_inline(query: InlineQuery, cfg: Arc<CongratulatorConfig>) -> bool {
+    cfg.allowed_chat_ids().is_none_or(|allowed| allowed.contains(&ChatId::from(query.from.id)))
+  }
+
+  async fn chat_not_allowed(bot: Bot, msg: Message) -> CongratulatorHandlerResult {
+    warn!("[Congratulator] Command from a not allowed chat was rejected (chat_id={})", msg.chat.id);
+    bot.send_message(msg.chat.id, "Извините, в этом чате бот недоступен 🔒").await?;
+    Ok(())
+  }
+
+  async fn callback_not_allowed(bot: Bot, query: CallbackQuery) -> CongratulatorHandlerResult {
+    warn!("[Congratulator] Callback from a not allowed chat was rejected (User={:?})", query.from.id);
+    bot
+      .answer_callback_query(query.id)
+      .text("Извините, в этом чате бот недоступен 🔒")
+      .await?;
+    Ok(())
+  }
+
+  async fn inline_not_allowed(bot: Bot, query: InlineQuery) -> CongratulatorHandlerResult {
+    warn!("[Congratulator] Inline query from a not allowed user was rejected (User={:?})", query.from.id);
+    bot.answer_inline_query(query.id, Vec::<InlineQueryResult>::new()).await?;
+    Ok(())
+  }
+
+  async fn not_authorized(bot: Bot, msg: Message) -> CongratulatorHandlerResult {
+    warn!("[Congratulator] Admin command was rejected (chat_id={})", msg.chat.id);
+    bot.send_message(msg.chat.id, "Эта команда доступна только администраторам 🔒").await?;
+    Ok(())
+  }
+
+  async fn broadcast(
+    bot: Bot,
+    msg: Message,
+    dialog: CongratulatorDialogue,
+    text: String,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Broadcast] Start handling Broadcast (chat_id={})", chat_id);
+    let text = text.trim().to_string();
+    if text.is_empty() {
+      bot.send_message(chat_id, "Использование: /broadcast <текст объявления>").await?;
+      return Ok(());
+    }
+
+    let audience = cfg.notify_chat_ids();
+    if audience.len() > cfg.broadcast_confirm_threshold() {
+      debug!("[Congratulator][Broadcast] Audience of {} chats requires confirmation", audience.len());
+      let choices = [
+        InlineKeyboardButton::callback("Отправить", BROADCAST_CONFIRM),
+        InlineKeyboardButton::callback("Отмена", BROADCAST_CANCEL),
+      ];
+      bot
+        .send_message(chat_id, format!("Объявление получат {} чатов. Отправляем?", audience.len()))
+        .reply_markup(InlineKeyboardMarkup::new([choices]))
+        .await?;
+      dialog.update(State::ConfirmBroadcast { text }).await?;
+    } else {
+      Congratulator::send_broadcast(&bot, chat_id, &audience, &text).await?;
+    }
+    info!("[Congratulator][Broadcast] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn receive_broadcast_confirmation(
+    bot: Bot,
+    dialog: CongratulatorDialogue,
+    text: String,
+    callback_query: CallbackQuery,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = dialog.chat_id();
+    bot.answer_callback_query(callback_query.id).await?;
+    dialog.update(State::Default).await?;
+    if callback_query.data.as_deref() == Some(BROADCAST_CONFIRM) {
+      Congratulator::send_broadcast(&bot, chat_id, &cfg.notify_chat_ids(), &text).await?;
+    } else {
+      info!("[Congratulator][Broadcast] Broadcast was cancelled (chat_id={})", chat_id);
+      bot.send_message(chat_id, "Объявление отменено").await?;
+    }
+    Ok(())
+  }
+
+  async fn perf(bot: Bot, msg: Message, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][Perf] Start handling Perf (chat_id={})", chat_id);
+    let fetch = match dashboard.fetch_stats() {
+      Some(stats) => format!(
+        "⏱ Последняя загрузка: {:.2} с\n🔁 Запросов к API: {}",
+        stats.duration.as_secs_f64(),
+        stats.round_trips
+      ),
+      None => "⏱ Данные о последней загрузке отсутствуют".to_string(),
+    };
+    let msg = format!(
+      "{}\n👥 Участников: {}\n🗂 Записей: {}\n💾 Размер в памяти: ~{:.1} КБ",
+      fetch,
+      dashboard.participants().map_or(0, |p| p.len()),
+      dashboard.records_count(),
+      dashboard.estimated_size_bytes() as f64 / 1024.
+    );
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Perf] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn year(
+    bot: Bot,
+    msg: Message,
+    name: String,
+    hub: Arc<AsyncSheetsHub>,
+    cooldown: Arc<YearExportCooldown>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let name = name.trim();
+    info!("[Congratulator][Year] Start handling Year for '{}' (chat_id={})", name, chat_id);
+    if name.is_empty() {
+      bot.send_message(chat_id, "Использование: /year <имя участника>").await?;
+      return Ok(());
+    }
+    if let Err(left) = cooldown.0.try_start() {
+      warn!("[Congratulator][Year] Export is on cooldown for {:?}", left);
+      bot
+        .send_message(chat_id, format!("Выгрузка за год недавно выполнялась, попробуйте через {} с", left.as_secs()))
+        .await?;
+      return Ok(());
+    }
+
+    bot.send_message(chat_id, "Собираю данные за год, это может занять время ⏳").await?;
+    let today = current_time_local().date_naive();
+    let dashboards = Congratulator::fetch_year_dashboards(&hub, &today).await;
+    let mut csv_records = Vec::new();
+    for dashboard in &dashboards {
+      if let Some(table) = dashboard.get_person_by_name(name).and_then(|p| dashboard.find_table(p)) {
+        csv_records.extend(table.all_records());
+      }
+    }
+
+    if csv_records.is_empty() {
+      bot.send_message(chat_id, format!("Данные участника «{}» за год не найдены", name)).await?;
+    } else {
+      let csv = helpers::records_to_csv(&csv_records);
+      let file = InputFile::memory(csv.into_bytes()).file_name(format!("{}_{}.csv", name, today.year()));
+      bot.send_document(chat_id, file).await?;
+    }
+    info!("[Congratulator][Year] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn alltime(
+    bot: Bot,
+    msg: Message,
+    hub: Arc<AsyncSheetsHub>,
+    cache: Arc<AllTimeCache>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][AllTime] Start handling AllTime (chat_id={})", chat_id);
+
+    let cached = cache
+      .0
+      .lock()
+      .unwrap()
+      .as_ref()
+      .filter(|(built_at, _)| built_at.elapsed() < ALLTIME_CACHE_TTL)
+      .map(|(_, text)| text.clone());
+    let text = match cached {
+      Some(text) => {
+        debug!("[Congratulator][AllTime] Using cached summary");
+        text
+      }
+      None => {
+        bot.send_message(chat_id, "Собираю данные за год, это может занять время ⏳").await?;
+        let today = current_time_local().date_naive();
+        let dashboards = Congratulator::fetch_year_dashboards(&hub, &today).await;
+        let totals = participant_totals(&dashboards);
+        let text = helpers::format_alltime_msg(&totals, today.year(), dashboards.len());
+        *cache.0.lock().unwrap() = Some((Instant::now(), text.clone()));
+        text
+      }
+    };
+    bot.send_message(chat_id, text).await?;
+    info!("[Congratulator][AllTime] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn year_overview(
+    bot: Bot,
+    msg: Message,
+    hub: Arc<AsyncSheetsHub>,
+    cfg: Arc<CongratulatorConfig>,
+    cache: Arc<YearOverviewCache>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][YearOverview] Start handling YearOverview (chat_id={})", chat_id);
+
+    let cached = cache
+      .0
+      .lock()
+      .unwrap()
+      .as_ref()
+      .filter(|(built_at, _)| built_at.elapsed() < ALLTIME_CACHE_TTL)
+      .map(|(_, text)| text.clone());
+    let text = match cached {
+      Some(text) => {
+        debug!("[Congratulator][YearOverview] Using cached overview");
+        text
+      }
+      None => {
+        bot.send_message(chat_id, "Собираю данные за год, это может занять время ⏳").await?;
+        let today = current_time_local().date_naive();
+        let months = today.month().min(cfg.year_overview_months());
+        let dashboards = hub.fetch_dashboard_range(months).await;
+        let averages = yearly_averages(&dashboards);
+        let text = helpers::format_year_overview_msg(&averages, today.year(), dashboards.len());
+        *cache.0.lock().unwrap() = Some((Instant::now(), text.clone()));
+        text
+      }
+    };
+    bot.send_message(chat_id, text).await?;
+    info!("[Congratulator][YearOverview] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn verify(bot: Bot, msg: Message, args: String, locked_dashboard: Arc<LockedDashboard>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let dashboard = locked_dashboard.read().await;
+    info!("[Congratulator][Verify] Start handling Verify (chat_id={})", chat_id);
+
+    let records: Vec<_> = dashboard
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| dashboard.last_filled_score_table_record(p).map(|rec| (p, rec)))
+      .collect();
+    let mismatches = records.iter().filter(|(_, rec)| !rec.total_matches()).count();
+    debug!("[Congratulator][Verify] {} of {} latest records mismatch", mismatches, records.len());
+    let all = args.trim().eq_ignore_ascii_case("all");
+    bot.send_message(chat_id, helpers::format_verify_msg(&records, all)).await?;
+    info!("[Congratulator][Verify] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn pause(bot: Bot, msg: Message, duration: String, pause: Arc<PauseSwitch>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Pause] Start handling Pause (chat_id={})", chat_id);
+    let reply = match helpers::parse_duration_spec(&duration).filter(|duration| pause.pause(*duration)) {
+      Some(duration) => {
+        info!("[Congratulator][Pause] Scheduled sends are paused for {:?}", duration);
+        format!(
+          "⏸ Рассылки приостановлены на {}",
+          helpers::format_duration_ru(&chrono::Duration::from_std(duration).unwrap_or_default())
+        )
+      }
+      None => "Использование: /pause <длительность до года>, например /pause 2h или /pause 1h30m".to_string(),
+    };
+    bot.send_message(chat_id, reply).await?;
+    Ok(())
+  }
+
+  async fn resume(bot: Bot, msg: Message, pause: Arc<PauseSwitch>) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Resume] Start handling Resume (chat_id={})", chat_id);
+    let reply = if pause.resume() {
+      "▶️ Рассылки возобновлены"
+    } else {
+      "Рассылки не были приостановлены"
+    };
+    bot.send_message(chat_id, reply).await?;
+    Ok(())
+  }
+
+  async fn status(
+    bot: Bot,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+    task_manager: Arc<TaskManager<'_>>,
+    pause: Arc<PauseSwitch>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][Status] Start handling Status (chat_id={})", chat_id);
+    let sends = match pause.remaining() {
+      Some(left) => format!(
+        "⏸ Рассылки приостановлены, осталось {}",
+        helpers::format_duration_ru(&chrono::Duration::from_std(left).unwrap_or_default())
+      ),
+      None => "▶️ Рассылки активны".to_string(),
+    };
+    let tasks: Vec<_> = [
+      tasks::PeriodcTaskType::Fetcher,
+      tasks::PeriodcTaskType::Notifier,
+      tasks::PeriodcTaskType::Summary,
+    ]
+      .into_iter()
+      .flat_map(|task_type| task_manager.tasks(task_type))
+      .filter(|t| !t.is_finished())
+      .filter_map(|t| t.description())
+      .collect();
+    let (freshness, fetch_duration, participants, skipped) = {
+      let dashboard = locked_dashboard.read().await;
+      let skipped = dashboard
+        .skipped_rows()
+        .into_iter()
+        .map(|(person, rows)| format!("\n⚠️ Не распознано строк у участника {}: {}", person.name(), rows))
+        .collect::<String>();
+      (
+        helpers::format_freshness_msg(dashboard.last_fetched(), &helpers::current_time_utc()),
+        dashboard.fetch_stats().map(|stats| stats.duration),
+        dashboard.participants().map_or(0, |p| p.len()),
+        skipped,
+      )
+    };
+    let fetch_duration = match fetch_duration {
+      Some(duration) => format!("⏱ Длительность загрузки: {:.2} с", duration.as_secs_f64()),
+      None => "⏱ Длительность загрузки неизвестна".to_string(),
+    };
+    let msg = format!(
+      "{}\n{}\n{}\n👥 Участников: {}{}\n🗓 Задания:\n{}",
+      sends,
+      freshness,
+      fetch_duration,
+      participants,
+      skipped,
+      join(tasks, "\n")
+    );
+    bot.send_message(chat_id, msg).await?;
+    info!("[Congratulator][Status] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn diagnose(
+    bot: Bot,
+    msg: Message,
+    name: String,
+    locked_dashboard: Arc<LockedDashboard>,
+    hub: Arc<AsyncSheetsHub>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    let name = name.trim();
+    info!("[Congratulator][Diagnose] Start handling Diagnose for '{}' (chat_id={})", name, chat_id);
+
+    // Blocks are placed in the same order as the participants are parsed
+    let block_index = locked_dashboard
+      .read()
+      .await
+      .participants_names()
+      .and_then(|names| names.iter().position(|n| *n == name));
+    let reply = match block_index {
+      None => format!("Участник «{}» не найден. Использование: /diagnose <имя участника>", name),
+      Some(block_index) => match hub.diagnose_block(block_index).await {
+        Ok((table, report)) if table.person().name() == name => format!("🩺 {} (блок #{})\n{}", name, block_index + 1, report),
+        Ok((table, report)) => format!(
+          "⚠️ В блоке #{} теперь «{}», данные загружены до изменения таблицы\n{}",
+          block_index + 1,
+          table.person().name(),
+          report
+        ),
+        Err(err) => {
+          warn!("[Congratulator][Diagnose] Unable to fetch the block: {}", err);
+          format!("Не удалось загрузить блок участника: {}", err)
+        }
+      },
+    };
+    bot.send_message(chat_id, reply).await?;
+    info!("[Congratulator][Diagnose] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  /// Live dashboard, or the cached one if the fetch fails and the cache is configured
+  async fn initial_dashboard(hub: &AsyncSheetsHub, cache: Option<&DashboardCache>) -> Result<Dashboard, Error> {
+    let fetch_err = match hub.fetch_dashboard().await {
+      Ok(dashboard) => {
+        info!("[Congratulator] Starting with the live dashboard");
+        if let Some(cache) = cache {
+          if let Err(err) = cache.save(&dashboard).await {
+            warn!("[Congratulator] Unable to write the dashboard cache {:?}: {}", cache.path(), err);
+          }
+        }
+        return Ok(dashboard);
+      }
+      Err(err) => err,
+    };
+    let Some(cache) = cache else {
+      return Err(fetch_err.into());
+    };
+    warn!("[Congratulator] Unable to fetch the dashboard ({}), loading the cache {:?}", fetch_err, cache.path());
+    match cache.load(&hub.current_title()).await {
+      Ok(Some(dashboard)) => {
+        warn!("[Congratulator] Starting with the CACHED dashboard, it will be replaced by the next fetch");
+        Ok(dashboard)
+      }
+      Ok(None) => {
+        error!("[Congratulator] Dashboard cache is not used since it holds another month's sheet");
+        Err(fetch_err.into())
+      }
+      Err(err) => {
+        error!("[Congratulator] Unable to load the dashboard cache: {}", err);
+        Err(fetch_err.into())
+      }
+    }
+  }
+
+  /// Dashboards of every month sheet of the current year up to today, missing sheets are skipped
+  async fn fetch_year_dashboards(hub: &AsyncSheetsHub, today: &NaiveDate) -> Vec<Dashboard> {
+    let months = hub.fetch_dashboard_range(today.month()).await;
+    months.into_iter().map(|(_, dashboard)| dashboard).collect()
+  }
+
+  async fn layout(bot: Bot, msg: Message, hub: Arc<AsyncSheetsHub>) -> CongratulatorHandlerResult {
+    info!("[Congratulator] Sending layout to chat_id={}", msg.chat.id);
+    bot.send_message(msg.chat.id, hub.layout().to_string()).await?;
+    Ok(())
+  }
+
+  async fn send_broadcast(bot: &Bot, reply_to: ChatId, audience: &[ChatId], text: &str) -> CongratulatorHandlerResult {
+    let announcement = format!("📢 *Объявление*\n\n{}", helpers::escape_markdown_v2(text));
+    let report = broadcast::broadcast(bot, audience, &announcement, Some(ParseMode::MarkdownV2)).await;
+    info!("[Congratulator][Broadcast] Broadcast finished: {:?}", report);
+    bot.send_message(reply_to, format!("Объявление разослано ({})", report)).await?;
+    Ok(())
+  }
+
+  /// Replaces an aliased command in the message text with the canonical one before command parsing
+  fn apply_command_alias(mut msg: Message, aliases: CommandAliases) -> Message {
+    if let MessageKind::Common(MessageCommon {
+      media_kind: MediaKind::Text(media),
+      ..
+    }) = &mut msg.kind
+    {
+      if let Some(text) = helpers::apply_command_alias(&media.text, &aliases.0) {
+        debug!("[Congratulator] Aliased command '{}' was rewritten to '{}'", media.text, text);
+        media.text = text;
+      }
+    }
+    msg
+  }
+
+  /// Looks up participants by a part of the name from any chat (`@bot Alice`).
+  /// Inline mode must be enabled for the bot in BotFather.
+  async fn inline_query(
+    bot: Bot,
+    query: InlineQuery,
+    locked_dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
+  ) -> CongratulatorHandlerResult {
+    let dashboard = locked_dashboard.read().await;
+    let pattern = query.query.trim().to_lowercase();
+    info!("[Congratulator][InlineQuery] Handling query '{}' from User={:?}", pattern, query.from.id);
+
+    // Inline results get into any chat, so with anonymization only the user's own participant is shown
+    let anonymize = cfg.anonymize_month_table();
+    let usernames = cfg.telegram_usernames();
+    let is_own = |person: &Person| match (query.from.username.as_deref(), usernames.get(person.name())) {
+      (Some(own), Some(mapped)) => mapped.trim_start_matches('@').eq_ignore_ascii_case(own),
+      _ => false,
+    };
+    let results: Vec<InlineQueryResult> = dashboard
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|p| !anonymize || is_own(p))
+      .filter(|p| p.name().to_lowercase().contains(&pattern))
+      .take(INLINE_RESULTS_LIMIT)
+      .enumerate()
+      .map(|(i, person)| {
+        let (text, description) = match dashboard.last_filled_score_table_record(person) {
+          Some(record) => (
+            helpers::format_user_score_msg(record, person),
+            format!("{} {}", record.percent(), record.percent().emoji()),
+          ),
+          None => (helpers::format_no_filled_days_msg(person), "нет заполненных дней".to_string()),
+        };
+        let content = InputMessageContent::Text(InputMessageContentText::new(text).parse_mode(ParseMode::MarkdownV2));
+        let article = InlineQueryResultArticle::new(i.to_string(), person.display_name(), content);
+        InlineQueryResult::Article(article.description(description))
+      })
+      .collect();
+
+    debug!("[Congratulator][InlineQuery] Found {} matching participant(s)", results.len());
+    bot.answer_inline_query(query.id, results).cache_time(60).await?;
+    Ok(())
+  }
+
+  /// Shows the participant's record for the date entered after the "another day" button
+  async fn receive_date(
+    bot: Bot,
+    dialog: CongratulatorDialogue,
+    person: String,
+    msg: Message,
+    locked_dashboard: Arc<LockedDashboard>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = msg.chat.id;
+    info!("[Congratulator][ReceiveDate] Start handling date for '{}' (chat_id={})", person, chat_id);
+    let Some(date) = msg.text().and_then(|text| NaiveDate::parse_from_str(text.trim(), "%d.%m.%Y").ok()) else {
+      // The dialogue stays in the same state, so the date can be entered again
+      bot.send_message(chat_id, "Не удалось разобрать дату, введите ее в формате ДД.ММ.ГГГГ").await?;
+      return Ok(());
+    };
+    dialog.update(State::Default).await?;
+    let text = {
+      let dashboard = locked_dashboard.read().await;
+      match dashboard.record_on(&person, &date) {
+        Ok((found, record)) => helpers::format_user_score_msg(record, found),
+        Err(DashboardError::NoRecordForDate(date)) => {
+          helpers::escape_markdown_v2(&format!("У «{}» нет результата за {}", person, date.format("%d.%m.%Y")))
+        }
+        Err(DashboardError::PersonNotFound(name)) => {
+          warn!("[Congratulator][ReceiveDate] Person '{}' was not found", name);
+          helpers::escape_markdown_v2(&format!("Участник «{}» не найден 🤷", name))
+        }
+        Err(err) => {
+          warn!("[Congratulator][ReceiveDate] {}", err);
+          helpers::escape_markdown_v2("Данные таблицы еще не загружены, попробуйте позже ⏳")
+        }
+      }
+    };
+    broadcast::send_markdown(&bot, chat_id, &text).await?;
+    info!("[Congratulator][ReceiveDate] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  async fn unhandled_message(_bot: Bot, msg: Message) -> CongratulatorHandlerResult {
+    warn!("Called unhandled_message() callback with msg={:?}", msg);
+    Ok(())
+  }
+
+  async fn my_chat_member_update_handler(bot: Bot, update: ChatMemberUpdated) -> CongratulatorHandlerResult {
+    let ChatMemberUpdated {
+      chat,
+      from,
+      new_chat_member,
+      ..
+    } = &update;
+    let chat_id = chat.id;
+
+    info!("[Congratulator][ChatMemberUpdated] Start handling update (chat_id={})", chat_id);
+    let msg = if new_chat_member.is_member() {
+      format!("Благодаря *{}* я теперь в этом чатике\\. Большое спасибо\\!", from.full_name())
+    } else {
+      debug!("[Congratulator][ChatMemberUpdated] The update was unhandled {:?}", update);
+      return Ok(());
+    };
+
+    bot.send_message(chat_id, msg).parse_mode(ParseMode::MarkdownV2).await?;
+    Ok(())
+  }
+
+  async fn receive_user_selected(
+    bot: Bot,
+    dialog: CongratulatorDialogue,
+    callback_query: CallbackQuery,
+    locked_dashboard: Arc<LockedDashboard>,
+  ) -> CongratulatorHandlerResult {
+    let chat_id = dialog.chat_id();
+    let dashboard = locked_dashboard.read().await;
+    info!(
+      "[Congratulator][ReceiveSelectedUser] Handling state from User={:?} (chat_id={})",
+      callback_query.from, chat_id
+    );
+
+    let callback_data: String = callback_query.data.ok_or_else(|| {
+      error!("[Congratulator][ReceiveSelectedUser] Received None in callback data");
+      Error::EmptyCallbackData
+    })?;
+
+    // The dashboard may have been refreshed without the person since the keyboard was sent
+    let person = match Congratulator::selected_person(&dashboard, &callback_data) {
+      Ok(person) => person,
+      Err(Error::PersonNotFound) => {
+        bot.answer_callback_query(callback_query.id).send().await?;
+        bot.send_message(chat_id, STALE_PERSON_MSG).await?;
+        info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+        return Ok(());
+      }
+      Err(err) => return Err(err.into()),
+    };
+
+    // The month stats button is attached to the selected person's scores
+    if callback_data.starts_with(PERSON_STATS_PREFIX) {
+      let text = Congratulator::monthly_stats_text(&dashboard, person.name())?;
+      broadcast::send_markdown(&bot, chat_id, &text).await?;
+      bot.answer_callback_query(callback_query.id).send().await?;
+      info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+      return Ok(());
+    }
+    if callback_data.starts_with(PERSON_DATE_PREFIX) {
+      bot
+        .send_message(chat_id, format!("За какой день показать результат «{}»? Введите дату ДД.ММ.ГГГГ", person.name()))
+        .await?;
+      dialog.update(State::AwaitingDate { person: person.name().to_string() }).await?;
+      bot.answer_callback_query(callback_query.id).send().await?;
+      info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+      return Ok(());
+    }
+    if callback_data.starts_with(PERSON_DATES_PREFIX) {
+      let text = helpers::format_filled_dates_msg(&dashboard.filled_dates(person), person);
+      broadcast::send_markdown(&bot, chat_id, &text).await?;
+      bot.answer_callback_query(callback_query.id).send().await?;
+      info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+      return Ok(());
+    }
+    if callback_data.starts_with(COMPARE_PREFIX) {
+      let first = person;
+      let choices: Vec<_> = dashboard
+        .participants()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|person| *person != first)
+        .map(|person| InlineKeyboardButton::callback(person.name(), person.name()))
+        .collect();
+      bot
+        .send_message(chat_id, format!("С кем сравнить «{}»?", first.name()))
+        .reply_markup(InlineKeyboardMarkup::new([choices]))
+        .await?;
+      dialog.update(State::AwaitingSecondPerson { first: first.name().to_string() }).await?;
+      bot.answer_callback_query(callback_query.id).send().await?;
+      info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+      return Ok(());
+    }
+    if callback_data.starts_with(PERSON_CSV_PREFIX) {
+      let table = dashboard.find_table(person).ok_or_else(|| {
+        error!("[Congratulator][ReceiveSelectedUser] Table of '{}' was not found", person.name());
+        Error::PersonNotFound
+      })?;
+      let file =
+        InputFile::memory(helpers::person_table_to_csv(table).into_bytes()).file_name(format!("{}.csv", person.name()));
+      bot.send_document(chat_id, file).await?;
+      bot.answer_callback_query(callback_query.id).send().await?;
+      info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+      return Ok(());
+    }
+
+    debug!("[Congratulator][ReceiveSelectedUser] Selected person = {:?}", person);
+    match dashboard.last_filled_score_table_record(person) {
+      Some(last_record) => {
+        trace!("[Congratulator][ReceiveSelectedUser] Found {:?}", last_record);
+        let mut text = helpers::format_user_score_msg(last_record, person);
+        let percents: Vec<i32> = dashboard
+          .last_filled_score_table_records(person, SPARKLINE_DAYS)
+          .iter()
+          .map(|rec| rec.percent().value())
+          .collect();
+        if let Some(line) = helpers::sparkline(&percents) {
+          text.push_str(&format!("📈 *Тренд*: {}\n", line));
+        }
+        let stats_button = InlineKeyboardButton::callback(
+          "Показать статистику за месяц",
+          format!("{}{}", PERSON_STATS_PREFIX, person.name()),
+        );
+        let date_button =
+          InlineKeyboardButton::callback("Показать другой день", format!("{}{}", PERSON_DATE_PREFIX, person.name()));
+        let dates_button = InlineKeyboardButton::callback(
+          "Показать все заполненные дни",
+          format!("{}{}", PERSON_DATES_PREFIX, person.name()),
+        );
+        let csv_button = InlineKeyboardButton::callback("Выгрузить CSV", format!("{}{}", PERSON_CSV_PREFIX, person.name()));
+        let keyboard = InlineKeyboardMarkup::new([[stats_button], [date_button], [dates_button], [csv_button]]);
+        broadcast::send_markdown_with_keyboard(&bot, chat_id, &text, keyboard).await?;
+      }
+      None => {
+        warn!(
+          "[Congratulator][ReceiveSelectedUser] Last score record was not found for {:?}",
+          person
+        );
+        broadcast::send_markdown(&bot, chat_id, &helpers::format_no_filled_days_msg(person)).await?;
+      }
+    }
+
+    bot.answer_callback_query(callback_query.id).send().await?;
+    info!("[Congratulator][ReceiveSelectedUser] Finished handling (chat_id={})", chat_id);
+    Ok(())
+  }
+
+  fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use dptree::case;
+
+    // Help and dice don't expose any data, so they are open to every chat
+    let command_handler = teloxide::filter_command::<Command, _>()
+      .branch(case![Command::Help].endpoint(Congratulator::help))
+      .branch(case![Command::Dice].endpoint(Congratulator::dice))
+      .branch(
+        dptree::filter(Congratulator::is_allowed_chat)
+          .branch(case![Command::Participants].endpoint(Congratulator::participants))
+          .branch(case![Command::Scores].endpoint(Congratulator::scores))
+          .branch(case![Command::TodaySummary].endpoint(Congratulator::today_summary))
+          .branch(case![Command::YesterdaySummary].endpoint(Congratulator::yesterday_summary))
+          .branch(case![Command::EnabledNotifications].endpoint(Congratulator::show_enabled_notifications))
+          .branch(case![Command::Schedule].endpoint(Congratulator::schedule))
+          .branch(case![Command::Missing].endpoint(Congratulator::missing))
+          .branch(case![Command::Deadline].endpoint(Congratulator::deadline))
+          .branch(case![Command::RankHistory(name)].endpoint(Congratulator::rank_history))
+          .branch(case![Command::ReachGoal(args)].endpoint(Congratulator::reach_goal))
+          .branch(case![Command::Participation].endpoint(Congratulator::participation))
+          .branch(case![Command::Improvers].endpoint(Congratulator::improvers))
+          .branch(case![Command::GroupCategories].endpoint(Congratulator::group_categories))
+          .branch(case![Command::CategoryLeaders].endpoint(Congratulator::category_leaders))
+          .branch(case![Command::Now].endpoint(Congratulator::now))
+          .branch(case![Command::WhatIf(args)].endpoint(Congratulator::what_if))
+          .branch(case![Command::MostConsistent].endpoint(Congratulator::most_consistent))
+          .branch(case![Command::Trends].endpoint(Congratulator::trends))
+          .branch(case![Command::PickWinner].endpoint(Congratulator::pick_winner))
+          .branch(case![Command::YearOverview].endpoint(Congratulator::year_overview))
+          .branch(case![Command::Leaderboard(date)].endpoint(Congratulator::leaderboard))
+          .branch(case![Command::PersonStats(name)].endpoint(Congratulator::person_stats))
+          .branch(case![Command::ScoreOn].endpoint(Congratulator::score_on))
+          .branch(case![Command::CompareTwo].endpoint(Congratulator::compare_two))
+          .branch(case![Command::ExportJson].endpoint(Congratulator::export_json))
+          .branch(case![Command::Refresh].endpoint(Congratulator::refresh))
+          .branch(case![Command::Streaks].endpoint(Congratulator::streaks))
+          .branch(case![Command::Records].endpoint(Congratulator::records))
+          .branch(case![Command::MonthTable].endpoint(Congratulator::month_table))
+          .branch(case![Command::Watch(name)].endpoint(Congratulator::watch))
+          .branch(case![Command::Unwatch(name)].endpoint(Congratulator::unwatch)),
+      )
+      .branch(dptree::endpoint(Congratulator::chat_not_allowed));
+
+    let admin_command_handler = teloxide::filter_command::<AdminCommand, _>()
+      .branch(
+        dptree::filter(Congratulator::is_admin)
+          .branch(case![AdminCommand::Broadcast(text)].endpoint(Congratulator::broadcast))
+          .branch(case![AdminCommand::Perf].endpoint(Congratulator::perf))
+          .branch(case![AdminCommand::Year(name)].endpoint(Congratulator::year))
+          .branch(case![AdminCommand::Layout].endpoint(Congratulator::layout))
+          .branch(case![AdminCommand::Note(args)].endpoint(Congratulator::note))
+          .branch(case![AdminCommand::StandingsBoard(args)].endpoint(Congratulator::standings_board))
+          .branch(case![AdminCommand::AllTime].endpoint(Congratulator::alltime))
+          .branch(case![AdminCommand::Verify(args)].endpoint(Congratulator::verify))
+          .branch(case![AdminCommand::Pause(duration)].endpoint(Congratulator::pause))
+          .branch(case![AdminCommand::Resume].endpoint(Congratulator::resume))
+          .branch(case![AdminCommand::Status].endpoint(Congratulator::status))
+          .branch(case![AdminCommand::Diagnose(name)].endpoint(Congratulator::diagnose)),
+      )
+      .branch(dptree::endpoint(Congratulator::not_authorized));
+
+    let updates_handler = Update::filter_my_chat_member().branch(dptree::endpoint(Congratulator::my_chat_member_update_handler));
+
+    let message_handler = Update::filter_message()
+      .map(Congratulator::apply_command_alias)
+      .branch(command_handler)
+      .branch(admin_command_handler)
+      .branch(
+        dptree::filter(Congratulator::is_allowed_chat)
+          .branch(case![State::AwaitingDate { person }].endpoint(Congratulator::receive_date)),
+      )
+      .branch(dptree::endpoint(Congratulator::unhandled_message));
+
+    let callback_query_handler = Update::filter_callback_query()
+      .branch(
+        dptree::filter(|query: CallbackQuery, cfg: Arc<CongratulatorConfig>| !Congratulator::is_allowed_callback(query, cfg))
+          .endpoint(Congratulator::callback_not_allowed),
+      )
+      .branch(case![State::Default].endpoint(Congratulator::receive_user_selected))
+      .branch(case![State::ConfirmBroadcast { text }].endpoint(Congratulator::receive_broadcast_confirmation))
+      .branch(case![State::AwaitingSecondPerson { first }].endpoint(Congratulator::receive_compare_second));
+
+    // Inline queries are not bound to a chat, so they are handled outside of the dialogue
+    let inline_query_handler = Update::filter_inline_query()
+      .branch(dptree::filter(Congratulator::is_allowed_inline).endpoint(Congratulator::inline_query))
+      .branch(dptree::endpoint(Congratulator::inline_not_allowed));
+
+    let dialogue_handler = dialogue::enter::<Update, CongratulatorStorage, State, _>()
+      .branch(updates_handler)
+      .branch(message_handler)
+      .branch(callback_query_handler);
+
+    dptree::entry().branch(inline_query_handler).branch(dialogue_handler)
+  }
+}
+
+impl<'a> Drop for Congratulator<'a> {
+  fn drop(&mut self) {
+    debug!("[Congratulator] Dropping ...");
+    self.task_manager.finalize_tasks();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::ops::ControlFlow;
+
+  use chrono::{TimeZone, Utc};
+  use serde_json::json;
+
+  use super::*;
+  use crate::{
+    bot::fake::FakeTelegram,
+    dashboard::{
+      fake::{date, record, table},
+      score_table::ScoreTable,
+    },
+    helpers::clock::{set_clock, FixedClock},
+  };
+
+  const ALLOWED: i64 = 100;
+  const OTHER: i64 = 200;
+
+  fn user(id: i64) -> serde_json::Value {
+    json!({ "id": id, "is_bot": false, "first_name": "Анна" })
+  }
+
+  fn message(chat_id: i64, text: &str) -> serde_json::Value {
+    json!({
+      "message_id": 1,
+      "date": 0,
+      "chat": { "id": chat_id, "type": "private", "first_name": "Анна" },
+      "from": user(chat_id),
+      "text": text,
+    })
+  }
+
+  fn update(kind: &str, payload: serde_json::Value) -> Update {
+    // Update kinds are parsed from borrowed strings only, so the value is serialized first
+    serde_json::from_str(&json!({ "update_id": 1, (kind): payload }).to_string()).unwrap()
+  }
+
+  fn callback(chat_id: i64) -> Update {
+    callback_with(chat_id, "Анна")
+  }
+
+  fn callback_with(chat_id: i64, data: &str) -> Update {
+    update(
+      "callback_query",
+      json!({ "id": "1", "from": user(chat_id), "message": message(chat_id, "Выберите"), "chat_instance": "1", "data": data }),
+    )
+  }
+
+  fn inline(user_id: i64) -> Update {
+    update("inline_query", json!({ "id": "1", "from": user(user_id), "query": "", "offset": "" }))
+  }
+
+  const NOT_ALLOWED: &str = "Извините, в этом чате бот недоступен 🔒";
+
+  /// Bot API calls made by the whole schema while handling the update, with or without the allowlist.
+  /// Inline results aren't anonymized, so that an empty answer means the rejection.
+  async fn calls(restricted: bool, upd: Update) -> Vec<(String, serde_json::Value)> {
+    let mut cfg = json!({ "anonymize_month_table": false });
+    if restricted {
+      cfg["allowed_chat_ids"] = json!(ALLOWED.to_string());
+    }
+    let chat = DialogueChat::with_config(cfg);
+    chat.dispatch(upd).await;
+    chat.telegram.take_calls()
+  }
+
+  /// Whether the reply tells the chat it is not allowed to use the bot
+  fn rejected(calls: &[(String, serde_json::Value)]) -> bool {
+    calls.iter().any(|(method, payload)| match method.as_str() {
+      "AnswerInlineQuery" => payload["results"].as_array().is_some_and(|results| results.is_empty()),
+      _ => payload["text"] == NOT_ALLOWED,
+    })
+  }
+
+  #[tokio::test]
+  async fn allowed_chats_pass_every_gate() {
+    assert!(!rejected(&calls(true, update("message", message(ALLOWED, "/participants"))).await));
+    assert!(!rejected(&calls(true, callback(ALLOWED)).await));
+    assert!(!rejected(&calls(true, inline(ALLOWED)).await));
+  }
+
+  #[tokio::test]
+  async fn other_chats_are_rejected_by_every_gate() {
+    let reply = calls(true, update("message", message(OTHER, "/participants"))).await;
+    assert_eq!(reply.len(), 1);
+    assert_eq!((reply[0].0.as_str(), &reply[0].1["text"]), ("SendMessage", &json!(NOT_ALLOWED)));
+    assert!(rejected(&calls(true, callback(OTHER)).await));
+    assert!(rejected(&calls(true, inline(OTHER)).await));
+  }
+
+  #[tokio::test]
+  async fn every_chat_passes_without_allowlist() {
+    assert!(!rejected(&calls(false, update("message", message(OTHER, "/participants"))).await));
+    assert!(!rejected(&calls(false, callback(OTHER)).await));
+    assert!(!rejected(&calls(false, inline(OTHER)).await));
+  }
+
+  fn dashboard_of(names: &[&str]) -> Dashboard {
+    Dashboard::from(names.iter().map(|name| ScoreTable::new(Person::new(name.to_string()), Vec::new())).collect())
+  }
+
+  #[test]
+  fn selected_person_is_found_with_and_without_prefix() {
+    let dashboard = dashboard_of(&["Анна", "Борис"]);
+    assert_eq!(Congratulator::selected_person(&dashboard, "Анна").unwrap().name(), "Анна");
+    assert_eq!(Congratulator::selected_person(&dashboard, "csv:Борис").unwrap().name(), "Борис");
+  }
+
+  #[test]
+  fn stale_selected_person_is_not_found() {
+    // The keyboard was rendered with "Вера", then the refreshed dashboard dropped her
+    let dashboard = dashboard_of(&["Анна"]);
+    for data in ["Вера", "stats:Вера", "cmp:Вера"] {
+      assert!(matches!(Congratulator::selected_person(&dashboard, data), Err(Error::PersonNotFound)));
+    }
+  }
+
+  #[test]
+  fn anonymized_month_table_caption_has_no_names() {
+    let names = vec!["Анна".to_string(), "Борис".to_string()];
+    assert_eq!(Congratulator::month_table_caption("Январь 24", None), "🗓 Январь 24");
+    assert_eq!(
+      Congratulator::month_table_caption("Январь 24", Some(&names)),
+      "🗓 Январь 24\n1 — Анна\n2 — Борис"
+    );
+  }
+
+  /// Chat with the whole schema, the bot talks to the fake Bot API
+  struct DialogueChat {
+    telegram: FakeTelegram,
+    storage: Arc<CongratulatorStorage>,
+    dashboard: Arc<LockedDashboard>,
+    cfg: Arc<CongratulatorConfig>,
+  }
+
+  impl DialogueChat {
+    fn new() -> Self {
+      DialogueChat::with_config(json!({}))
+    }
+
+    fn with_config(cfg: serde_json::Value) -> Self {
+      let dashboard = Dashboard::from(vec![
+        table("Анна", vec![record(date(2024, 1, 15), 70)]),
+        table("Борис", vec![record(date(2024, 1, 15), 40)]),
+      ]);
+      Self {
+        telegram: FakeTelegram::start(),
+        storage: InMemStorage::<State>::new().erase(),
+        dashboard: Arc::new(RwLock::new(dashboard)),
+        cfg: Arc::new(CongratulatorConfig::for_tests(cfg)),
+      }
+    }
+
+    /// Methods of the Bot API called while handling the update
+    async fn send(&self, upd: Update) -> Vec<String> {
+      self.dispatch(upd).await;
+      self.telegram.take_methods()
+    }
+
+    /// Texts of the messages sent while handling the update
+    async fn send_for_texts(&self, upd: Update) -> Vec<String> {
+      self.dispatch(upd).await;
+      self.telegram.take_texts()
+    }
+
+    async fn dispatch(&self, upd: Update) {
+      let cfg = self.cfg.clone();
+      let me: teloxide::types::Me = serde_json::from_value(json!({
+        "id": 1, "is_bot": true, "first_name": "Бот", "username": "test_bot",
+        "can_join_groups": true, "can_read_all_group_messages": false, "supports_inline_queries": true,
+      }))
+      .unwrap();
+      let aliases = CommandAliases::new(cfg.command_aliases());
+      let deps = dptree::deps![self.telegram.bot(), self.storage.clone(), self.dashboard.clone(), cfg, aliases, me, upd];
+      let result = Congratulator::schema().dispatch(deps).await;
+      match result {
+        ControlFlow::Break(Ok(())) => {}
+        ControlFlow::Break(Err(err)) => panic!("{err:?}"),
+        ControlFlow::Continue(_) => panic!("the update isn't handled"),
+      }
+    }
+
+    async fn state(&self) -> Option<State> {
+      self.storage.clone().get_dialogue(ChatId(ALLOWED)).await.unwrap()
+    }
+  }
+
+  #[tokio::test]
+  async fn date_dialogue_waits_for_a_valid_date() {
+    let chat = DialogueChat::new();
+
+    let methods = chat.send(callback_with(ALLOWED, &format!("{PERSON_DATE_PREFIX}Анна"))).await;
+    assert_eq!(methods, ["SendMessage", "AnswerCallbackQuery"]);
+    assert!(matches!(chat.state().await, Some(State::AwaitingDate { person }) if person == "Анна"));
+
+    chat.send(update("message", message(ALLOWED, "вчера"))).await;
+    assert!(matches!(chat.state().await, Some(State::AwaitingDate { .. })), "unparsed date keeps the dialogue waiting for a date");
+
+    assert_eq!(chat.send(update("message", message(ALLOWED, "15.01.2024"))).await, ["SendMessage"]);
+    assert!(matches!(chat.state().await, Some(State::Default) | None));
+  }
+
+  #[tokio::test]
+  async fn compare_dialogue_waits_for_the_second_person() {
+    let chat = DialogueChat::new();
+
+    chat.send(callback_with(ALLOWED, &format!("{COMPARE_PREFIX}Анна"))).await;
+    assert!(matches!(chat.state().await, Some(State::AwaitingSecondPerson { first }) if first == "Анна"));
+
+    let methods = chat.send(callback_with(ALLOWED, "Борис")).await;
+    assert_eq!(methods, ["AnswerCallbackQuery", "SendMessage"]);
+    assert!(matches!(chat.state().await, Some(State::Default) | None));
+  }
+
+  #[tokio::test]
+  async fn deadline_tells_the_remaining_time_and_then_who_missed() {
+    let chat = DialogueChat::with_config(json!({ "fill_deadline": "22:00" }));
+    let deadline = || update("message", message(ALLOWED, "/deadline"));
+
+    // 19:45 of January 16th for the participants (default offset is +3)
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 16, 16, 45, 30).unwrap())));
+    let texts = chat.send_for_texts(deadline()).await;
+    assert_eq!(texts, ["⏳ До дедлайна (22:00) осталось 2 ч 14 мин, не забудьте заполнить таблицу 📋"]);
+
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 16, 19, 30, 0).unwrap())));
+    let texts = chat.send_for_texts(deadline()).await;
+    assert_eq!(texts, ["⌛ Дедлайн (22:00) прошел. Не заполнили таблицу:\nАнна\nБорис"]);
+  }
+
+  #[test]
+  fn alias_of_an_existing_command_is_ignored() {
+    let aliases = CommandAliases::new(HashMap::from([
+      ("help".to_string(), "dice".to_string()),
+      ("dl".to_string(), "deadline".to_string()),
+      ("typo".to_string(), "dedline".to_string()),
+    ]));
+    assert_eq!(aliases.0.len(), 2);
+    assert!(!aliases.0.contains_key("help"));
+  }
+
+  #[tokio::test]
+  async fn aliased_command_is_handled_as_the_canonical_one() {
+    let chat = DialogueChat::with_config(json!({ "command_aliases": { "dl": "deadline" } }));
+    let texts = chat.send_for_texts(update("message", message(ALLOWED, "/dl"))).await;
+    assert_eq!(texts, ["Дедлайн заполнения таблицы не настроен 🤷"]);
+  }
+
+  /// Titles of the inline results answered to the query of the user with the username
+  async fn inline_titles(chat: &DialogueChat, username: &str) -> Vec<String> {
+    let from = json!({ "id": ALLOWED, "is_bot": false, "first_name": "Анна", "username": username });
+    chat.dispatch(update("inline_query", json!({ "id": "1", "from": from, "query": "", "offset": "" }))).await;
+    let calls = chat.telegram.take_calls();
+    let (_, answer) = calls.iter().find(|(method, _)| method == "AnswerInlineQuery").unwrap();
+    let titles = answer["results"].as_array().unwrap().iter().map(|r| r["title"].as_str().unwrap().to_string());
+    titles.collect()
+  }
+
+  #[tokio::test]
+  async fn anonymized_inline_query_shows_only_own_participant() {
+    let chat = DialogueChat::with_config(json!({ "telegram_usernames": { "Анна": "@anna_u" } }));
+    assert_eq!(inline_titles(&chat, "Anna_U").await, ["Анна"]);
+    assert!(inline_titles(&chat, "stranger").await.is_empty());
+
+    let open = DialogueChat::with_config(json!({ "anonymize_month_table": false }));
+    assert_eq!(inline_titles(&open, "stranger").await, ["Анна", "Борис"]);
+  }
+
+  #[tokio::test]
+  async fn inline_results_are_capped() {
+    let chat = DialogueChat::with_config(json!({ "anonymize_month_table": false }));
+    let names: Vec<String> = (0..60).map(|i| format!("Участник {i}")).collect();
+    *chat.dashboard.write().await = dashboard_of(&names.iter().map(String::as_str).collect::<Vec<_>>());
+    assert_eq!(inline_titles(&chat, "stranger").await.len(), INLINE_RESULTS_LIMIT);
   }
 }
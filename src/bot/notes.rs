@@ -0,0 +1,85 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::NaiveDate;
+use log::{info, warn};
+use tokio::sync::RwLock;
+
+use super::storage::{self, JsonFileStorageError};
+
+/// Manual notes attached to the dates by admins, independent of the sheet data.
+/// Persisted to the JSON file if it's configured, otherwise kept in memory only.
+pub struct DayNotes {
+  file: Option<PathBuf>,
+  notes: RwLock<BTreeMap<NaiveDate, String>>,
+}
+
+impl DayNotes {
+  pub async fn load(file: Option<&str>) -> Result<Self, JsonFileStorageError> {
+    let file = file.map(PathBuf::from);
+    let notes = match &file {
+      Some(path) => storage::load_json(path).await?,
+      None => BTreeMap::new(),
+    };
+    info!("[DayNotes] Loaded {} note(s)", notes.len());
+    Ok(Self {
+      file,
+      notes: RwLock::new(notes),
+    })
+  }
+
+  pub async fn get(&self, date: &NaiveDate) -> Option<String> {
+    self.notes.read().await.get(date).cloned()
+  }
+
+  pub async fn set(&self, date: NaiveDate, text: String) -> Result<(), JsonFileStorageError> {
+    let mut notes = self.notes.write().await;
+    notes.insert(date, text);
+    self.persist(&notes).await
+  }
+
+  /// Returns `false` if there was no note for the date
+  pub async fn clear(&self, date: &NaiveDate) -> Result<bool, JsonFileStorageError> {
+    let mut notes = self.notes.write().await;
+    if notes.remove(date).is_none() {
+      return Ok(false);
+    }
+    self.persist(&notes).await?;
+    Ok(true)
+  }
+
+  async fn persist(&self, notes: &BTreeMap<NaiveDate, String>) -> Result<(), JsonFileStorageError> {
+    match &self.file {
+      Some(path) => storage::save_json(path, notes).await,
+      None => {
+        warn!("[DayNotes] Notes file is not configured, the note will be lost on restart");
+        Ok(())
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::storage::tests::temp_path;
+
+  #[tokio::test]
+  async fn notes_are_set_overwritten_and_cleared() {
+    let path = temp_path("day-notes");
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let notes = DayNotes::load(path.to_str()).await.unwrap();
+    assert_eq!(notes.get(&date).await, None);
+
+    notes.set(date, "Субботник".to_string()).await.unwrap();
+    notes.set(date, "Праздник".to_string()).await.unwrap();
+    assert_eq!(notes.get(&date).await.as_deref(), Some("Праздник"));
+
+    let reloaded = DayNotes::load(path.to_str()).await.unwrap();
+    assert_eq!(reloaded.get(&date).await.as_deref(), Some("Праздник"));
+
+    assert!(reloaded.clear(&date).await.unwrap());
+    assert!(!reloaded.clear(&date).await.unwrap());
+    let reloaded = DayNotes::load(path.to_str()).await.unwrap();
+    assert_eq!(reloaded.get(&date).await, None);
+  }
+}
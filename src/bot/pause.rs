@@ -0,0 +1,76 @@
+use std::{
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+/// Suppresses scheduled sends until the moment set by an operator, the tasks themselves keep running
+#[derive(Default)]
+pub struct PauseSwitch {
+  until: Mutex<Option<Instant>>,
+}
+
+impl PauseSwitch {
+  /// Returns `false` if the duration is too long to be represented, the current pause is kept then
+  pub fn pause(&self, duration: Duration) -> bool {
+    let Some(until) = Instant::now().checked_add(duration) else {
+      return false;
+    };
+    *self.until.lock().unwrap() = Some(until);
+    true
+  }
+
+  /// Returns `false` if the sends weren't paused
+  pub fn resume(&self) -> bool {
+    self.until.lock().unwrap().take().is_some_and(|until| until > Instant::now())
+  }
+
+  /// Time left until the sends are resumed automatically, `None` if not paused
+  pub fn remaining(&self) -> Option<Duration> {
+    self
+      .until
+      .lock()
+      .unwrap()
+      .and_then(|until| until.checked_duration_since(Instant::now()))
+      .filter(|left| !left.is_zero())
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.remaining().is_some()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pause_suppresses_until_resumed() {
+    let pause = PauseSwitch::default();
+    assert!(!pause.is_paused());
+    assert!(!pause.resume());
+
+    assert!(pause.pause(Duration::from_secs(60 * 60)));
+    assert!(pause.is_paused());
+    assert!(pause.remaining().is_some_and(|left| left > Duration::from_secs(59 * 60)));
+    assert!(pause.resume());
+    assert!(!pause.is_paused());
+  }
+
+  #[test]
+  fn pause_expires() {
+    let pause = PauseSwitch::default();
+    assert!(pause.pause(Duration::from_millis(20)));
+    std::thread::sleep(Duration::from_millis(40));
+
+    assert!(!pause.is_paused());
+    assert_eq!(pause.remaining(), None);
+    assert!(!pause.resume());
+  }
+
+  #[test]
+  fn unrepresentable_pause_is_refused() {
+    let pause = PauseSwitch::default();
+    assert!(!pause.pause(Duration::MAX));
+    assert!(!pause.is_paused());
+  }
+}
@@ -0,0 +1,174 @@
+use std::{
+  collections::HashMap,
+  io::ErrorKind,
+  marker::PhantomData,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+  },
+};
+
+use futures::future::BoxFuture;
+use log::{debug, info};
+use serde::{de::DeserializeOwned, Serialize};
+use teloxide::{dispatching::dialogue::Storage, types::ChatId};
+use thiserror::Error;
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+
+#[derive(Debug, Error)]
+pub enum JsonFileStorageError {
+  #[error("Unable to access JSON storage file: {0}")]
+  Io(#[from] std::io::Error),
+  #[error("Unable to (de)serialize JSON storage: {0}")]
+  Serde(#[from] serde_json::Error),
+}
+
+/// Reads a JSON file, missing file is treated as an empty (default) value
+pub async fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, JsonFileStorageError> {
+  match tokio::fs::read(path).await {
+    Ok(content) => Ok(serde_json::from_slice(&content)?),
+    Err(err) if err.kind() == ErrorKind::NotFound => Ok(T::default()),
+    Err(err) => Err(err.into()),
+  }
+}
+
+/// Reads a JSON file which is expected to exist
+pub async fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, JsonFileStorageError> {
+  Ok(serde_json::from_slice(&tokio::fs::read(path).await?)?)
+}
+
+/// Writes the value to a temporary file next to the target and renames it over the target,
+/// so a crash in the middle of the write never leaves a truncated file behind
+pub async fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), JsonFileStorageError> {
+  write_atomically(path, &serde_json::to_vec(value)?).await
+}
+
+/// Replaces the file content via a temporary file, see [`save_json`]
+pub async fn write_atomically(path: &Path, content: &[u8]) -> Result<(), JsonFileStorageError> {
+  // Every write gets its own temporary file, so the concurrent writes of the same path don't mix
+  static WRITES: AtomicU64 = AtomicU64::new(0);
+  let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+  tmp_name.push(format!(".{}.tmp", WRITES.fetch_add(1, Ordering::Relaxed)));
+  let tmp_path = path.with_file_name(tmp_name);
+
+  let written = async {
+    let mut file = tokio::fs::File::create(&tmp_path).await?;
+    file.write_all(content).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&tmp_path, path).await
+  }
+  .await;
+  if written.is_err() {
+    let _ = tokio::fs::remove_file(&tmp_path).await;
+  }
+  Ok(written?)
+}
+
+/// Dialogue storage that keeps every chat's state in a single JSON file,
+/// so the in-progress flows survive the bot restart. The file is rewritten on every change.
+pub struct JsonFileStorage<D> {
+  path: PathBuf,
+  dialogues: Mutex<HashMap<i64, serde_json::Value>>,
+  _dialogue: PhantomData<fn() -> D>,
+}
+
+impl<D> JsonFileStorage<D> {
+  /// Loads previously stored dialogues. Missing file means there is nothing to resume yet.
+  pub async fn open(path: impl AsRef<Path>) -> Result<Arc<Self>, JsonFileStorageError> {
+    let path = path.as_ref().to_path_buf();
+    let dialogues: HashMap<_, _> = load_json(&path).await?;
+    info!("[JsonFileStorage] Loaded {} dialogue(s) from {:?}", dialogues.len(), path);
+    Ok(Arc::new(Self {
+      path,
+      dialogues: Mutex::new(dialogues),
+      _dialogue: PhantomData,
+    }))
+  }
+
+  async fn flush(&self, dialogues: &HashMap<i64, serde_json::Value>) -> Result<(), JsonFileStorageError> {
+    debug!("[JsonFileStorage] Writing {} dialogue(s) to {:?}", dialogues.len(), self.path);
+    save_json(&self.path, dialogues).await
+  }
+}
+
+impl<D> Storage<D> for JsonFileStorage<D>
+where
+  D: Serialize + DeserializeOwned + Send + 'static,
+{
+  type Error = JsonFileStorageError;
+
+  fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<'static, Result<(), Self::Error>> {
+    Box::pin(async move {
+      let mut dialogues = self.dialogues.lock().await;
+      if dialogues.remove(&chat_id.0).is_some() {
+        self.flush(&dialogues).await?;
+      }
+      Ok(())
+    })
+  }
+
+  fn update_dialogue(self: Arc<Self>, chat_id: ChatId, dialogue: D) -> BoxFuture<'static, Result<(), Self::Error>> {
+    Box::pin(async move {
+      let value = serde_json::to_value(dialogue)?;
+      let mut dialogues = self.dialogues.lock().await;
+      if dialogues.get(&chat_id.0) != Some(&value) {
+        dialogues.insert(chat_id.0, value);
+        self.flush(&dialogues).await?;
+      }
+      Ok(())
+    })
+  }
+
+  fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<'static, Result<Option<D>, Self::Error>> {
+    Box::pin(async move {
+      let dialogues = self.dialogues.lock().await;
+      match dialogues.get(&chat_id.0) {
+        Some(value) => Ok(Some(D::deserialize(value)?)),
+        None => Ok(None),
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  /// Fresh path in the temp directory, unique per test
+  pub(crate) fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("congratulator-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("storage.json")
+  }
+
+  #[tokio::test]
+  async fn json_round_trips_without_leftovers() {
+    let path = temp_path("json-round-trip");
+    let missing: HashMap<String, u32> = load_json(&path).await.unwrap();
+    assert!(missing.is_empty());
+
+    let value = HashMap::from([("a".to_string(), 1u32)]);
+    save_json(&path, &value).await.unwrap();
+    save_json(&path, &HashMap::from([("b".to_string(), 2u32)])).await.unwrap();
+
+    let loaded: HashMap<String, u32> = load_json(&path).await.unwrap();
+    assert_eq!(loaded, HashMap::from([("b".to_string(), 2u32)]));
+    let files = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+    assert_eq!(files, 1, "temporary files are left behind");
+  }
+
+  #[tokio::test]
+  async fn dialogue_state_survives_reopen() {
+    let path = temp_path("dialogue-round-trip");
+    let storage = JsonFileStorage::<String>::open(&path).await.unwrap();
+    storage.clone().update_dialogue(ChatId(1), "awaiting".to_string()).await.unwrap();
+    storage.clone().update_dialogue(ChatId(2), "other".to_string()).await.unwrap();
+    storage.remove_dialogue(ChatId(2)).await.unwrap();
+
+    let reopened = JsonFileStorage::<String>::open(&path).await.unwrap();
+    assert_eq!(reopened.clone().get_dialogue(ChatId(1)).await.unwrap().as_deref(), Some("awaiting"));
+    assert_eq!(reopened.get_dialogue(ChatId(2)).await.unwrap(), None);
+  }
+}
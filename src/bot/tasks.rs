@@ -1,28 +1,42 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
 
 use log::{debug, error, info, trace, warn};
-use teloxide::{
-  payloads::SendMessageSetters,
-  requests::Requester,
-  types::{ChatId, ParseMode},
-  Bot,
-};
+use teloxide::{requests::Requester, types::ChatId, Bot, RequestError};
+use tokio::sync::RwLock;
 
 use crate::{
-  dashboard::DashboardError,
+  api::{error::AsyncSheetsHubError, SheetsDataSource},
   helpers::{self, PeriodicTimeUtc},
 };
 
-use super::{AsyncSheetsHub, LockedDashboard};
+use super::{broadcast, hooks::FetchHook, notes::DayNotes, pause::PauseSwitch, LockedDashboard};
 
 pub type TaskHandle = tokio::task::JoinHandle<()>;
 
+/// Moment of the last successful dashboard fetch, `None` until the first one
+pub type LastFetch = Arc<RwLock<Option<DateTime<Utc>>>>;
+
 #[derive(PartialEq)]
 pub enum PeriodcTaskType {
   Notifier,
+  Summary,
   Fetcher,
 }
 
+impl PeriodcTaskType {
+  pub const ALL: [PeriodcTaskType; 3] = [PeriodcTaskType::Fetcher, PeriodcTaskType::Notifier, PeriodcTaskType::Summary];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      PeriodcTaskType::Notifier => "напоминание",
+      PeriodcTaskType::Summary => "итоги дня",
+      PeriodcTaskType::Fetcher => "загрузка данных",
+    }
+  }
+}
+
 pub trait PeriodicTask: Sync + Send {
   fn schedule(&mut self, when: PeriodicTimeUtc) -> bool {
     info!("[{}] Scheduling the task ({})", self.name(), when);
@@ -52,7 +66,17 @@ pub trait PeriodicTask: Sync + Send {
     }
   }
 
+  /// Aborts the running job (if any) and submits the new one at `when`
+  fn reschedule(&mut self, when: PeriodicTimeUtc) {
+    info!("[{}] Rescheduling the task ({})", self.name(), when);
+    if let Some(handle) = self.take_handle() {
+      handle.abort();
+    }
+    self.submit_job(when);
+  }
+
   fn submit_job(&mut self, when: PeriodicTimeUtc);
+  fn take_handle(&mut self) -> Option<TaskHandle>;
 
   fn name(&self) -> &str; 
   fn when(&self) -> Option<&PeriodicTimeUtc>;
@@ -64,28 +88,90 @@ pub trait PeriodicTask: Sync + Send {
 pub struct TaskManager<'a> {
   bot: Bot,
   tasks: Vec<Box<dyn PeriodicTask + 'a>>,
+  fetch_hooks: Vec<Arc<dyn FetchHook>>,
+  last_fetch: LastFetch,
   dashboard: Arc<LockedDashboard>,
+  notes: Arc<DayNotes>,
+  pause: Arc<PauseSwitch>,
 }
 
 impl<'a> TaskManager<'a> {
-  pub fn new(bot: Bot, dashboard: Arc<LockedDashboard>) -> Self {
+  pub fn new(bot: Bot, dashboard: Arc<LockedDashboard>, notes: Arc<DayNotes>, pause: Arc<PauseSwitch>) -> Self {
     Self {
       bot,
       dashboard,
+      notes,
+      pause,
       tasks: Vec::new(),
+      fetch_hooks: Vec::new(),
+      last_fetch: LastFetch::default(),
     }
   }
 
-  pub fn create_notifier_task(&self, text: String, chat_id: ChatId) -> PeriodicNotifier {
-    PeriodicNotifier::new(self.bot.clone(), text, chat_id)
+  pub fn last_fetch(&self) -> LastFetch {
+    self.last_fetch.clone()
+  }
+
+  /// `usernames` maps participant names to Telegram usernames mentioned in the reminder
+  pub fn create_notifier_task(
+    &self,
+    text: String,
+    chat_ids: Vec<ChatId>,
+    skip_if_all_filled: bool,
+    usernames: HashMap<String, String>,
+  ) -> PeriodicNotifier {
+    PeriodicNotifier::new(
+      self.bot.clone(),
+      self.pause.clone(),
+      self.dashboard.clone(),
+      text,
+      chat_ids,
+      skip_if_all_filled,
+      Arc::new(usernames),
+    )
+  }
+
+  /// The hooks are also kept for the on-demand refreshes
+  pub fn create_data_fetcher_task(&mut self, hub: Arc<dyn SheetsDataSource>, hooks: Vec<Arc<dyn FetchHook>>) -> PeriodicDataFetcher {
+    self.fetch_hooks = hooks.clone();
+    PeriodicDataFetcher::new(
+      self.bot.clone(),
+      self.pause.clone(),
+      hub,
+      self.dashboard.clone(),
+      hooks,
+      self.last_fetch.clone(),
+    )
+  }
+
+  /// Fetches and replaces the dashboard right away, the same way the data fetcher does
+  pub async fn refresh_dashboard(&self, hub: Arc<dyn SheetsDataSource>) -> Result<(), AsyncSheetsHubError> {
+    PeriodicDataFetcher::do_update(
+      "ManualRefresh".to_string(),
+      self.bot.clone(),
+      self.pause.clone(),
+      hub,
+      self.dashboard.clone(),
+      self.fetch_hooks.clone(),
+      self.last_fetch.clone(),
+    )
+    .await
   }
 
-  pub fn create_data_fetcher_task(&self, hub: Arc<AsyncSheetsHub>) -> PeriodicDataFetcher {
-    PeriodicDataFetcher::new(hub, self.dashboard.clone())
+  pub fn create_summary_sender_task(&self, chat_ids: Vec<ChatId>) -> PeriodicSummarySender {
+    PeriodicSummarySender::new(self.bot.clone(), self.pause.clone(), self.dashboard.clone(), self.notes.clone(), chat_ids, None)
   }
 
-  pub fn create_summary_sender_task(&self, chat_id: ChatId) -> PeriodicSummarySender {
-    PeriodicSummarySender::new(self.bot.clone(), self.dashboard.clone(), chat_id)
+  /// Summary sender that also appends the reminder addressed to those who haven't filled the table
+  pub fn create_combined_evening_task(&self, reminder: String, chat_ids: Vec<ChatId>) -> PeriodicSummarySender {
+    PeriodicSummarySender::new(
+      self.bot.clone(),
+      self.pause.clone(),
+      self.dashboard.clone(),
+      self.notes.clone(),
+      chat_ids,
+      Some(reminder),
+    )
   }
 
   pub fn tasks(&self, task_type: PeriodcTaskType) -> Vec<&(dyn PeriodicTask + 'a)> {
@@ -105,6 +191,26 @@ impl<'a> TaskManager<'a> {
     self.tasks.push(Box::new(task));
   }
 
+  /// Returns `false` if there is no such task
+  pub fn cancel_task(&self, task_type: PeriodcTaskType, name: &str) -> bool {
+    let mut found = false;
+    for task in self.tasks.iter().filter(|t| *t.task_type() == task_type && t.name() == name) {
+      task.cancel();
+      found = true;
+    }
+    found
+  }
+
+  /// Cancels the task's current job and submits the new one. Returns `false` if there is no such task.
+  pub fn reschedule_task(&mut self, task_type: PeriodcTaskType, name: &str, when: PeriodicTimeUtc) -> bool {
+    let mut found = false;
+    for task in self.tasks.iter_mut().filter(|t| *t.task_type() == task_type && t.name() == name) {
+      task.reschedule(when.clone());
+      found = true;
+    }
+    found
+  }
+
   pub fn finalize_tasks(&self) {
     for t in &self.tasks {
       t.cancel();
@@ -113,9 +219,13 @@ impl<'a> TaskManager<'a> {
 }
 
 /// This task periodically downloads latest data from Sheets through the AsyncHub instance,
-/// and updates the Dashboard through RwLock
+/// and updates the Dashboard through RwLock. Hooks are notified about every replacement.
 pub struct PeriodicDataFetcher {
-  hub: Arc<AsyncSheetsHub>,
+  bot: Bot,
+  pause: Arc<PauseSwitch>,
+  hub: Arc<dyn SheetsDataSource>,
+  hooks: Vec<Arc<dyn FetchHook>>,
+  last_fetch: LastFetch,
   name: String,
   when: Option<PeriodicTimeUtc>,
   handle: Option<TaskHandle>,
@@ -124,9 +234,20 @@ pub struct PeriodicDataFetcher {
 }
 
 impl PeriodicDataFetcher {
-  fn new(hub: Arc<AsyncSheetsHub>, dashboard: Arc<LockedDashboard>) -> Self {
+  fn new(
+    bot: Bot,
+    pause: Arc<PauseSwitch>,
+    hub: Arc<dyn SheetsDataSource>,
+    dashboard: Arc<LockedDashboard>,
+    hooks: Vec<Arc<dyn FetchHook>>,
+    last_fetch: LastFetch,
+  ) -> Self {
     PeriodicDataFetcher {
+      bot,
+      pause,
       hub,
+      hooks,
+      last_fetch,
       dashboard,
       name: "PeriodicDataFetcher".to_string(),
       when: None,
@@ -135,12 +256,21 @@ impl PeriodicDataFetcher {
     }
   }
 
-  async fn do_update(name: String, hub: Arc<AsyncSheetsHub>, dashboard: Arc<LockedDashboard>) {
+  async fn do_update(
+    name: String,
+    bot: Bot,
+    pause: Arc<PauseSwitch>,
+    hub: Arc<dyn SheetsDataSource>,
+    dashboard: Arc<LockedDashboard>,
+    hooks: Vec<Arc<dyn FetchHook>>,
+    last_fetch: LastFetch,
+  ) -> Result<(), AsyncSheetsHubError> {
     info!("[{}] Task has started at {}", name, helpers::current_time_utc());
     debug!("[{}] Fetching the latest data...", name);
     let latest_dashboard = match hub.fetch_dashboard().await {
       Ok(data) => {
         debug!("[{}] New dashboard has been successfully fetched", name);
+        *last_fetch.write().await = Some(helpers::current_time_utc());
         data
       }
       Err(hub_err) => {
@@ -148,21 +278,57 @@ impl PeriodicDataFetcher {
           "[{}] Error occured while fetching the data: {:#?}. Exiting the task...",
           name, hub_err
         );
-        return;
+        return Err(hub_err);
       }
     };
 
     trace!("[{}] Acquiring WRITE lock on dashboard...", name);
-    {
+    let messages: Vec<_> = {
       let mut locked_dashboard = dashboard.write().await;
       trace!("[{}] WRITE lock on dashboard has been acquired", name);
+      let messages = hooks
+        .iter()
+        .flat_map(|hook| {
+          let messages = hook.on_update(&locked_dashboard, &latest_dashboard);
+          debug!("[{}] Hook {} produced {} message(s)", name, hook.name(), messages.len());
+          messages
+        })
+        .collect();
       *locked_dashboard = latest_dashboard;
       trace!(
         "[{}] New dashboard has been successfully fetched and replaced with the old one",
         name
       );
+      messages
+    };
+
+    let messages = if pause.is_paused() && !messages.is_empty() {
+      info!("[{}] Sends are paused, {} hook message(s) dropped", name, messages.len());
+      Vec::new()
+    } else {
+      messages
+    };
+    for message in messages {
+      let result = match message.edit {
+        Some(message_id) => broadcast::edit_markdown(&bot, message.chat_id, message_id, &message.text).await,
+        None => broadcast::send_markdown(&bot, message.chat_id, &message.text).await.map(|_| ()),
+      };
+      PeriodicDataFetcher::handle_delivery(&name, message.chat_id, result, &hooks);
     }
     info!("[{}] Task has finished at {}", name, helpers::current_time_utc());
+    Ok(())
+  }
+
+  /// Logs the delivery of the hook message, the hooks stop producing messages for the unreachable chat
+  fn handle_delivery(name: &str, chat_id: ChatId, result: Result<(), RequestError>, hooks: &[Arc<dyn FetchHook>]) {
+    match result {
+      Ok(()) => info!("[{}] Delivered hook message to chat_id={}", name, chat_id),
+      Err(err) if broadcast::is_unreachable_chat_error(&err) => {
+        warn!("[{}] chat_id={} is unreachable ({}), notifying hooks", name, chat_id, err);
+        hooks.iter().for_each(|hook| hook.on_unreachable(chat_id));
+      }
+      Err(err) => error!("[{}] Unable to deliver hook message to chat_id={} due to {:?}", name, chat_id, err),
+    }
   }
 }
 
@@ -170,16 +336,34 @@ impl PeriodicTask for PeriodicDataFetcher {
   fn submit_job(&mut self, when: PeriodicTimeUtc) {
     assert!(self.is_finished(), "should be finished");
 
+    let bot = self.bot.clone();
+    let pause = self.pause.clone();
     let hub = self.hub.clone();
     let dashboard = self.dashboard.clone();
+    let hooks = self.hooks.clone();
+    let last_fetch = self.last_fetch.clone();
     let name = self.name.clone();
 
     let task = move || {
+      let cloned_bot = bot.clone();
+      let cloned_pause = pause.clone();
       let cloned_hub = hub.clone();
       let cloned_dashboard = dashboard.clone();
+      let cloned_hooks = hooks.clone();
+      let cloned_last_fetch = last_fetch.clone();
       let cloned_name = name.clone();
       async move {
-        PeriodicDataFetcher::do_update(cloned_name, cloned_hub, cloned_dashboard).await;
+        // The error is already logged, the next run will try again
+        let _ = PeriodicDataFetcher::do_update(
+          cloned_name,
+          cloned_bot,
+          cloned_pause,
+          cloned_hub,
+          cloned_dashboard,
+          cloned_hooks,
+          cloned_last_fetch,
+        )
+        .await;
       }
     };
 
@@ -199,6 +383,10 @@ impl PeriodicTask for PeriodicDataFetcher {
     self.handle.as_ref()
   }
 
+  fn take_handle(&mut self) -> Option<TaskHandle> {
+    self.handle.take()
+  }
+
   fn when(&self) -> Option<&PeriodicTimeUtc> {
     self.when.as_ref()
   }
@@ -208,34 +396,91 @@ impl PeriodicTask for PeriodicDataFetcher {
   }
 }
 
-/// This task periodically (once a day) sends text to the specified 'chat_id'
+/// This task periodically (once a day) sends text to every specified chat
 pub struct PeriodicNotifier {
   bot: Bot,
+  pause: Arc<PauseSwitch>,
+  dashboard: Arc<LockedDashboard>,
   text: String,
   name: String,
   when: Option<PeriodicTimeUtc>,
   handle: Option<TaskHandle>,
   task_type: PeriodcTaskType,
-  chat_id: ChatId,
+  chat_ids: Vec<ChatId>,
+  skip_if_all_filled: bool,
+  usernames: Arc<HashMap<String, String>>,
 }
 
 impl PeriodicNotifier {
-  fn new(bot: Bot, text: String, chat_id: ChatId) -> Self {
+  fn new(
+    bot: Bot,
+    pause: Arc<PauseSwitch>,
+    dashboard: Arc<LockedDashboard>,
+    text: String,
+    chat_ids: Vec<ChatId>,
+    skip_if_all_filled: bool,
+    usernames: Arc<HashMap<String, String>>,
+  ) -> Self {
     PeriodicNotifier {
       bot,
+      pause,
+      dashboard,
       text,
-      chat_id,
+      chat_ids,
+      skip_if_all_filled,
+      usernames,
       name: "PeriodicNotifier".to_string(),
       when: None,
       handle: None,
       task_type: PeriodcTaskType::Notifier,
     }
   }
-  async fn do_notify(name: String, bot: Bot, text: String, chat_id: ChatId) {
+
+  /// Reminder followed by the mentions of the ones who haven't filled today,
+  /// `None` if everyone has filled and the reminder is skipped then
+  async fn reminder_text(
+    dashboard: &LockedDashboard,
+    text: String,
+    skip_if_all_filled: bool,
+    usernames: &HashMap<String, String>,
+  ) -> Option<String> {
+    if !skip_if_all_filled && usernames.is_empty() {
+      return Some(text);
+    }
+    let dashboard = dashboard.read().await;
+    if dashboard.tables().is_none() {
+      return Some(text);
+    }
+    let missed = dashboard.not_filled_today();
+    if missed.is_empty() && skip_if_all_filled {
+      return None;
+    }
+    let mentions: Vec<_> = missed
+      .iter()
+      .filter_map(|person| usernames.get(person.name()))
+      .map(|username| format!("@{}", username.trim_start_matches('@')))
+      .collect();
+    Some(if mentions.is_empty() {
+      text
+    } else {
+      format!("{}\n{}", text, mentions.join(" "))
+    })
+  }
+  async fn do_notify(name: String, bot: Bot, pause: Arc<PauseSwitch>, text: String, chat_ids: Vec<ChatId>) {
     info!("[{}] Task has started at {}", name, helpers::current_time_utc());
-    match bot.send_message(chat_id, &text[..]).await {
-      Ok(_) => info!("[{}] Sent text='{}' to chat_id={}", name, text, chat_id),
-      Err(err) => error!("[{}] Unable to send text='{}' to chat_id={} due to {:?}", name, text, chat_id, err),
+    if pause.is_paused() {
+      info!("[{}] Sends are paused, text='{}' is not sent", name, text);
+    } else {
+      // A failed send doesn't prevent the other chats from getting the text
+      for chat_id in chat_ids {
+        match bot.send_message(chat_id, &text[..]).await {
+          Ok(_) => info!("[{}] Sent text='{}' to chat_id={}", name, text, chat_id),
+          Err(err) if broadcast::is_unreachable_chat_error(&err) => {
+            warn!("[{}] chat_id={} has blocked the bot, text='{}' is not delivered", name, chat_id, text)
+          }
+          Err(err) => error!("[{}] Unable to send text='{}' to chat_id={} due to {:?}", name, text, chat_id, err),
+        }
+      }
     }
     info!("[{}] Task has finished at {}", name, helpers::current_time_utc());
   }
@@ -246,16 +491,29 @@ impl PeriodicTask for PeriodicNotifier {
     assert!(self.is_finished(), "should be finished");
 
     let bot = self.bot.clone();
+    let pause = self.pause.clone();
+    let dashboard = self.dashboard.clone();
     let text = self.text.clone();
-    let chat_id = self.chat_id;
+    let chat_ids = self.chat_ids.clone();
+    let skip_if_all_filled = self.skip_if_all_filled;
+    let usernames = self.usernames.clone();
     let name = self.name.clone();
 
     let task = move || {
       let cloned_bot = bot.clone();
+      let cloned_pause = pause.clone();
+      let cloned_dashboard = dashboard.clone();
       let cloned_text = text.clone();
+      let cloned_chat_ids = chat_ids.clone();
+      let cloned_usernames = usernames.clone();
       let cloned_name = name.clone();
       async move {
-        PeriodicNotifier::do_notify(cloned_name, cloned_bot, cloned_text, chat_id).await;
+        let text =
+          PeriodicNotifier::reminder_text(&cloned_dashboard, cloned_text, skip_if_all_filled, &cloned_usernames).await;
+        match text {
+          Some(text) => PeriodicNotifier::do_notify(cloned_name, cloned_bot, cloned_pause, text, cloned_chat_ids).await,
+          None => info!("[{}] Everyone has filled the table today, the reminder is skipped", cloned_name),
+        }
       }
     };
 
@@ -264,7 +522,8 @@ impl PeriodicTask for PeriodicNotifier {
   }
 
   fn description(&self) -> Option<String> {
-    self.when().map(|w| format!("Я прошу всех заполнить таблицу {}", w))
+    let whom = if self.skip_if_all_filled { "тех, кто еще не заполнил," } else { "всех" };
+    self.when().map(|w| format!("Я прошу {} заполнить таблицу {}", whom, w))
   }
 
   fn task_type(&self) -> &PeriodcTaskType {
@@ -275,6 +534,10 @@ impl PeriodicTask for PeriodicNotifier {
     self.handle.as_ref()
   }
 
+  fn take_handle(&mut self) -> Option<TaskHandle> {
+    self.handle.take()
+  }
+
   fn when(&self) -> Option<&PeriodicTimeUtc> {
     self.when.as_ref()
   }
@@ -284,42 +547,78 @@ impl PeriodicTask for PeriodicNotifier {
   }
 }
 
-/// This task periodically (once a day) sends summary text similar to /todaysummary bot command
+/// This task periodically (once a day) sends summary text similar to /todaysummary bot command.
+/// With a reminder set, the same message also asks the ones who missed to fill the table.
 pub struct PeriodicSummarySender {
   bot: Bot,
+  pause: Arc<PauseSwitch>,
+  reminder: Option<String>,
   name: String,
   when: Option<PeriodicTimeUtc>,
   handle: Option<TaskHandle>,
   task_type: PeriodcTaskType,
-  chat_id: ChatId,
+  chat_ids: Vec<ChatId>,
   dashboard: Arc<LockedDashboard>,
+  notes: Arc<DayNotes>,
 }
 
 impl PeriodicSummarySender {
-  fn new(bot: Bot, dashboard: Arc<LockedDashboard>, chat_id: ChatId) -> Self {
+  fn new(
+    bot: Bot,
+    pause: Arc<PauseSwitch>,
+    dashboard: Arc<LockedDashboard>,
+    notes: Arc<DayNotes>,
+    chat_ids: Vec<ChatId>,
+    reminder: Option<String>,
+  ) -> Self {
     PeriodicSummarySender {
       bot,
+      pause,
+      reminder,
       dashboard,
+      notes,
       name: "PeriodicSummarySender".to_string(),
-      chat_id,
+      chat_ids,
       when: None,
       handle: None,
-      task_type: PeriodcTaskType::Notifier,
+      task_type: PeriodcTaskType::Summary,
     }
   }
 
-  pub async fn send_summary(name: String, bot: Bot, dashboard: Arc<LockedDashboard>, chat_id: ChatId) {
+  pub async fn send_summary(
+    name: String,
+    bot: Bot,
+    pause: Arc<PauseSwitch>,
+    dashboard: Arc<LockedDashboard>,
+    notes: Arc<DayNotes>,
+    chat_ids: Vec<ChatId>,
+    reminder: Option<String>,
+  ) {
     info!("[{}] Task has started at {}", name, helpers::current_time_utc());
+    if pause.is_paused() {
+      info!("[{}] Sends are paused, summary is not sent", name);
+      return;
+    }
     let locked_dashboard = dashboard.read().await;
-    let by_date = helpers::current_time_utc().date_naive(); // always send "today" summary
+    let by_date = helpers::current_time_local().date_naive(); // always send "today" summary
     match locked_dashboard.summary(&by_date) {
       Ok(summary) => {
-        let msg = helpers::format_summary_msg(&summary, &by_date);
-        let _ = bot.send_message(chat_id, msg).parse_mode(ParseMode::MarkdownV2).await;
-        info!("[{}] Summary has been successfully sent", name);
+        let note = notes.get(&by_date).await;
+        let mut msg = helpers::format_summary_msg(&summary, &by_date, note.as_deref());
+        if let Some(reminder) = &reminder {
+          let missed = locked_dashboard.missed(&by_date).unwrap_or_default();
+          msg = helpers::format_evening_msg(&msg, reminder, &missed);
+        }
+        // A failed send doesn't prevent the other chats from getting the summary
+        for chat_id in chat_ids {
+          match broadcast::send_markdown_chunks(&bot, chat_id, &msg).await {
+            Ok(_) => info!("[{}] Summary has been successfully sent to chat_id={}", name, chat_id),
+            Err(err) => error!("[{}] Unable to send summary to chat_id={} due to {:?}", name, chat_id, err),
+          }
+        }
       }
-      Err(DashboardError::EmptyParticipants) => {
-        warn!("[{}] The participants were not found", name);
+      Err(err) => {
+        warn!("[{}] Summary is not sent: {}", name, err);
       }
     }
     info!("[{}] Task has finished at {}", name, helpers::current_time_utc());
@@ -331,16 +630,32 @@ impl PeriodicTask for PeriodicSummarySender {
     assert!(self.is_finished(), "should be finished");
 
     let bot = self.bot.clone();
-    let chat_id = self.chat_id;
+    let pause = self.pause.clone();
+    let chat_ids = self.chat_ids.clone();
     let name = self.name.clone();
     let dashboard = self.dashboard.clone();
+    let notes = self.notes.clone();
+    let reminder = self.reminder.clone();
 
     let task = move || {
       let cloned_bot = bot.clone();
+      let cloned_pause = pause.clone();
       let cloned_name = name.clone();
       let cloned_dashboard = dashboard.clone();
+      let cloned_notes = notes.clone();
+      let cloned_reminder = reminder.clone();
+      let cloned_chat_ids = chat_ids.clone();
       async move {
-        PeriodicSummarySender::send_summary(cloned_name, cloned_bot, cloned_dashboard, chat_id).await;
+        PeriodicSummarySender::send_summary(
+          cloned_name,
+          cloned_bot,
+          cloned_pause,
+          cloned_dashboard,
+          cloned_notes,
+          cloned_chat_ids,
+          cloned_reminder,
+        )
+        .await;
       }
     };
 
@@ -349,7 +664,10 @@ impl PeriodicTask for PeriodicSummarySender {
   }
 
   fn description(&self) -> Option<String> {
-    self.when().map(|w| format!("Я отправляю /todaysummary {}", w))
+    self.when().map(|w| match self.reminder {
+      Some(_) => format!("Я отправляю /todaysummary и прошу не заполнивших заполнить таблицу {}", w),
+      None => format!("Я отправляю /todaysummary {}", w),
+    })
   }
 
   fn task_type(&self) -> &PeriodcTaskType {
@@ -360,6 +678,10 @@ impl PeriodicTask for PeriodicSummarySender {
     self.handle.as_ref()
   }
 
+  fn take_handle(&mut self) -> Option<TaskHandle> {
+    self.handle.take()
+  }
+
   fn when(&self) -> Option<&PeriodicTimeUtc> {
     self.when.as_ref()
   }
@@ -368,3 +690,225 @@ impl PeriodicTask for PeriodicSummarySender {
     &self.name[..]
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+  use teloxide::{types::MessageId, ApiError};
+
+  use crate::{
+    api::fake::FakeSheetsHub,
+    bot::{
+      fake::FakeTelegram,
+      hooks::{FetchCounter, StandingsBoards, StandingsHook, WatchHook},
+      watches::Watches,
+    },
+    dashboard::{
+      fake::{date, empty_record, record, table},
+      score_table::{entities::Person, ScoreTable},
+      Dashboard,
+    },
+    helpers::clock::{set_clock, FixedClock},
+  };
+
+  use super::*;
+
+  async fn update(
+    hub: FakeSheetsHub,
+    dashboard: &Arc<LockedDashboard>,
+    counter: &Arc<FetchCounter>,
+    last_fetch: &LastFetch,
+  ) -> Result<(), AsyncSheetsHubError> {
+    PeriodicDataFetcher::do_update(
+      "TestFetcher".to_string(),
+      Bot::new("test-token"),
+      Arc::new(PauseSwitch::default()),
+      Arc::new(hub),
+      dashboard.clone(),
+      vec![counter.clone()],
+      last_fetch.clone(),
+    )
+    .await
+  }
+
+  #[tokio::test]
+  async fn fetched_dashboard_replaces_the_current_one() {
+    let dashboard = Arc::new(RwLock::new(Dashboard::new()));
+    let counter = Arc::new(FetchCounter::default());
+    let last_fetch = LastFetch::default();
+    let hub = FakeSheetsHub::with_tables(vec![ScoreTable::new(Person::new("Борис".to_string()), Vec::new())]);
+
+    update(hub, &dashboard, &counter, &last_fetch).await.unwrap();
+
+    assert_eq!(dashboard.read().await.participants_names(), Some(vec!["Борис"]));
+    assert_eq!(counter.count(), 1);
+    assert!(last_fetch.read().await.is_some());
+  }
+
+  #[tokio::test]
+  async fn failed_fetch_keeps_the_current_dashboard() {
+    let dashboard = Arc::new(RwLock::new(Dashboard::new()));
+    let counter = Arc::new(FetchCounter::default());
+    let last_fetch = LastFetch::default();
+
+    assert!(update(FakeSheetsHub::failing(), &dashboard, &counter, &last_fetch).await.is_err());
+
+    assert!(dashboard.read().await.tables().is_none());
+    assert_eq!(counter.count(), 0);
+    assert!(last_fetch.read().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn blocked_chat_is_unregistered_from_hooks() {
+    let boards = Arc::new(StandingsBoards::default());
+    let watches = Arc::new(Watches::load(None).await.unwrap());
+    let hooks: Vec<Arc<dyn FetchHook>> = vec![
+      Arc::new(StandingsHook::new(boards.clone())),
+      Arc::new(WatchHook::new(watches.clone(), None)),
+    ];
+    for chat_id in [ChatId(1), ChatId(2)] {
+      boards.register(chat_id, MessageId(1), String::new());
+      watches.add(chat_id, "Анна").await.unwrap();
+    }
+
+    let blocked = Err(RequestError::Api(ApiError::BotBlocked));
+    PeriodicDataFetcher::handle_delivery("TestFetcher", ChatId(1), blocked, &hooks);
+    // A mistyped or migrated chat may come back, its subscriptions are kept
+    let not_found = Err(RequestError::Api(ApiError::ChatNotFound));
+    PeriodicDataFetcher::handle_delivery("TestFetcher", ChatId(2), not_found, &hooks);
+
+    assert!(!boards.remove(ChatId(1)));
+    assert!(watches.watched_by(ChatId(1)).is_empty());
+    assert!(boards.remove(ChatId(2)));
+    assert_eq!(watches.watched_by(ChatId(2)), vec!["Анна"]);
+  }
+
+  /// Task whose job never finishes on its own
+  struct DummyTask {
+    name: String,
+    task_type: PeriodcTaskType,
+    when: Option<PeriodicTimeUtc>,
+    handle: Option<TaskHandle>,
+  }
+
+  impl DummyTask {
+    fn new(name: &str, task_type: PeriodcTaskType) -> Self {
+      Self {
+        name: name.to_string(),
+        task_type,
+        when: None,
+        handle: None,
+      }
+    }
+  }
+
+  impl PeriodicTask for DummyTask {
+    fn submit_job(&mut self, when: PeriodicTimeUtc) {
+      self.when = Some(when);
+      self.handle = Some(tokio::spawn(std::future::pending()));
+    }
+
+    fn take_handle(&mut self) -> Option<TaskHandle> {
+      self.handle.take()
+    }
+
+    fn name(&self) -> &str {
+      &self.name
+    }
+
+    fn when(&self) -> Option<&PeriodicTimeUtc> {
+      self.when.as_ref()
+    }
+
+    fn handle(&self) -> Option<&TaskHandle> {
+      self.handle.as_ref()
+    }
+
+    fn task_type(&self) -> &PeriodcTaskType {
+      &self.task_type
+    }
+
+    fn description(&self) -> Option<String> {
+      None
+    }
+  }
+
+  async fn task_manager<'a>() -> TaskManager<'a> {
+    let mut manager = TaskManager::new(
+      Bot::new("test-token"),
+      Arc::new(RwLock::new(Dashboard::new())),
+      Arc::new(DayNotes::load(None).await.unwrap()),
+      Arc::new(PauseSwitch::default()),
+    );
+    // Same-named tasks of different types are told apart by the type
+    for task_type in [PeriodcTaskType::Notifier, PeriodcTaskType::Summary] {
+      manager.schedule_task(DummyTask::new("Reminder", task_type), PeriodicTimeUtc::every_min_time_utc(5));
+    }
+    manager
+  }
+
+  /// Lets the runtime complete the aborted jobs
+  async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  }
+
+  #[tokio::test]
+  async fn cancel_task_stops_only_the_matching_one() {
+    let manager = task_manager().await;
+
+    assert!(!manager.cancel_task(PeriodcTaskType::Fetcher, "Reminder"));
+    assert!(!manager.cancel_task(PeriodcTaskType::Notifier, "Summary"));
+    assert!(manager.cancel_task(PeriodcTaskType::Notifier, "Reminder"));
+    settle().await;
+
+    assert!(manager.tasks(PeriodcTaskType::Notifier)[0].is_finished());
+    assert!(!manager.tasks(PeriodcTaskType::Summary)[0].is_finished());
+  }
+
+  #[tokio::test]
+  async fn reschedule_task_replaces_the_job_of_the_matching_one() {
+    let mut manager = task_manager().await;
+
+    let when = PeriodicTimeUtc::every_min_time_utc(15);
+    assert!(!manager.reschedule_task(PeriodcTaskType::Fetcher, "Reminder", when.clone()));
+    assert!(manager.reschedule_task(PeriodcTaskType::Summary, "Reminder", when));
+    settle().await;
+
+    let summary = manager.tasks(PeriodcTaskType::Summary)[0];
+    assert!(matches!(summary.when(), Some(PeriodicTimeUtc::EveryMin(_, 15))));
+    assert!(!summary.is_finished());
+    let notifier = manager.tasks(PeriodcTaskType::Notifier)[0];
+    assert!(matches!(notifier.when(), Some(PeriodicTimeUtc::EveryMin(_, 5))));
+  }
+
+  /// Texts sent to every chat by the summary task for 2024-01-15, when Анна filled the day and Борис didn't
+  async fn evening_texts(reminder: Option<&str>) -> Vec<String> {
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 15, 18, 0, 0).unwrap())));
+    let telegram = FakeTelegram::start();
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(date(2024, 1, 15), 70)]),
+      table("Борис", vec![empty_record(date(2024, 1, 15))]),
+    ]);
+    PeriodicSummarySender::send_summary(
+      "TestSummary".to_string(),
+      telegram.bot(),
+      Arc::new(PauseSwitch::default()),
+      Arc::new(RwLock::new(dashboard)),
+      Arc::new(DayNotes::load(None).await.unwrap()),
+      vec![ChatId(1), ChatId(2)],
+      reminder.map(String::from),
+    )
+    .await;
+    telegram.take_texts()
+  }
+
+  #[tokio::test]
+  async fn combined_evening_message_ends_with_the_reminder() {
+    let summary = evening_texts(None).await;
+    let combined = evening_texts(Some("Не забудьте заполнить таблицу!")).await;
+
+    assert_eq!(combined.len(), 2);
+    assert_eq!(combined[0], combined[1]);
+    assert_eq!(combined[0], format!("{}\n\nНе забудьте заполнить таблицу\\!\nБорис", summary[0]));
+  }
+}
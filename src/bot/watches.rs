@@ -0,0 +1,156 @@
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  path::PathBuf,
+  sync::{Arc, Mutex},
+};
+
+use log::{error, info, warn};
+use teloxide::types::ChatId;
+
+use super::storage::{self, JsonFileStorageError};
+
+type WatchMap = BTreeMap<i64, BTreeSet<String>>;
+
+/// Participants watched by the chats (accountability partners), keyed by the watcher chat.
+/// Persisted to the JSON file if it's configured, otherwise kept in memory only.
+pub struct Watches {
+  file: Option<PathBuf>,
+  watches: Arc<Mutex<WatchMap>>,
+  /// Held across the file write, so the snapshots land on disk in the order they were taken
+  writes: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Watches {
+  pub async fn load(file: Option<&str>) -> Result<Self, JsonFileStorageError> {
+    let file = file.map(PathBuf::from);
+    let watches: WatchMap = match &file {
+      Some(path) => storage::load_json(path).await?,
+      None => BTreeMap::new(),
+    };
+    info!("[Watches] Loaded watches of {} chat(s)", watches.len());
+    Ok(Self {
+      file,
+      watches: Arc::new(Mutex::new(watches)),
+      writes: Arc::default(),
+    })
+  }
+
+  /// Returns `false` if the chat already watches the participant
+  pub async fn add(&self, chat_id: ChatId, name: &str) -> Result<bool, JsonFileStorageError> {
+    let _write = self.writes.lock().await;
+    if !self.watches.lock().unwrap().entry(chat_id.0).or_default().insert(name.to_string()) {
+      return Ok(false);
+    }
+    if let Err(err) = self.persist().await {
+      Watches::unwatch(&mut self.watches.lock().unwrap(), chat_id, name);
+      return Err(err);
+    }
+    Ok(true)
+  }
+
+  /// Returns `false` if the chat didn't watch the participant
+  pub async fn remove(&self, chat_id: ChatId, name: &str) -> Result<bool, JsonFileStorageError> {
+    let _write = self.writes.lock().await;
+    if !Watches::unwatch(&mut self.watches.lock().unwrap(), chat_id, name) {
+      return Ok(false);
+    }
+    if let Err(err) = self.persist().await {
+      self.watches.lock().unwrap().entry(chat_id.0).or_default().insert(name.to_string());
+      return Err(err);
+    }
+    Ok(true)
+  }
+
+  /// Drops every watch of the chat. Not async since it's called by the fetch hook,
+  /// so the file is written in background.
+  pub fn remove_chat(&self, chat_id: ChatId) -> bool {
+    if self.watches.lock().unwrap().remove(&chat_id.0).is_none() {
+      return false;
+    }
+    if let Some(path) = self.file.clone() {
+      let (watches, writes) = (self.watches.clone(), self.writes.clone());
+      tokio::spawn(async move {
+        // The snapshot is taken under the write lock, so it can't overwrite a later one
+        let _write = writes.lock().await;
+        let snapshot = watches.lock().unwrap().clone();
+        if let Err(err) = storage::save_json(&path, &snapshot).await {
+          error!("[Watches] Unable to persist watches: {}", err);
+        }
+      });
+    }
+    true
+  }
+
+  pub fn watched_by(&self, chat_id: ChatId) -> Vec<String> {
+    let watches = self.watches.lock().unwrap();
+    watches.get(&chat_id.0).map(|names| names.iter().cloned().collect()).unwrap_or_default()
+  }
+
+  /// Chats watching the participant
+  pub fn watchers_of(&self, name: &str) -> Vec<ChatId> {
+    let watches = self.watches.lock().unwrap();
+    watches
+      .iter()
+      .filter(|(_, names)| names.contains(name))
+      .map(|(chat_id, _)| ChatId(*chat_id))
+      .collect()
+  }
+
+  /// Returns `false` if the chat didn't watch the participant
+  fn unwatch(watches: &mut WatchMap, chat_id: ChatId, name: &str) -> bool {
+    let Some(names) = watches.get_mut(&chat_id.0) else {
+      return false;
+    };
+    if !names.remove(name) {
+      return false;
+    }
+    if names.is_empty() {
+      watches.remove(&chat_id.0);
+    }
+    true
+  }
+
+  /// Writes the current watches, must be called under the write lock
+  async fn persist(&self) -> Result<(), JsonFileStorageError> {
+    match &self.file {
+      Some(path) => {
+        let snapshot = self.watches.lock().unwrap().clone();
+        storage::save_json(path, &snapshot).await
+      }
+      None => {
+        warn!("[Watches] Watches file is not configured, the watch will be lost on restart");
+        Ok(())
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::storage::tests::temp_path;
+
+  #[tokio::test]
+  async fn failed_write_rolls_back_the_watch() {
+    // The parent directory doesn't exist, so every write fails
+    let path = temp_path("watches-rollback").with_file_name("missing").join("watches.json");
+    let watches = Watches::load(path.to_str()).await.unwrap();
+
+    assert!(watches.add(ChatId(1), "Анна").await.is_err());
+    assert!(watches.watched_by(ChatId(1)).is_empty());
+  }
+
+  #[tokio::test]
+  async fn watches_survive_reload() {
+    let path = temp_path("watches-reload");
+    let watches = Watches::load(path.to_str()).await.unwrap();
+    assert!(watches.add(ChatId(1), "Анна").await.unwrap());
+    assert!(!watches.add(ChatId(1), "Анна").await.unwrap());
+    assert!(watches.add(ChatId(1), "Борис").await.unwrap());
+    assert!(watches.remove(ChatId(1), "Борис").await.unwrap());
+
+    let reloaded = Watches::load(path.to_str()).await.unwrap();
+    assert_eq!(reloaded.watched_by(ChatId(1)), vec!["Анна"]);
+    assert_eq!(reloaded.watchers_of("Анна"), vec![ChatId(1)]);
+  }
+}
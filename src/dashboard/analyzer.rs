@@ -1,6 +1,128 @@
-use chrono::NaiveDate;
+use std::{cmp::Ordering, collections::HashSet};
 
-use super::{score_table::entities::Person, Dashboard, ScoreTable, ScoreTableRecord};
+use chrono::{Datelike, Duration, NaiveDate};
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+
+use super::{
+  score_table::entities::{Category, Percentage, Person},
+  Dashboard, ScoreTable, ScoreTableRecord,
+};
+
+/// Outcome of estimating how many days it takes to bring the average up to a target
+#[derive(Debug, PartialEq)]
+pub enum GoalEstimate {
+  AlreadyReached { average: f64 },
+  Days { needed: u32, average: f64 },
+  Unreachable { needed: Option<u32>, available: u32, average: f64 },
+}
+
+/// Participant's figures over the month
+#[derive(Debug)]
+pub struct MonthlyStats {
+  pub filled_days: u32,
+  pub average_percent: f64,
+  pub best: (NaiveDate, Percentage),
+  pub worst: (NaiveDate, Percentage),
+}
+
+/// Head-to-head of two participants' latest filled records, `None` on the side which hasn't filled any day
+#[derive(Debug)]
+pub struct Comparison<'a> {
+  pub first: (&'a Person, Option<&'a ScoreTableRecord>),
+  pub second: (&'a Person, Option<&'a ScoreTableRecord>),
+}
+
+/// Participant's figures aggregated over several month dashboards
+#[derive(Debug, Clone)]
+pub struct ParticipantTotals {
+  pub name: String,
+  pub filled_days: u32,
+  pub average_percent: f64,
+}
+
+/// Aggregates filled days and the average percentage per participant over the dashboards.
+/// Participants are matched by name since every month is a separate sheet.
+/// Ranked by filled days, then by average percentage, then by name.
+pub fn participant_totals(dashboards: &[Dashboard]) -> Vec<ParticipantTotals> {
+  let mut percents: Vec<(&str, Vec<i32>)> = Vec::new();
+  for table in dashboards.iter().filter_map(|d| d.tables()).flatten() {
+    let filled = table
+      .all_records()
+      .into_iter()
+      .filter(|rec| rec.has_total())
+      .map(|rec| rec.percent().value());
+    let name = table.person().name();
+    match percents.iter_mut().find(|(n, _)| *n == name) {
+      Some((_, values)) => values.extend(filled),
+      None => percents.push((name, filled.collect())),
+    }
+  }
+  let mut totals: Vec<_> = percents
+    .into_iter()
+    .map(|(name, values)| ParticipantTotals {
+      name: name.to_string(),
+      filled_days: values.len() as u32,
+      average_percent: if values.is_empty() {
+        0.
+      } else {
+        values.iter().sum::<i32>() as f64 / values.len() as f64
+      },
+    })
+    .collect();
+  totals.sort_by(|a, b| {
+    b.filled_days
+      .cmp(&a.filled_days)
+      .then_with(|| b.average_percent.total_cmp(&a.average_percent))
+      .then_with(|| a.name.cmp(&b.name))
+  });
+  totals
+}
+
+/// Participant's monthly averages over a range of month dashboards
+#[derive(Debug, Clone)]
+pub struct YearlyAverage {
+  pub name: String,
+  /// First day of the month with the average of the month's filled days, oldest first
+  pub monthly: Vec<(NaiveDate, f64)>,
+  pub filled_days: u32,
+  /// Average over all the filled days, so the fuller months weigh more
+  pub average_percent: f64,
+}
+
+/// Yearly averages of the participants who filled at least one day over the month dashboards keyed
+/// by the first day of the month. Participants are matched by name, ranked by the average, then by name.
+pub fn yearly_averages(months: &[(NaiveDate, Dashboard)]) -> Vec<YearlyAverage> {
+  let mut averages: Vec<YearlyAverage> = Vec::new();
+  for (month, dashboard) in months {
+    let analyzer = dashboard.build_analyzer();
+    for person in analyzer.participants().unwrap_or_default() {
+      let Some(stats) = analyzer.monthly_stats(person) else {
+        continue;
+      };
+      let index = match averages.iter().position(|a| a.name == person.name()) {
+        Some(index) => index,
+        None => {
+          averages.push(YearlyAverage {
+            name: person.name().to_string(),
+            monthly: Vec::new(),
+            filled_days: 0,
+            average_percent: 0.,
+          });
+          averages.len() - 1
+        }
+      };
+      let average = &mut averages[index];
+      let days = (average.filled_days + stats.filled_days) as f64;
+      average.average_percent = (average.average_percent * average.filled_days as f64
+        + stats.average_percent * stats.filled_days as f64)
+        / days;
+      average.filled_days += stats.filled_days;
+      average.monthly.push((*month, stats.average_percent));
+    }
+  }
+  averages.sort_by(|a, b| b.average_percent.total_cmp(&a.average_percent).then_with(|| a.name.cmp(&b.name)));
+  averages
+}
 
 pub struct DashboardAnalyzer<'a> {
   dashboard: &'a Dashboard,
@@ -25,6 +147,13 @@ impl<'a> DashboardAnalyzer<'a> {
     }
   }
 
+  pub fn last_filled_score_table_records(&self, person: &Person, count: usize) -> Vec<&'a ScoreTableRecord> {
+    match self.find_table(person) {
+      Some(table) => table.last_filled_records(count),
+      _ => Vec::new(),
+    }
+  }
+
   pub fn find_filled_score_table_record(&self, person: &Person, date: &NaiveDate) -> Option<&'a ScoreTableRecord> {
     match self.find_table(person) {
       Some(table) => table.by_date(date).filter(|&record| record.has_total()),
@@ -32,6 +161,367 @@ impl<'a> DashboardAnalyzer<'a> {
     }
   }
 
+  /// Participants without a filled record for the given date
+  pub fn missed(&self, date: &NaiveDate) -> Option<Vec<&'a Person>> {
+    self.participants().map(|persons| {
+      persons
+        .into_iter()
+        .filter(|p| self.find_filled_score_table_record(p, date).is_none())
+        .collect()
+    })
+  }
+
+  /// Person's rank by percentage among the participants who filled the table on the date,
+  /// as (position, filled participants). Equal percentages share the same position.
+  pub fn rank_on(&self, person: &Person, date: &NaiveDate) -> Option<(usize, usize)> {
+    let own = self.find_filled_score_table_record(person, date)?.percent();
+    let percents: Vec<_> = self
+      .participants()?
+      .into_iter()
+      .filter_map(|p| self.find_filled_score_table_record(p, date).map(|rec| rec.percent()))
+      .collect();
+    let position = 1 + percents.iter().filter(|&&p| p > own).count();
+    Some((position, percents.len()))
+  }
+
+  /// Participants who filled the table on the date with their percentages, highest first, ties are ordered by name
+  pub fn ranking_by_date(&self, date: &NaiveDate) -> Vec<(&'a Person, &'a Percentage)> {
+    let mut ranking: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| self.find_filled_score_table_record(p, date).map(|rec| (p, rec.percent())))
+      .collect();
+    ranking.sort_by(|(a_person, a), (b_person, b)| b.cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
+    ranking
+  }
+
+  /// Random participant who filled the date, the higher the percentage the higher the chance.
+  /// Everyone is equally likely when nobody has filled the date (or everyone has 0%).
+  pub fn weighted_pick(&self, date: &NaiveDate, rng: &mut impl Rng) -> Option<&'a Person> {
+    let ranking = self.ranking_by_date(date);
+    match WeightedIndex::new(ranking.iter().map(|(_, percent)| percent.value().max(0))) {
+      Ok(index) => Some(ranking[index.sample(rng)].0),
+      Err(_) => self.participants()?.choose(rng).copied(),
+    }
+  }
+
+  /// Daily ranks for the week ending at `end_date` (inclusive), oldest day first
+  pub fn daily_ranks(&self, person: &Person, end_date: &NaiveDate) -> Vec<(NaiveDate, Option<(usize, usize)>)> {
+    (0..7)
+      .rev()
+      .map(|days_ago| *end_date - Duration::days(days_ago))
+      .map(|date| (date, self.rank_on(person, &date)))
+      .collect()
+  }
+
+  /// Share of participants (0.0..=1.0) who filled the table for each of the `days` days ending at `end_date`,
+  /// oldest day first. Empty if there are no participants.
+  pub fn participation_rates(&self, end_date: &NaiveDate, days: u32) -> Vec<(NaiveDate, f64)> {
+    let total = self.participants().map_or(0, |p| p.len());
+    if total == 0 {
+      return Vec::new();
+    }
+    (0..days as i64)
+      .rev()
+      .map(|days_ago| *end_date - Duration::days(days_ago))
+      .map(|date| {
+        let missed = self.missed(&date).map_or(total, |m| m.len());
+        (date, (total - missed) as f64 / total as f64)
+      })
+      .collect()
+  }
+
+  /// Mean percentage of the person's filled records within the dates range (inclusive)
+  pub fn average_percent_between(&self, person: &Person, from: &NaiveDate, to: &NaiveDate) -> Option<f64> {
+    let percents: Vec<i32> = self
+      .find_table(person)?
+      .all_records()
+      .into_iter()
+      .filter(|rec| rec.has_total() && rec.date() >= from && rec.date() <= to)
+      .map(|rec| rec.percent().value())
+      .collect();
+    if percents.is_empty() {
+      return None;
+    }
+    Some(percents.iter().sum::<i32>() as f64 / percents.len() as f64)
+  }
+
+  /// This week's (Monday up to `today`) average percentage minus the previous week's one.
+  /// `None` if either week has no filled days.
+  pub fn weekly_improvement(&self, person: &Person, today: &NaiveDate) -> Option<f64> {
+    let this_monday = *today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let last_monday = this_monday - Duration::days(7);
+    let last_sunday = this_monday - Duration::days(1);
+    let this_week = self.average_percent_between(person, &this_monday, today)?;
+    let last_week = self.average_percent_between(person, &last_monday, &last_sunday)?;
+    Some(this_week - last_week)
+  }
+
+  /// Mean percentage of the participants who filled the table on the date
+  pub fn average_percent(&self, date: &NaiveDate) -> Option<f64> {
+    let percents: Vec<i32> = self
+      .participants()?
+      .into_iter()
+      .filter_map(|p| self.find_filled_score_table_record(p, date))
+      .map(|rec| rec.percent().value())
+      .collect();
+    if percents.is_empty() {
+      return None;
+    }
+    Some(percents.iter().sum::<i32>() as f64 / percents.len() as f64)
+  }
+
+  /// Participants ordered by the average percentage since the beginning of the month up to the date (inclusive).
+  /// Participants without filled days are not ranked.
+  pub fn standings(&self, date: &NaiveDate) -> Vec<(&'a Person, f64)> {
+    let month_start = date.with_day(1).unwrap_or(*date);
+    let mut standings: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| self.average_percent_between(p, &month_start, date).map(|avg| (p, avg)))
+      .collect();
+    standings.sort_by(|(a_person, a), (b_person, b)| b.total_cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
+    standings
+  }
+
+  /// Participants whose percentage on the date is above their best one among the earlier filled days of the month,
+  /// as (person, percentage on the date, previous best). The first filled day is not considered a record,
+  /// since there is nothing to beat yet.
+  pub fn new_personal_bests(&self, date: &NaiveDate) -> Vec<(&'a Person, Percentage, Percentage)> {
+    self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|person| {
+        let today = self.find_filled_score_table_record(person, date)?.percent();
+        let previous_best = self
+          .find_table(person)?
+          .all_records()
+          .into_iter()
+          .filter(|rec| rec.has_total() && rec.date() < date && rec.date().month() == date.month())
+          .map(|rec| rec.percent())
+          .max()?;
+        (today > previous_best).then(|| (person, today.clone(), previous_best.clone()))
+      })
+      .collect()
+  }
+
+  /// Amount of filled days per participant, most filled first, ties are ordered by name
+  pub fn filled_day_counts(&self) -> Vec<(&'a Person, u32)> {
+    let mut counts: Vec<_> = self
+      .dashboard
+      .tables()
+      .into_iter()
+      .flatten()
+      .map(|table| {
+        let filled = table.all_records().into_iter().filter(|rec| rec.has_total()).count();
+        (table.person(), filled as u32)
+      })
+      .collect();
+    counts.sort_by(|(a_person, a), (b_person, b)| b.cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
+    counts
+  }
+
+  /// `None` if the participant hasn't filled any day. The earliest day wins among equal best (worst) ones.
+  pub fn monthly_stats(&self, person: &Person) -> Option<MonthlyStats> {
+    let records: Vec<_> = self.find_table(person)?.filled_records().collect();
+    let best = records.iter().rev().max_by_key(|rec| rec.percent())?;
+    let worst = records.iter().rev().min_by_key(|rec| rec.percent())?;
+    let sum: i64 = records.iter().map(|rec| rec.percent().value() as i64).sum();
+    Some(MonthlyStats {
+      filled_days: records.len() as u32,
+      average_percent: sum as f64 / records.len() as f64,
+      best: (*best.date(), best.percent().clone()),
+      worst: (*worst.date(), worst.percent().clone()),
+    })
+  }
+
+  /// Sorted dates of the person's filled records
+  pub fn filled_dates(&self, person: &Person) -> Vec<NaiveDate> {
+    let mut dates: Vec<NaiveDate> = self
+      .find_table(person)
+      .map(|table| table.filled_records().map(|rec| *rec.date()).collect())
+      .unwrap_or_default();
+    dates.sort();
+    dates.dedup();
+    dates
+  }
+
+  /// Mean percentage of the person's filled days this month
+  pub fn monthly_average(&self, person: &Person) -> Option<f64> {
+    self.monthly_stats(person).map(|stats| stats.average_percent)
+  }
+
+  /// Month-to-date averages sorted descending, with the latest filled day compared to the own average.
+  /// The comparison is `None` for a single filled day.
+  pub fn trends(&self) -> Vec<(&'a Person, f64, Option<Ordering>)> {
+    let mut trends: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|person| {
+        let average = self.monthly_average(person)?;
+        let table = self.find_table(person)?;
+        let trend = if table.filled_records().nth(1).is_some() {
+          let latest = table.last_filled_record()?.percent().value() as f64;
+          latest.partial_cmp(&average)
+        } else {
+          None
+        };
+        Some((person, average, trend))
+      })
+      .collect();
+    trends.sort_by(|(a_person, a, _), (b_person, b, _)| b.total_cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
+    trends
+  }
+
+  /// `None` if any of the participants isn't found or neither of them has filled any day
+  pub fn compare(&self, a: &Person, b: &Person) -> Option<Comparison<'a>> {
+    let (first, second) = (self.find_table(a)?, self.find_table(b)?);
+    let (first_record, second_record) = (first.last_filled_record(), second.last_filled_record());
+    if first_record.is_none() && second_record.is_none() {
+      return None;
+    }
+    Some(Comparison {
+      first: (first.person(), first_record),
+      second: (second.person(), second_record),
+    })
+  }
+
+  /// Consecutive calendar days filled by the participant up to today, or up to yesterday while today
+  /// isn't filled yet. Zero if neither of them is filled.
+  pub fn current_streak(&self, person: &Person, today: &NaiveDate) -> u32 {
+    let Some(table) = self.find_table(person) else {
+      return 0;
+    };
+    let filled: HashSet<NaiveDate> = table.filled_records().map(|rec| *rec.date()).collect();
+    let mut day = if filled.contains(today) {
+      *today
+    } else {
+      match today.pred_opt() {
+        Some(yesterday) => yesterday,
+        None => return 0,
+      }
+    };
+    let mut streak = 0;
+    while filled.contains(&day) {
+      streak += 1;
+      match day.pred_opt() {
+        Some(previous) => day = previous,
+        None => break,
+      }
+    }
+    streak
+  }
+
+  /// Current streaks of every participant, longest first, ties are ordered by name
+  pub fn current_streaks(&self, today: &NaiveDate) -> Vec<(&'a Person, u32)> {
+    let mut streaks: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .map(|person| (person, self.current_streak(person, today)))
+      .collect();
+    streaks.sort_by(|(a_person, a), (b_person, b)| b.cmp(a).then_with(|| a_person.name().cmp(b_person.name())));
+    streaks
+  }
+
+  /// Sum of every category across the participants who filled the table on the date, with the contributors count.
+  /// Categories nobody filled are skipped.
+  pub fn group_category_totals(&self, date: &NaiveDate) -> Vec<(Category, f64, usize)> {
+    let records: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| self.find_filled_score_table_record(p, date))
+      .collect();
+    Category::ALL
+      .into_iter()
+      .filter_map(|category| {
+        let values: Vec<f64> = records
+          .iter()
+          .filter_map(|rec| rec.scores().get(category))
+          .collect();
+        (!values.is_empty()).then(|| (category, values.iter().sum(), values.len()))
+      })
+      .collect()
+  }
+
+  /// Participants with the highest value of every category on the date, all of them on a tie.
+  /// Categories nobody filled are skipped.
+  pub fn category_leaders(&self, date: &NaiveDate) -> Vec<(Category, Vec<&'a Person>, f64)> {
+    let records: Vec<_> = self
+      .participants()
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|p| self.find_filled_score_table_record(p, date).map(|rec| (p, rec)))
+      .collect();
+    Category::ALL
+      .into_iter()
+      .filter_map(|category| {
+        let values: Vec<(&'a Person, f64)> = records
+          .iter()
+          .filter_map(|(p, rec)| rec.scores().get(category).map(|value| (*p, value)))
+          .collect();
+        let best = values.iter().map(|(_, value)| *value).reduce(f64::max)?;
+        let leaders = values.into_iter().filter(|(_, value)| *value == best).map(|(p, _)| p).collect();
+        Some((category, leaders, best))
+      })
+      .collect()
+  }
+
+  /// Estimates amount of days at `daily_percent` needed to raise person's average of the filled days
+  /// up to `target`, given `days_left` days are still available this month
+  pub fn estimate_days_to_goal(&self, person: &Person, target: f64, daily_percent: f64, days_left: u32) -> GoalEstimate {
+    let percents: Vec<i32> = self
+      .find_table(person)
+      .map(|t| t.all_records().into_iter().filter(|rec| rec.has_total()).map(|rec| rec.percent().value()).collect())
+      .unwrap_or_default();
+    let filled = percents.len() as f64;
+    let average = if percents.is_empty() {
+      0.
+    } else {
+      percents.iter().sum::<i32>() as f64 / filled
+    };
+
+    if !percents.is_empty() && average >= target {
+      return GoalEstimate::AlreadyReached { average };
+    }
+    if daily_percent < target {
+      return GoalEstimate::Unreachable {
+        needed: None,
+        available: days_left,
+        average,
+      };
+    }
+    // (filled * average + k * daily) / (filled + k) >= target
+    let needed = if daily_percent == target {
+      // Only possible with no history at all
+      if percents.is_empty() {
+        1
+      } else {
+        return GoalEstimate::Unreachable {
+          needed: None,
+          available: days_left,
+          average,
+        };
+      }
+    } else {
+      ((filled * (target - average) / (daily_percent - target)).ceil() as u32).max(1)
+    };
+    if needed > days_left {
+      GoalEstimate::Unreachable {
+        needed: Some(needed),
+        available: days_left,
+        average,
+      }
+    } else {
+      GoalEstimate::Days { needed, average }
+    }
+  }
+
   pub fn get_person_by_name(&self, name: &str) -> Option<&'a Person> {
     if let Some(tables) = self.dashboard.tables() {
       return tables
@@ -49,3 +539,92 @@ impl<'a> DashboardAnalyzer<'a> {
     None
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use rand::{rngs::StdRng, SeedableRng};
+
+  use super::*;
+  use crate::dashboard::fake::{date, empty_record, record, table};
+
+  #[test]
+  fn weighted_pick_favours_higher_percentage() {
+    let today = date(2024, 1, 31);
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(today, 90)]),
+      table("Борис", vec![record(today, 10)]),
+      table("Вера", vec![]),
+    ]);
+    let analyzer = dashboard.build_analyzer();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let picks: Vec<_> = (0..1000).filter_map(|_| analyzer.weighted_pick(&today, &mut rng)).collect();
+    let anna = picks.iter().filter(|p| p.name() == "Анна").count();
+    assert_eq!(picks.len(), 1000);
+    assert!(picks.iter().all(|p| p.name() != "Вера"));
+    assert!(anna > 800, "Анна picked {anna} times");
+  }
+
+  #[test]
+  fn weighted_pick_is_uniform_when_nobody_filled() {
+    let today = date(2024, 1, 31);
+    let dashboard = Dashboard::from(vec![table("Анна", vec![]), table("Борис", vec![])]);
+    let analyzer = dashboard.build_analyzer();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let picks: Vec<_> = (0..100).filter_map(|_| analyzer.weighted_pick(&today, &mut rng)).collect();
+    assert_eq!(picks.len(), 100);
+    assert!(picks.iter().any(|p| p.name() == "Анна") && picks.iter().any(|p| p.name() == "Борис"));
+    assert!(Dashboard::new().build_analyzer().weighted_pick(&today, &mut rng).is_none());
+  }
+
+  #[test]
+  fn personal_best_has_to_beat_the_previous_one() {
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(date(2024, 1, 1), 50), record(date(2024, 1, 2), 60)]),
+      table("Борис", vec![record(date(2024, 1, 1), 70), record(date(2024, 1, 2), 70)]),
+      table("Вера", vec![empty_record(date(2024, 1, 1)), record(date(2024, 1, 2), 90)]),
+    ]);
+    let analyzer = dashboard.build_analyzer();
+
+    let bests = analyzer.new_personal_bests(&date(2024, 1, 2));
+    assert_eq!(bests.len(), 1);
+    let (person, today, previous) = &bests[0];
+    assert_eq!(person.name(), "Анна");
+    assert_eq!((today.value(), previous.value()), (60, 50));
+  }
+
+  #[test]
+  fn first_filled_day_is_not_a_personal_best() {
+    let dashboard = Dashboard::from(vec![table("Анна", vec![record(date(2024, 1, 1), 90)])]);
+    assert!(dashboard.build_analyzer().new_personal_bests(&date(2024, 1, 1)).is_empty());
+  }
+
+  #[test]
+  fn participation_rate_is_the_share_of_ones_who_filled() {
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(date(2024, 1, 1), 50), record(date(2024, 1, 2), 60)]),
+      table("Борис", vec![empty_record(date(2024, 1, 1)), record(date(2024, 1, 2), 70)]),
+      table("Вера", vec![empty_record(date(2024, 1, 1))]),
+      table("Глеб", vec![]),
+    ]);
+
+    let rates = dashboard.build_analyzer().participation_rates(&date(2024, 1, 3), 3);
+    assert_eq!(rates, [(date(2024, 1, 1), 0.25), (date(2024, 1, 2), 0.5), (date(2024, 1, 3), 0.)]);
+    assert!(Dashboard::new().build_analyzer().participation_rates(&date(2024, 1, 3), 3).is_empty());
+  }
+
+  #[test]
+  fn filled_day_counts_are_ordered_by_count_then_by_name() {
+    let dashboard = Dashboard::from(vec![
+      table("Вера", vec![record(date(2024, 1, 1), 50), empty_record(date(2024, 1, 2))]),
+      table("Анна", vec![record(date(2024, 1, 1), 50)]),
+      table("Борис", vec![record(date(2024, 1, 1), 50), record(date(2024, 1, 2), 60)]),
+      table("Глеб", vec![empty_record(date(2024, 1, 1))]),
+    ]);
+
+    let analyzer = dashboard.build_analyzer();
+    let counts: Vec<_> = analyzer.filled_day_counts().into_iter().map(|(p, count)| (p.name(), count)).collect();
+    assert_eq!(counts, [("Борис", 2), ("Анна", 1), ("Вера", 1), ("Глеб", 0)]);
+  }
+}
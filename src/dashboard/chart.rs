@@ -0,0 +1,149 @@
+use chrono::{Duration, NaiveDate};
+use png::EncodingError;
+
+use super::Dashboard;
+
+const CELL: u32 = 18;
+const GAP: u32 = 2;
+const MARGIN: u32 = 10;
+/// Pixel size of a glyph dot
+const SCALE: u32 = 2;
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const LEGEND_STEPS: [i32; 3] = [0, 50, 100];
+
+type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [255, 255, 255];
+const UNFILLED: Rgb = [230, 230, 230];
+const TEXT: Rgb = [60, 60, 60];
+
+/// 3x5 bitmap glyphs, one row per element, the most significant of 3 bits is the leftmost dot
+fn glyph(c: char) -> Option<[u8; 5]> {
+  let rows = match c {
+    '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+    '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+    '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+    '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+    '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+    '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+    '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+    '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+    '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+    '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+    '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+    _ => return None,
+  };
+  Some(rows)
+}
+
+/// Red (0%) -> yellow (50%) -> green (100%), parsed percentages never exceed 100%
+fn percent_color(percent: i32) -> Rgb {
+  let lerp = |from: u8, to: u8, t: f64| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+  let mix = |from: Rgb, to: Rgb, t: f64| [lerp(from[0], to[0], t), lerp(from[1], to[1], t), lerp(from[2], to[2], t)];
+  let (red, yellow, green) = ([230, 80, 70], [245, 205, 70], [90, 190, 90]);
+  match percent {
+    p if p <= 0 => red,
+    p if p < 50 => mix(red, yellow, p as f64 / 50.),
+    p if p < 100 => mix(yellow, green, (p - 50) as f64 / 50.),
+    _ => green,
+  }
+}
+
+struct Canvas {
+  width: u32,
+  height: u32,
+  pixels: Vec<u8>,
+}
+
+impl Canvas {
+  fn new(width: u32, height: u32) -> Self {
+    let pixels = BACKGROUND.repeat((width * height) as usize);
+    Self { width, height, pixels }
+  }
+
+  fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Rgb) {
+    for py in y..(y + height).min(self.height) {
+      for px in x..(x + width).min(self.width) {
+        let offset = ((py * self.width + px) * 3) as usize;
+        self.pixels[offset..offset + 3].copy_from_slice(&color);
+      }
+    }
+  }
+
+  fn text(&mut self, x: u32, y: u32, text: &str) {
+    for (i, rows) in text.chars().filter_map(glyph).enumerate() {
+      let glyph_x = x + i as u32 * (GLYPH_WIDTH + 1) * SCALE;
+      for (row, bits) in rows.iter().enumerate() {
+        for col in 0..GLYPH_WIDTH {
+          if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+            self.fill_rect(glyph_x + col * SCALE, y + row as u32 * SCALE, SCALE, SCALE, TEXT);
+          }
+        }
+      }
+    }
+  }
+
+  fn encode(&self) -> Result<Vec<u8>, EncodingError> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&self.pixels)?;
+    Ok(bytes)
+  }
+}
+
+fn text_width(text: &str) -> u32 {
+  text.chars().count() as u32 * (GLYPH_WIDTH + 1) * SCALE
+}
+
+/// Renders PNG heatmap of the participants (rows, numbered from 1 in the dashboard order) by the days
+/// (columns) starting at `first_day`. Cells are shaded by the day's percentage, unfilled days are gray.
+pub fn render_month_table(dashboard: &Dashboard, first_day: &NaiveDate, days: u32) -> Result<Vec<u8>, EncodingError> {
+  let participants = dashboard.participants().unwrap_or_default();
+  let rows = participants.len() as u32;
+  let label_width = text_width(&rows.max(1).to_string()) + GAP * 2;
+  let glyph_height = GLYPH_HEIGHT * SCALE;
+
+  let grid_x = MARGIN + label_width;
+  let grid_y = MARGIN + glyph_height + GAP * 2;
+  let grid_width = days * (CELL + GAP);
+  let grid_height = rows * (CELL + GAP);
+  let legend_y = grid_y + grid_height + MARGIN;
+  let legend_width = LEGEND_STEPS.len() as u32 * (CELL + GAP + text_width("100%") + MARGIN);
+
+  let width = grid_x + grid_width.max(legend_width) + MARGIN;
+  let height = legend_y + CELL + MARGIN;
+  let mut canvas = Canvas::new(width, height);
+
+  // Header with the day numbers
+  for day in 0..days {
+    let label = (day + 1).to_string();
+    let x = grid_x + day * (CELL + GAP) + CELL.saturating_sub(text_width(&label)) / 2;
+    canvas.text(x, MARGIN, &label);
+  }
+
+  for (row, person) in participants.iter().enumerate() {
+    let y = grid_y + row as u32 * (CELL + GAP);
+    canvas.text(MARGIN, y + (CELL - glyph_height) / 2, &(row + 1).to_string());
+    for day in 0..days {
+      let date = *first_day + Duration::days(day as i64);
+      let color = dashboard
+        .find_filled_score_table_record(person, &date)
+        .map_or(UNFILLED, |rec| percent_color(rec.percent().value()));
+      canvas.fill_rect(grid_x + day * (CELL + GAP), y, CELL, CELL, color);
+    }
+  }
+
+  // Legend: color samples with their percentage
+  let mut x = grid_x;
+  for step in LEGEND_STEPS {
+    canvas.fill_rect(x, legend_y, CELL, CELL, percent_color(step));
+    let label = format!("{}%", step);
+    canvas.text(x + CELL + GAP, legend_y + (CELL - glyph_height) / 2, &label);
+    x += CELL + GAP + text_width(&label) + MARGIN;
+  }
+
+  canvas.encode()
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+
+use super::{
+  score_table::entities::{Percentage, Person},
+  Dashboard,
+};
+
+/// Filled record that has appeared or changed its percentage between two fetches
+#[derive(Debug)]
+pub struct PercentChange<'a> {
+  pub person: &'a Person,
+  pub date: NaiveDate,
+  pub previous: Option<&'a Percentage>,
+  pub latest: &'a Percentage,
+}
+
+/// Difference between the replaced and the freshly fetched dashboards
+pub struct DashboardDiff<'a> {
+  changes: Vec<PercentChange<'a>>,
+}
+
+impl<'a> DashboardDiff<'a> {
+  pub fn between(previous: &'a Dashboard, latest: &'a Dashboard) -> Self {
+    let mut changes = Vec::new();
+    for table in latest.tables().into_iter().flatten() {
+      let person = table.person();
+      let previous_person = previous.get_person_by_name(person.name());
+      for record in table.all_records().into_iter().filter(|rec| rec.has_total()) {
+        let previous_percent = previous_person
+          .and_then(|p| previous.find_filled_score_table_record(p, record.date()))
+          .map(|rec| rec.percent());
+        if previous_percent != Some(record.percent()) {
+          changes.push(PercentChange {
+            person,
+            date: *record.date(),
+            previous: previous_percent,
+            latest: record.percent(),
+          });
+        }
+      }
+    }
+    Self { changes }
+  }
+
+  pub fn changes(&self) -> &[PercentChange<'a>] {
+    &self.changes
+  }
+
+  pub fn changes_on(&self, date: &NaiveDate) -> impl Iterator<Item = &PercentChange<'a>> {
+    let date = *date;
+    self.changes.iter().filter(move |c| c.date == date)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.changes.is_empty()
+  }
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDate;
+
+use super::score_table::{
+  entities::{Category, Percentage, Person, Scores},
+  ScoreTable, ScoreTableRecord,
+};
+
+/// Filled record of the day with the single sport score
+pub fn record(date: NaiveDate, percent: i32) -> ScoreTableRecord {
+  let scores = Scores::default().with_overrides(&[(Category::Sport, 1.)]);
+  ScoreTableRecord::new(date, scores, Some(1.), Percentage::from(percent))
+}
+
+/// Record of the day nobody has touched yet
+pub fn empty_record(date: NaiveDate) -> ScoreTableRecord {
+  ScoreTableRecord::new(date, Scores::default(), None, Percentage::default())
+}
+
+pub fn table(name: &str, records: Vec<ScoreTableRecord>) -> ScoreTable {
+  ScoreTable::new(Person::new(name.to_string()), records)
+}
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+  NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
@@ -1,30 +1,143 @@
 use self::{
-  analyzer::DashboardAnalyzer,
+  analyzer::{Comparison, DashboardAnalyzer, GoalEstimate, MonthlyStats},
   score_table::ScoreTableRecord,
-  score_table::{entities::Person, ScoreTable},
+  score_table::{
+    entities::{Category, Percentage, Person},
+    ScoreTable,
+  },
 };
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use log::{debug, error, trace, warn};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, time::Duration};
+use thiserror::Error;
+
+use crate::helpers::{self, i18n};
 
 pub mod analyzer;
+pub mod chart;
+pub mod diff;
+#[cfg(test)]
+pub mod fake;
 pub mod score_table;
 
-#[derive(Default)]
+/// Performance numbers of the fetch that produced the dashboard
+#[derive(Debug, Clone, Copy)]
+pub struct FetchStats {
+  pub duration: Duration,
+  pub round_trips: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct Dashboard {
   score_tables: Option<Vec<ScoreTable>>,
+  /// Kept in the on-disk cache, so that the age of the cached data is known as well
+  #[serde(default)]
+  last_fetched: Option<DateTime<Utc>>,
+  /// Title of the sheet the tables were fetched from, kept in the on-disk cache
+  /// so that the cache of another month's sheet is not used
+  #[serde(default)]
+  sheet_title: Option<String>,
+  /// Not kept in the on-disk cache, the cached dashboard is not a result of a fetch
+  #[serde(skip)]
+  fetch_stats: Option<FetchStats>,
 }
 
 impl Dashboard {
   pub fn new() -> Self {
-    Self { score_tables: None }
+    Self {
+      score_tables: None,
+      last_fetched: None,
+      sheet_title: None,
+      fetch_stats: None,
+    }
   }
 
   pub fn from(score_tables: Vec<ScoreTable>) -> Self {
     Self {
       score_tables: Some(score_tables),
+      last_fetched: Some(helpers::current_time_utc()),
+      sheet_title: None,
+      fetch_stats: None,
+    }
+  }
+
+  /// Copy of the dashboard where every table is preceded by the same-named person's records
+  /// from the previous month dashboard, so the analysis can look across the month boundary
+  pub fn with_previous_month(&self, previous: &Dashboard) -> Dashboard {
+    let tables = self.tables().map(|tables| {
+      tables
+        .iter()
+        .map(|table| {
+          match previous
+            .get_person_by_name(table.person().name())
+            .and_then(|p| previous.find_table(p))
+          {
+            Some(earlier) => table.preceded_by(earlier),
+            None => table.clone(),
+          }
+        })
+        .collect()
+    });
+    Self {
+      score_tables: tables,
+      last_fetched: self.last_fetched,
+      sheet_title: self.sheet_title.clone(),
+      fetch_stats: self.fetch_stats,
     }
   }
 
+  pub fn set_fetch_stats(&mut self, stats: FetchStats) {
+    self.fetch_stats = Some(stats);
+  }
+
+  pub fn set_sheet_title(&mut self, title: String) {
+    self.sheet_title = Some(title);
+  }
+
+  /// Title of the sheet the tables were fetched from, `None` for the caches written by the older versions
+  pub fn sheet_title(&self) -> Option<&str> {
+    self.sheet_title.as_deref()
+  }
+
+  /// Moment the tables were fetched from Sheets, `None` if the dashboard isn't a result of a fetch
+  pub fn last_fetched(&self) -> Option<&DateTime<Utc>> {
+    self.last_fetched.as_ref()
+  }
+
+  pub fn fetch_stats(&self) -> Option<&FetchStats> {
+    self.fetch_stats.as_ref()
+  }
+
+  /// Total amount of records over all the tables
+  pub fn records_count(&self) -> usize {
+    self.tables().map_or(0, |tables| tables.iter().map(|t| t.records_count()).sum())
+  }
+
+  /// Rough estimate of the memory held by the parsed tables
+  pub fn estimated_size_bytes(&self) -> usize {
+    let names: usize = self
+      .participants()
+      .map_or(0, |persons| persons.iter().map(|p| p.name().len()).sum());
+    self.records_count() * std::mem::size_of::<ScoreTableRecord>()
+      + self.tables().map_or(0, |tables| tables.len()) * std::mem::size_of::<ScoreTable>()
+      + names
+  }
+
+  /// Participants having rows which couldn't be parsed, with the amount of such rows
+  pub fn skipped_rows(&self) -> Vec<(&Person, usize)> {
+    self
+      .tables()
+      .map(|tables| {
+        tables
+          .iter()
+          .filter(|table| table.skipped_rows() > 0)
+          .map(|table| (table.person(), table.skipped_rows()))
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
   pub fn initialize(&mut self, score_tables: Vec<ScoreTable>) -> bool {
     if self.score_tables.is_none() {
       trace!("[Dashboard] Initialization done. (tables amount = {})", score_tables.len());
@@ -39,15 +152,29 @@ impl Dashboard {
     self.score_tables.as_ref()
   }
 
+  /// One MarkdownV2 line (name, percentage and emoji) per participant who filled the table on the date
   pub fn summary(&self, by_date: &NaiveDate) -> Result<Vec<String>, DashboardError> {
-    if let Some(persons) = self.participants() {
+    let persons = self.participants().ok_or_else(|| {
+      warn!("[Dashboard][Summary] The dashboard is not initialized");
+      DashboardError::NotInitialized
+    })?;
+    if !persons.is_empty() {
       debug!("[Dashboard][Summary] Found {} participants", persons.len());
       let summary: Vec<String> = persons
         .iter()
         .filter_map(|p| {
           self
             .find_filled_score_table_record(p, by_date)
-            .map(|rec| format!("{} молодец на {} {}", p.name(), rec.percent(), rec.percent().emoji()))
+            .map(|rec| {
+              i18n::fill(
+                i18n::tr(i18n::Text::SummaryLine),
+                &[
+                  &helpers::escape_markdown_v2(&p.display_name()),
+                  &rec.percent().to_string(),
+                  rec.percent().emoji(),
+                ],
+              )
+            })
         })
         .collect();
       return Ok(summary);
@@ -56,6 +183,20 @@ impl Dashboard {
     Err(DashboardError::EmptyParticipants)
   }
 
+  /// Filled record of the named participant for the date
+  pub fn record_on(&self, name: &str, date: &NaiveDate) -> Result<(&Person, &ScoreTableRecord), DashboardError> {
+    if self.tables().is_none() {
+      return Err(DashboardError::NotInitialized);
+    }
+    let person = self
+      .get_person_by_name(name)
+      .ok_or_else(|| DashboardError::PersonNotFound(name.to_string()))?;
+    let record = self
+      .find_filled_score_table_record(person, date)
+      .ok_or(DashboardError::NoRecordForDate(*date))?;
+    Ok((person, record))
+  }
+
   pub fn find_table(&self, person: &Person) -> Option<&ScoreTable> {
     self.build_analyzer().find_table(person)
   }
@@ -68,10 +209,97 @@ impl Dashboard {
     self.build_analyzer().last_filled_score_table_record(person)
   }
 
+  pub fn last_filled_score_table_records(&self, person: &Person, count: usize) -> Vec<&ScoreTableRecord> {
+    self.build_analyzer().last_filled_score_table_records(person, count)
+  }
+
   pub fn find_filled_score_table_record(&self, person: &Person, date: &NaiveDate) -> Option<&ScoreTableRecord> {
     self.build_analyzer().find_filled_score_table_record(person, date)
   }
 
+  pub fn missed(&self, date: &NaiveDate) -> Option<Vec<&Person>> {
+    self.build_analyzer().missed(date)
+  }
+
+  /// Participants without a filled record for today (participants' local date)
+  pub fn not_filled_today(&self) -> Vec<&Person> {
+    self.missed(&helpers::current_time_local().date_naive()).unwrap_or_default()
+  }
+
+  pub fn daily_ranks(&self, person: &Person, end_date: &NaiveDate) -> Vec<(NaiveDate, Option<(usize, usize)>)> {
+    self.build_analyzer().daily_ranks(person, end_date)
+  }
+
+  pub fn participation_rates(&self, end_date: &NaiveDate, days: u32) -> Vec<(NaiveDate, f64)> {
+    self.build_analyzer().participation_rates(end_date, days)
+  }
+
+  pub fn weekly_improvement(&self, person: &Person, today: &NaiveDate) -> Option<f64> {
+    self.build_analyzer().weekly_improvement(person, today)
+  }
+
+  pub fn average_percent(&self, date: &NaiveDate) -> Option<f64> {
+    self.build_analyzer().average_percent(date)
+  }
+
+  pub fn new_personal_bests(&self, date: &NaiveDate) -> Vec<(&Person, Percentage, Percentage)> {
+    self.build_analyzer().new_personal_bests(date)
+  }
+
+  pub fn standings(&self, date: &NaiveDate) -> Vec<(&Person, f64)> {
+    self.build_analyzer().standings(date)
+  }
+
+  pub fn filled_day_counts(&self) -> Vec<(&Person, u32)> {
+    self.build_analyzer().filled_day_counts()
+  }
+
+  pub fn monthly_stats(&self, person: &Person) -> Option<MonthlyStats> {
+    self.build_analyzer().monthly_stats(person)
+  }
+
+  pub fn filled_dates(&self, person: &Person) -> Vec<NaiveDate> {
+    self.build_analyzer().filled_dates(person)
+  }
+
+  pub fn trends(&self) -> Vec<(&Person, f64, Option<Ordering>)> {
+    self.build_analyzer().trends()
+  }
+
+  pub fn compare(&self, a: &Person, b: &Person) -> Option<Comparison<'_>> {
+    self.build_analyzer().compare(a, b)
+  }
+
+  pub fn ranking_by_date(&self, date: &NaiveDate) -> Vec<(&Person, &Percentage)> {
+    self.build_analyzer().ranking_by_date(date)
+  }
+
+  pub fn weighted_pick(&self, date: &NaiveDate, rng: &mut impl rand::Rng) -> Option<&Person> {
+    self.build_analyzer().weighted_pick(date, rng)
+  }
+
+  pub fn current_streak(&self, person: &Person, today: &NaiveDate) -> u32 {
+    self.build_analyzer().current_streak(person, today)
+  }
+
+  pub fn current_streaks(&self, today: &NaiveDate) -> Vec<(&Person, u32)> {
+    self.build_analyzer().current_streaks(today)
+  }
+
+  pub fn group_category_totals(&self, date: &NaiveDate) -> Vec<(Category, f64, usize)> {
+    self.build_analyzer().group_category_totals(date)
+  }
+
+  pub fn category_leaders(&self, date: &NaiveDate) -> Vec<(Category, Vec<&Person>, f64)> {
+    self.build_analyzer().category_leaders(date)
+  }
+
+  pub fn estimate_days_to_goal(&self, person: &Person, target: f64, daily_percent: f64, days_left: u32) -> GoalEstimate {
+    self
+      .build_analyzer()
+      .estimate_days_to_goal(person, target, daily_percent, days_left)
+  }
+
   /// Return list of the participants
   pub fn participants(&self) -> Option<Vec<&Person>> {
     self.build_analyzer().participants()
@@ -81,12 +309,54 @@ impl Dashboard {
     self.participants().map(|persons| persons.iter().map(|p| p.name()).collect())
   }
 
-  pub fn build_analyzer(&self) -> DashboardAnalyzer {
+  pub fn build_analyzer(&self) -> DashboardAnalyzer<'_> {
     DashboardAnalyzer::new(self)
   }
 }
 
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum DashboardError {
-  EmptyParticipants
+  #[error("Dashboard is not initialized")]
+  NotInitialized,
+  #[error("No participants have been identified")]
+  EmptyParticipants,
+  #[error("Person '{0}' was not found")]
+  PersonNotFound(String),
+  #[error("No filled record for {0}")]
+  NoRecordForDate(NaiveDate),
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dashboard::fake::{date, empty_record, record, table};
+
+  #[test]
+  fn summary_lists_everyone_who_filled() {
+    let day = date(2024, 1, 15);
+    let dashboard = Dashboard::from(vec![table("Анна", vec![record(day, 90)]), table("Борис", vec![record(day, 40)])]);
+
+    let summary = dashboard.summary(&day).unwrap();
+    assert_eq!(summary, ["Анна молодец на 90% 🔥", "Борис молодец на 40% 😐"]);
+  }
+
+  #[test]
+  fn summary_skips_the_ones_who_did_not_fill() {
+    let day = date(2024, 1, 15);
+    let dashboard = Dashboard::from(vec![
+      table("Анна", vec![record(day, 60)]),
+      table("Борис", vec![empty_record(day)]),
+      table("Вера", Vec::new()),
+    ]);
+
+    assert_eq!(dashboard.summary(&day).unwrap(), ["Анна молодец на 60% 👍"]);
+    assert!(dashboard.summary(&date(2024, 1, 16)).unwrap().is_empty());
+  }
+
+  #[test]
+  fn summary_of_empty_dashboard_is_an_error() {
+    let day = date(2024, 1, 15);
+    assert!(matches!(Dashboard::new().summary(&day), Err(DashboardError::NotInitialized)));
+    assert!(matches!(Dashboard::from(Vec::new()).summary(&day), Err(DashboardError::EmptyParticipants)));
+  }
 }
@@ -1,33 +1,155 @@
-use std::{
-  collections::hash_map::DefaultHasher,
-  hash::{Hash, Hasher},
-};
+use std::{borrow::Cow, collections::HashMap, sync::OnceLock};
 
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+/// Participant is identified by the name, which is also the serialized form
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub struct Person {
-  id: u64,
   name: String,
 }
 
 impl Person {
   pub fn new(name: String) -> Person {
-    let mut hasher = DefaultHasher::new();
-    name.hash(&mut hasher);
-    Person { id: hasher.finish(), name }
+    Person { name }
   }
 
   pub fn name(&self) -> &str {
     &self.name
   }
+
+  /// Name for rendering in messages, sanitized according to the installed `NameSanitization`.
+  /// The original `name()` is still used for matching.
+  pub fn display_name(&self) -> Cow<'_, str> {
+    NAME_SANITIZATION.get().copied().unwrap_or_default().apply(&self.name)
+  }
+}
+
+/// Rounding of the displayed numbers, installed once at startup. Stored values are never rounded.
+static DISPLAY_ROUNDING: OnceLock<DisplayRounding> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+  #[default]
+  Round,
+  Floor,
+  Ceil,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayRounding {
+  pub score_decimals: usize,
+  pub percent_decimals: usize,
+  pub percent_mode: RoundingMode,
 }
 
-impl PartialEq for Person {
-  fn eq(&self, other: &Self) -> bool {
-    self.id == other.id && self.name == other.name
+impl Default for DisplayRounding {
+  fn default() -> Self {
+    Self {
+      score_decimals: 1,
+      percent_decimals: 1,
+      percent_mode: RoundingMode::Round,
+    }
   }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+impl DisplayRounding {
+  /// Returns `false` if the rounding was already installed
+  pub fn install(self) -> bool {
+    DISPLAY_ROUNDING.set(self).is_ok()
+  }
+
+  fn current() -> DisplayRounding {
+    DISPLAY_ROUNDING.get().copied().unwrap_or_default()
+  }
+
+  /// Score value rounded half away from zero to the configured decimal places
+  pub fn score(value: f64) -> String {
+    let decimals = DisplayRounding::current().score_decimals;
+    format!("{:.*}", decimals, DisplayRounding::round(value, decimals, RoundingMode::Round))
+  }
+
+  /// Computed (e.g. averaged) percentage rounded according to the configured mode, without '%' sign
+  pub fn percent(value: f64) -> String {
+    format!("{:.*}", DisplayRounding::current().percent_decimals, DisplayRounding::rounded_percent(value))
+  }
+
+  /// Percentage the way [`DisplayRounding::percent`] shows it, e.g. to pick the sign matching the shown value
+  pub fn rounded_percent(value: f64) -> f64 {
+    let rounding = DisplayRounding::current();
+    DisplayRounding::round(value, rounding.percent_decimals, rounding.percent_mode)
+  }
+
+  fn round(value: f64, decimals: usize, mode: RoundingMode) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    // Removes the representation error, so that 4.95 is treated as 49.5 tenths rather than 49.4999...
+    let scaled = ((value * factor) * 1e9).round() / 1e9;
+    let rounded = match mode {
+      RoundingMode::Round => scaled.round(),
+      RoundingMode::Floor => scaled.floor(),
+      RoundingMode::Ceil => scaled.ceil(),
+    };
+    // Adding zero turns -0.0 (e.g. -0.04 rounded) into 0.0, so it's never shown as "-0.0"
+    rounded / factor + 0.
+  }
+}
+
+/// Display policy for names with characters that turn on MarkdownV2 formatting, installed once at startup
+static NAME_SANITIZATION: OnceLock<NameSanitization> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameSanitization {
+  /// Names are shown as is, formatters escape them
+  #[default]
+  Escape,
+  /// Formatting characters are removed
+  Strip,
+  /// Formatting characters are replaced with `REPLACEMENT`
+  Replace,
+}
+
+impl NameSanitization {
+  pub const REPLACEMENT: char = '·';
+
+  /// Returns `false` if the policy was already installed
+  pub fn install(self) -> bool {
+    NAME_SANITIZATION.set(self).is_ok()
+  }
+
+  /// Name sanitized according to the policy
+  pub fn apply(self, name: &str) -> Cow<'_, str> {
+    match self {
+      NameSanitization::Escape => Cow::Borrowed(name),
+      NameSanitization::Strip => Cow::Owned(name.chars().filter(|c| !NameSanitization::is_dangerous(*c)).collect()),
+      NameSanitization::Replace => Cow::Owned(
+        name
+          .chars()
+          .map(|c| if NameSanitization::is_dangerous(c) { NameSanitization::REPLACEMENT } else { c })
+          .collect(),
+      ),
+    }
+  }
+
+  fn is_dangerous(c: char) -> bool {
+    matches!(c, '*' | '_' | '[' | ']' | '(' | ')' | '~' | '`' | '|' | '\\' | '>')
+  }
+}
+
+impl From<String> for Person {
+  fn from(name: String) -> Self {
+    Person::new(name)
+  }
+}
+
+impl From<Person> for String {
+  fn from(person: Person) -> Self {
+    person.name
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Percentage {
   value: i32,
 }
@@ -37,19 +159,26 @@ impl Percentage {
     self.value
   }
 
-  pub fn emoji(&self) -> &str {
-    if self.value() < 100 {
-      "💪🏻"
-    } else if self.value() < 150 {
-      "⚡️"
+  pub fn emoji(&self) -> &'static str {
+    if self.value() >= 80 {
+      "🔥"
+    } else if self.value() >= 50 {
+      "👍"
     } else {
-      "🏆"
+      "😐"
     }
   }
 
   pub fn from(value: i32) -> Percentage {
     Percentage { value }
   }
+
+  /// Percentage of a parsed cell brought into 0..=100
+  pub fn from_clamped(value: i32) -> Percentage {
+    Percentage {
+      value: value.clamp(0, 100),
+    }
+  }
 }
 
 impl std::fmt::Display for Percentage {
@@ -58,72 +187,428 @@ impl std::fmt::Display for Percentage {
   }
 }
 
-#[derive(Debug)]
+/// Emoji and label overrides for every category, installed once at startup
+static CATEGORY_APPEARANCE: OnceLock<HashMap<Category, CategoryAppearance>> = OnceLock::new();
+
+/// Categories whose columns hold checkboxes (TRUE/FALSE) instead of numbers, installed once at startup
+static CHECKBOX_CATEGORIES: OnceLock<Vec<Category>> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+  // Per thread, so that the tests running in parallel can install their own settings
+  static TEST_CHECKBOX_CATEGORIES: std::cell::RefCell<Option<Vec<Category>>> = const { std::cell::RefCell::new(None) };
+  static TEST_CATEGORY_APPEARANCE: std::cell::Cell<Option<&'static HashMap<Category, CategoryAppearance>>> =
+    const { std::cell::Cell::new(None) };
+}
+
+/// Custom look of a category. Unspecified fields fall back to the built-in defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryAppearance {
+  emoji: Option<String>,
+  label: Option<String>,
+  /// Column the group doesn't track, it's left out of the per-day scores
+  hidden: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+  Sport,
+  ProfessionalGrowth,
+  Health,
+  SpiritualGrowth,
+  ForeignLanguage,
+  PersonalDev,
+}
+
+impl Category {
+  pub const COUNT: usize = 6;
+
+  /// All categories in the order of the sheet columns
+  pub const ALL: [Category; Category::COUNT] = [
+    Category::Sport,
+    Category::ProfessionalGrowth,
+    Category::Health,
+    Category::SpiritualGrowth,
+    Category::ForeignLanguage,
+    Category::PersonalDev,
+  ];
+
+  /// Categories the group tracks, in the order of the sheet columns
+  pub fn shown() -> impl Iterator<Item = Category> {
+    Category::ALL.into_iter().filter(|c| !c.is_hidden())
+  }
+
+  pub fn is_hidden(&self) -> bool {
+    self.appearance().and_then(|a| a.hidden).unwrap_or(false)
+  }
+
+  /// Key used to reference the category in the config file
+  pub fn key(&self) -> &'static str {
+    match self {
+      Category::Sport => "sport",
+      Category::ProfessionalGrowth => "professional_growth",
+      Category::Health => "health",
+      Category::SpiritualGrowth => "spiritual_growth",
+      Category::ForeignLanguage => "foreign_language",
+      Category::PersonalDev => "personal_dev",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Category> {
+    Category::ALL.into_iter().find(|c| c.key() == key)
+  }
+
+  /// Category of the sheet column within the participant's block, the columns 1..=6 follow the date one
+  pub fn from_column(column: usize) -> Option<Category> {
+    Category::ALL.get(column.checked_sub(1)?).copied()
+  }
+
+  /// Zero-based position among the categories, same as the order of the sheet columns
+  pub fn index(&self) -> usize {
+    *self as usize
+  }
+
+  pub fn emoji(&self) -> &str {
+    match self.appearance().and_then(|a| a.emoji.as_deref()) {
+      Some(emoji) => emoji,
+      None => self.default_emoji(),
+    }
+  }
+
+  pub fn label(&self) -> &str {
+    match self.appearance().and_then(|a| a.label.as_deref()) {
+      Some(label) => label,
+      None => self.default_label(),
+    }
+  }
+
+  /// Installs the overrides loaded from config, unknown keys are ignored.
+  /// `labels` name the columns in order, the label of the category's own section takes precedence.
+  /// Returns `false` if the overrides were already installed.
+  pub fn set_appearance_overrides(overrides: &HashMap<String, CategoryAppearance>, labels: &[String]) -> bool {
+    CATEGORY_APPEARANCE.set(Category::resolve_appearance(overrides, labels)).is_ok()
+  }
+
+  /// Replaces the appearance overrides of the current thread
+  #[cfg(test)]
+  pub fn set_test_appearance_overrides(overrides: &HashMap<String, CategoryAppearance>, labels: &[String]) {
+    let resolved = Box::leak(Box::new(Category::resolve_appearance(overrides, labels)));
+    TEST_CATEGORY_APPEARANCE.with(|appearance| appearance.set(Some(resolved)));
+  }
+
+  fn resolve_appearance(
+    overrides: &HashMap<String, CategoryAppearance>,
+    labels: &[String],
+  ) -> HashMap<Category, CategoryAppearance> {
+    let mut resolved: HashMap<Category, CategoryAppearance> = overrides
+      .iter()
+      .filter_map(|(key, appearance)| Category::from_key(key).map(|c| (c, appearance.clone())))
+      .collect();
+    for (category, label) in Category::ALL.into_iter().zip(labels) {
+      let label = label.trim();
+      if !label.is_empty() {
+        resolved.entry(category).or_default().label.get_or_insert_with(|| label.to_string());
+      }
+    }
+    resolved
+  }
+
+  /// Returns `false` if the checkbox categories were already installed
+  pub fn set_checkbox_categories(categories: Vec<Category>) -> bool {
+    CHECKBOX_CATEGORIES.set(categories).is_ok()
+  }
+
+  pub fn is_checkbox(&self) -> bool {
+    #[cfg(test)]
+    if let Some(checkbox) = TEST_CHECKBOX_CATEGORIES.with(|c| c.borrow().as_ref().map(|c| c.contains(self))) {
+      return checkbox;
+    }
+    CHECKBOX_CATEGORIES.get().is_some_and(|categories| categories.contains(self))
+  }
+
+  /// Replaces the checkbox categories of the current thread
+  #[cfg(test)]
+  pub fn set_test_checkbox_categories(categories: Vec<Category>) {
+    TEST_CHECKBOX_CATEGORIES.with(|c| *c.borrow_mut() = Some(categories));
+  }
+
+  fn appearance(&self) -> Option<&'static CategoryAppearance> {
+    #[cfg(test)]
+    if let Some(overrides) = TEST_CATEGORY_APPEARANCE.with(|appearance| appearance.get()) {
+      return overrides.get(self);
+    }
+    CATEGORY_APPEARANCE.get().and_then(|overrides| overrides.get(self))
+  }
+
+  fn default_emoji(&self) -> &'static str {
+    match self {
+      Category::Sport => "🏅",
+      Category::ProfessionalGrowth => "👨‍💻",
+      Category::Health => "🌿",
+      Category::SpiritualGrowth => "🛐",
+      Category::ForeignLanguage => "📚",
+      Category::PersonalDev => "🤸",
+    }
+  }
+
+  fn default_label(&self) -> &'static str {
+    match self {
+      Category::Sport => "Спорт",
+      Category::ProfessionalGrowth => "Проф. рост",
+      Category::Health => "Здоровье",
+      Category::SpiritualGrowth => "Дух. рост",
+      Category::ForeignLanguage => "Ин. языки",
+      Category::PersonalDev => "Свое",
+    }
+  }
+}
+
+/// Category scores of a day indexed by the category, `None` for the cells left empty (a real zero is `Some(0.)`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Scores {
-  sport: f64,
-  professional_growth: f64,
-  health: f64,
-  spiritual_growth: f64,
-  foreign_language: f64,
-  personal_dev: f64,
+  values: [Option<f64>; Category::COUNT],
 }
 
 impl Scores {
-  pub const UNITITIALIZED_SCORE: f64 = 0.;
+  /// Rendered in place of an empty cell
+  pub const MISSING: &'static str = "—";
 
-  pub fn set_sport(&mut self, value: f64) {
-    self.sport = value;
+  pub fn get(&self, category: Category) -> Option<f64> {
+    self.values[category.index()]
   }
 
-  pub fn set_professional_growth(&mut self, value: f64) {
-    self.professional_growth = value;
+  pub fn set(&mut self, category: Category, value: f64) {
+    self.values[category.index()] = Some(value);
   }
 
-  pub fn set_health(&mut self, value: f64) {
-    self.health = value;
+  pub fn sport(&self) -> Option<f64> {
+    self.get(Category::Sport)
   }
 
-  pub fn set_spiritual_growth(&mut self, value: f64) {
-    self.spiritual_growth = value;
+  pub fn professional_growth(&self) -> Option<f64> {
+    self.get(Category::ProfessionalGrowth)
   }
 
-  pub fn set_foreign_language(&mut self, value: f64) {
-    self.foreign_language = value;
+  pub fn health(&self) -> Option<f64> {
+    self.get(Category::Health)
   }
 
-  pub fn set_personal_dev(&mut self, value: f64) {
-    self.personal_dev = value;
+  pub fn spiritual_growth(&self) -> Option<f64> {
+    self.get(Category::SpiritualGrowth)
   }
 
-  pub fn total(&self) -> f64 {
-    self.sport + self.personal_dev + self.health + self.spiritual_growth + self.foreign_language + self.personal_dev
+  pub fn foreign_language(&self) -> Option<f64> {
+    self.get(Category::ForeignLanguage)
   }
-}
 
-impl Default for Scores {
-  fn default() -> Self {
-    Scores {
-      sport: Scores::UNITITIALIZED_SCORE,
-      professional_growth: Scores::UNITITIALIZED_SCORE,
-      health: Scores::UNITITIALIZED_SCORE,
-      spiritual_growth: Scores::UNITITIALIZED_SCORE,
-      foreign_language: Scores::UNITITIALIZED_SCORE,
-      personal_dev: Scores::UNITITIALIZED_SCORE,
+  pub fn personal_dev(&self) -> Option<f64> {
+    self.get(Category::PersonalDev)
+  }
+
+  /// Copy of the scores with the given categories replaced, the rest are kept as is
+  pub fn with_overrides(&self, overrides: &[(Category, f64)]) -> Scores {
+    let mut scores = self.clone();
+    for (category, value) in overrides {
+      scores.set(*category, *value);
     }
+    scores
+  }
+
+  /// Sum of the present scores, the empty cells add nothing
+  pub fn total(&self) -> f64 {
+    self.values.iter().flatten().sum()
+  }
+
+  /// Whether anything was entered for the day. Unchecked boxes don't count,
+  /// as they can't be told apart from the untouched ones.
+  pub fn has_entries(&self) -> bool {
+    Category::ALL
+      .into_iter()
+      .any(|category| self.get(category).is_some_and(|value| !category.is_checkbox() || value != 0.))
+  }
+
+  /// Displayed score, `MISSING` for an empty cell
+  pub fn display(value: Option<f64>) -> String {
+    value.map_or_else(|| Scores::MISSING.to_string(), DisplayRounding::score)
   }
 }
 
 impl std::fmt::Display for Scores {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    writeln!(
-      f,
-      "🏅 Спорт: {}\n\
-       👨‍💻 Проф. рост: {}\n\
-       🌿 Здоровье: {}\n\
-       🛐 Дух. рост: {}\n\
-       📚 Ин. языки: {}\n\
-       🤸 Свое: {}",
-      self.sport, self.professional_growth, self.health, self.spiritual_growth, self.foreign_language, self.personal_dev
-    )
+    for category in Category::shown() {
+      writeln!(f, "{} {}: {}", category.emoji(), category.label(), Scores::display(self.get(category)))?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scores(values: [f64; 6]) -> Scores {
+    let mut scores = Scores::default();
+    for (category, value) in Category::ALL.into_iter().zip(values) {
+      scores.set(category, value);
+    }
+    scores
+  }
+
+  #[test]
+  fn total_sums_every_category_once() {
+    // Powers of two, so a category counted twice or skipped gives another sum
+    assert_eq!(scores([1., 2., 4., 8., 16., 32.]).total(), 63.);
+  }
+
+  #[test]
+  fn total_skips_empty_cells() {
+    let mut scores = Scores::default();
+    scores.set(Category::ProfessionalGrowth, 2.5);
+    scores.set(Category::PersonalDev, 0.);
+    assert_eq!(scores.total(), 2.5);
+  }
+
+  #[test]
+  fn emoji_tiers_switch_at_50_and_80() {
+    let emoji = |value| Percentage::from_clamped(value).emoji();
+    assert_eq!(emoji(0), "😐");
+    assert_eq!(emoji(49), "😐");
+    assert_eq!(emoji(50), "👍");
+    assert_eq!(emoji(79), "👍");
+    assert_eq!(emoji(80), "🔥");
+    assert_eq!(emoji(100), "🔥");
+  }
+
+  #[test]
+  fn parsed_percentage_is_clamped() {
+    assert_eq!(Percentage::from_clamped(-5).value(), 0);
+    assert_eq!(Percentage::from_clamped(0).value(), 0);
+    assert_eq!(Percentage::from_clamped(100).value(), 100);
+    assert_eq!(Percentage::from_clamped(180).value(), 100);
+  }
+
+  #[test]
+  fn category_columns_follow_the_sheet_order() {
+    assert_eq!(Category::from_column(0), None);
+    assert_eq!(Category::from_column(1), Some(Category::Sport));
+    assert_eq!(Category::from_column(6), Some(Category::PersonalDev));
+    assert_eq!(Category::from_column(7), None);
+  }
+
+  #[test]
+  fn labels_rename_the_columns_in_order() {
+    let overrides = HashMap::from([(
+      "health".to_string(),
+      CategoryAppearance {
+        label: Some("Сон".to_string()),
+        ..Default::default()
+      },
+    )]);
+    let labels = ["Бег", "", "Прогулка"].map(String::from);
+    Category::set_test_appearance_overrides(&overrides, &labels);
+
+    assert_eq!(Category::Sport.label(), "Бег");
+    assert_eq!(Category::ProfessionalGrowth.label(), "Проф. рост");
+    // The category's own section wins over the column list
+    assert_eq!(Category::Health.label(), "Сон");
+    let mut scores = Scores::default();
+    scores.set(Category::Sport, 2.);
+    let lines: Vec<String> = scores.to_string().lines().map(String::from).collect();
+    assert_eq!(lines[..3], ["🏅 Бег: 2.0", "👨‍💻 Проф. рост: —", "🌿 Сон: —"]);
+  }
+
+  #[test]
+  fn overridden_emoji_and_hidden_columns_change_the_scores() {
+    let scores = scores([1., 2., 3., 4., 5., 6.]);
+    let defaults = scores.to_string();
+    assert_eq!(defaults.lines().next(), Some("🏅 Спорт: 1.0"));
+    assert_eq!(defaults.lines().count(), 6);
+
+    let overrides = HashMap::from([
+      (
+        "sport".to_string(),
+        CategoryAppearance {
+          emoji: Some("🏃".to_string()),
+          ..Default::default()
+        },
+      ),
+      (
+        "foreign_language".to_string(),
+        CategoryAppearance {
+          hidden: Some(true),
+          ..Default::default()
+        },
+      ),
+      // Unknown keys are ignored
+      ("cooking".to_string(), CategoryAppearance::default()),
+    ]);
+    Category::set_test_appearance_overrides(&overrides, &[]);
+
+    let lines: Vec<String> = scores.to_string().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "🏃 Спорт: 1.0");
+    assert!(lines.iter().all(|line| !line.contains("Ин. языки")));
+    // Hidden column still counts in the stored total
+    assert_eq!(scores.total(), 21.);
+  }
+
+  #[test]
+  fn rounding_modes_at_the_boundaries() {
+    let round = DisplayRounding::round;
+    assert_eq!(round(4.95, 1, RoundingMode::Round), 5.0);
+    assert_eq!(round(4.94, 1, RoundingMode::Round), 4.9);
+    assert_eq!(round(4.95, 1, RoundingMode::Floor), 4.9);
+    assert_eq!(round(4.91, 1, RoundingMode::Ceil), 5.0);
+    assert_eq!(round(4.9, 1, RoundingMode::Ceil), 4.9);
+    assert_eq!(round(-4.95, 1, RoundingMode::Round), -5.0);
+    assert_eq!(round(72.5, 0, RoundingMode::Round), 73.0);
+  }
+
+  #[test]
+  fn rounding_never_gives_negative_zero() {
+    for mode in [RoundingMode::Round, RoundingMode::Floor, RoundingMode::Ceil] {
+      let rounded = DisplayRounding::round(-0.04, 1, mode);
+      if rounded == 0. {
+        assert!(rounded.is_sign_positive(), "{mode:?} gives -0.0");
+      }
+    }
+    assert_eq!(format!("{:.1}", DisplayRounding::round(-0.04, 1, RoundingMode::Round)), "0.0");
+  }
+
+  #[test]
+  fn every_field_getter_returns_what_was_set() {
+    type Getter = fn(&Scores) -> Option<f64>;
+    let getters: [(Category, Getter); Category::COUNT] = [
+      (Category::Sport, Scores::sport),
+      (Category::ProfessionalGrowth, Scores::professional_growth),
+      (Category::Health, Scores::health),
+      (Category::SpiritualGrowth, Scores::spiritual_growth),
+      (Category::ForeignLanguage, Scores::foreign_language),
+      (Category::PersonalDev, Scores::personal_dev),
+    ];
+    for (category, getter) in getters {
+      let mut scores = Scores::default();
+      assert_eq!(getter(&scores), None);
+      scores.set(category, 2.5);
+      assert_eq!(getter(&scores), Some(2.5), "{category:?}");
+      // The other fields are left untouched
+      assert_eq!(scores.total(), 2.5, "{category:?}");
+    }
+  }
+
+  #[test]
+  fn persons_with_equal_names_are_the_same() {
+    let mut persons = std::collections::HashSet::new();
+    persons.insert(Person::new("Анна".to_string()));
+    persons.insert(Person::new("Анна".to_string()));
+    assert_eq!(persons.len(), 1);
+  }
+
+  #[test]
+  fn name_sanitization_policies() {
+    let name = "Анна_[админ] *2*";
+    assert_eq!(NameSanitization::Escape.apply(name), name);
+    assert_eq!(NameSanitization::Strip.apply(name), "Аннаадмин 2");
+    assert_eq!(NameSanitization::Replace.apply(name), "Анна··админ· ·2·");
   }
 }
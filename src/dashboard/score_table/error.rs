@@ -6,6 +6,8 @@ pub enum ParseError {
   DateParseError(chrono::format::ParseErrorKind),
   #[error("Score parse error occured (cell index={0}). Error: {1:?}")]
   ScoreParseError(usize, std::num::ParseFloatError),
+  #[error("Checkbox parse error occured (cell index={0}). Value: {1:?}")]
+  CheckboxParseError(usize, String),
   #[error("Percent parse error occured. Error kind: {0:?}")]
   PercentParseError(std::num::IntErrorKind),
 }
@@ -1,32 +1,82 @@
 use chrono::NaiveDate;
 use google_sheets4::api::{CellData, NumberFormat};
+use serde::{Deserialize, Serialize};
 
-use self::entities::{Percentage, Person, Scores};
+use crate::helpers;
+
+use self::entities::{Category, Percentage, Person, Scores};
 use self::error::{Empty::*, InvalidCell::*, ParseError::*, ScoreTableRecordError as Error};
 
 pub mod entities;
 pub mod error;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ScoreTable {
   person: Person,
   table: Vec<ScoreTableRecord>,
+  /// Rows dropped because they couldn't be parsed
+  #[serde(default)]
+  skipped_rows: usize,
 }
 
 impl ScoreTable {
   pub fn new(person: Person, table: Vec<ScoreTableRecord>) -> ScoreTable {
-    ScoreTable { person, table }
+    ScoreTable {
+      person,
+      table,
+      skipped_rows: 0,
+    }
+  }
+
+  pub fn with_skipped_rows(mut self, skipped_rows: usize) -> ScoreTable {
+    self.skipped_rows = skipped_rows;
+    self
+  }
+
+  /// Table of the same person holding `earlier` records followed by the own ones
+  pub fn preceded_by(&self, earlier: &ScoreTable) -> ScoreTable {
+    let table = earlier.table.iter().chain(self.table.iter()).cloned().collect();
+    ScoreTable::new(self.person.clone(), table).with_skipped_rows(earlier.skipped_rows + self.skipped_rows)
+  }
+
+  pub fn skipped_rows(&self) -> usize {
+    self.skipped_rows
   }
 
   pub fn person(&self) -> &Person {
     &self.person
   }
 
+  pub fn all_records(&self) -> Vec<&ScoreTableRecord> {
+    self.table.iter().collect()
+  }
+
+  pub fn records(&self) -> &[ScoreTableRecord] {
+    &self.table
+  }
+
+  /// Records having the total filled, in the sheet order
+  pub fn filled_records(&self) -> impl DoubleEndedIterator<Item = &ScoreTableRecord> {
+    self.table.iter().filter(|rec| rec.has_total())
+  }
+
+  pub fn records_count(&self) -> usize {
+    self.table.len()
+  }
+
   pub fn last_record(&self) -> Option<&ScoreTableRecord> {
     self.table.last()
   }
 
   pub fn last_filled_record(&self) -> Option<&ScoreTableRecord> {
-    self.table.iter().rev().find(|rec| rec.has_total())
+    self.filled_records().next_back()
+  }
+
+  /// Up to `count` most recent filled records, oldest first
+  pub fn last_filled_records(&self, count: usize) -> Vec<&ScoreTableRecord> {
+    let mut records: Vec<_> = self.filled_records().rev().take(count).collect();
+    records.reverse();
+    records
   }
 
   pub fn by_date(&self, date: &NaiveDate) -> Option<&ScoreTableRecord> {
@@ -34,16 +84,19 @@ impl ScoreTable {
   }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoreTableRecord {
   date: NaiveDate,
   scores: Scores,
-  total_score: f64,
+  total_score: Option<f64>,
   percent: Percentage,
 }
 
 impl ScoreTableRecord {
-  pub fn new(date: NaiveDate, scores: Scores, total_score: f64, percent: Percentage) -> ScoreTableRecord {
+  /// Used when the cell's date pattern is absent or unrecognized
+  const DEFAULT_DATE_FORMAT: &'static str = "%d.%m.%Y";
+
+  pub fn new(date: NaiveDate, scores: Scores, total_score: Option<f64>, percent: Percentage) -> ScoreTableRecord {
     ScoreTableRecord {
       date,
       scores,
@@ -52,57 +105,91 @@ impl ScoreTableRecord {
     }
   }
 
+  /// Day is filled when the total is present and some score was entered. A total formula
+  /// evaluating to 0 on an untouched row doesn't make it filled, while the entered zeros do.
   pub fn has_total(&self) -> bool {
-    self.total_score != Scores::UNITITIALIZED_SCORE
+    self.total_score.is_some() && self.scores.has_entries()
+  }
+
+  pub fn date(&self) -> &NaiveDate {
+    &self.date
+  }
+
+  pub fn scores(&self) -> &Scores {
+    &self.scores
+  }
+
+  pub fn total_score(&self) -> Option<f64> {
+    self.total_score
+  }
+
+  /// Whether the sheet's total agrees with the sum of the parsed category scores (an empty total can't disagree)
+  pub fn total_matches(&self) -> bool {
+    self.total_score.is_none_or(|total| (total - self.scores.total()).abs() < 1e-6)
   }
 
   pub fn percent(&self) -> &Percentage {
     &self.percent
   }
 
+  /// Row is considered empty when neither the date nor any category score is present.
+  /// Total and percent cells are not checked since they usually hold formulas.
+  pub fn is_empty_row(row: &[CellData]) -> bool {
+    row
+      .iter()
+      .take(1 + Category::ALL.len())
+      .all(|cell| cell.formatted_value.as_deref().is_none_or(|v| v.trim().is_empty()))
+  }
+
   pub fn from_vec(row: &[CellData]) -> Result<ScoreTableRecord, Error> {
-    let mut date: NaiveDate = NaiveDate::default();
+    let mut date: Option<NaiveDate> = None;
     let mut scores = Scores::default();
-    let mut total_score = Scores::UNITITIALIZED_SCORE;
+    let mut total_score = None;
     let mut percent = Percentage::from(0);
 
     // Iterate over cells in a row
     for (i, cell) in row.iter().enumerate() {
       match i {
-        0 => date = ScoreTableRecord::parse_date(cell)?,
+        0 => date = Some(ScoreTableRecord::parse_date(cell)?),
         1..=7 => {
-          let score = ScoreTableRecord::parse_score(cell, i)?;
-          match i {
-            1 => scores.set_sport(score),
-            2 => scores.set_professional_growth(score),
-            3 => scores.set_health(score),
-            4 => scores.set_spiritual_growth(score),
-            5 => scores.set_foreign_language(score),
-            6 => scores.set_personal_dev(score),
-            7 => total_score = score,
-            _ => panic!("Should not reach here"),
+          let Some(score) = ScoreTableRecord::parse_score(cell, i)? else {
+            continue;
           };
+          // The column after the categories holds the total
+          match Category::from_column(i) {
+            Some(category) => scores.set(category, score),
+            None => total_score = Some(score),
+          }
         }
         8 => percent = ScoreTableRecord::parse_percentage(cell)?,
         _ => return Err(Error::UnexpectedFieldIndex(i)),
       }
     }
 
+    let date = date.ok_or(Error::Empty(EmptyFormattedValue(0)))?;
     Ok(ScoreTableRecord::new(date, scores, total_score, percent))
   }
 
   fn parse_date(cell: &CellData) -> Result<NaiveDate, Error> {
+    // A cell without the formatted value may still carry the serial date number
+    let serial = cell.effective_value.as_ref().and_then(|v| v.number_value);
+    if let (None, Some(date)) = (&cell.formatted_value, serial.and_then(ScoreTableRecord::serial_date)) {
+      return Ok(date);
+    }
     let cell_format = cell.effective_format.as_ref().ok_or(Error::Empty(EmptyEffectiveFormat(0)))?;
 
-    if let Some(NumberFormat { pattern: _, type_ }) = &cell_format.number_format {
+    if let Some(NumberFormat { pattern, type_ }) = &cell_format.number_format {
       let date = match type_.as_ref().unwrap().as_str() {
         "DATE" => {
           let formatted_value = match cell.formatted_value.as_ref() {
             Some(value) => value,
             None => return Err(Error::InvalidCell(InvalidDateCell("can't be empty formatted value for date"))),
           };
-          // TODO: date format should be derived dynamically from 'pattern'
-          let parsed_date = match NaiveDate::parse_from_str(formatted_value.as_str(), "%d.%m.%Y") {
+          let format = pattern
+            .as_deref()
+            .and_then(ScoreTableRecord::chrono_date_format)
+            .unwrap_or_else(|| ScoreTableRecord::DEFAULT_DATE_FORMAT.to_string());
+          let parsed_date = match NaiveDate::parse_from_str(formatted_value.as_str(), &format) {
             Ok(date) => date,
             Err(parse_err) => return Err(Error::ParseError(DateParseError(parse_err.kind()))),
           };
@@ -116,6 +203,66 @@ impl ScoreTableRecord {
     }
   }
 
+  /// Sheets serial date number: days (the fraction is the time of day) since 30.12.1899
+  fn serial_date(serial: f64) -> Option<NaiveDate> {
+    if !serial.is_finite() {
+      return None;
+    }
+    NaiveDate::from_ymd_opt(1899, 12, 30)?.checked_add_signed(chrono::Duration::days(serial.floor() as i64))
+  }
+
+  /// Translates Google Sheets date pattern (`dd.mm.yyyy`, `m/d/yyyy`, `yyyy-mm-dd`, ...) into the chrono format.
+  /// Quoted (`dd "г."`) and escaped (`dd\г`) literals are kept as plain text without the quotes.
+  /// `None` if the pattern has tokens other than day, month and year ones.
+  fn chrono_date_format(pattern: &str) -> Option<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut format = String::new();
+    let push_literal = |format: &mut String, c: char| {
+      if c == '%' {
+        format.push('%');
+      }
+      format.push(c);
+    };
+    let mut i = 0;
+    while i < chars.len() {
+      match chars[i] {
+        '"' => {
+          let length = chars[i + 1..].iter().position(|&c| c == '"')?;
+          chars[i + 1..i + 1 + length].iter().for_each(|&c| push_literal(&mut format, c));
+          i += length + 2;
+          continue;
+        }
+        '\\' => {
+          push_literal(&mut format, *chars.get(i + 1)?);
+          i += 2;
+          continue;
+        }
+        _ => {}
+      }
+      let c = chars[i].to_ascii_lowercase();
+      let run = chars[i..].iter().take_while(|&&next| next.to_ascii_lowercase() == c).count();
+      let token = match (c, run) {
+        ('d', 1 | 2) => "%d",
+        ('d', 3) => "%a",
+        ('d', _) => "%A",
+        ('m', 1 | 2) => "%m",
+        ('m', 3) => "%b",
+        ('m', _) => "%B",
+        ('y', 1 | 2) => "%y",
+        ('y', _) => "%Y",
+        (c, _) if c.is_alphanumeric() => return None,
+        _ => {
+          push_literal(&mut format, chars[i]);
+          i += 1;
+          continue;
+        }
+      };
+      format.push_str(token);
+      i += run;
+    }
+    Some(format)
+  }
+
   fn parse_percentage(cell: &CellData) -> Result<Percentage, Error> {
     let percent_value: Result<i32, _> = match &cell.formatted_value {
       Some(value) => {
@@ -125,34 +272,87 @@ impl ScoreTableRecord {
           value[..value.len() - 1].parse()
         }
       }
-      None => Ok(0),
+      // Percent cell's number value is a fraction (0.7 for 70%)
+      None => Ok(
+        cell
+          .effective_value
+          .as_ref()
+          .and_then(|v| v.number_value)
+          .map_or(0, |fraction| (fraction * 100.).round() as i32),
+      ),
     };
     let percent_value = match percent_value {
-      Ok(value) => Percentage::from(value),
-      Err(erro) => return Err(Error::ParseError(PercentParseError(erro.kind().clone()))),
+      Ok(value) => Percentage::from_clamped(value),
+      Err(erro) => return Err(Error::ParseError(PercentParseError(*erro.kind()))),
     };
     Ok(percent_value)
   }
 
-  fn parse_score(cell: &CellData, index: usize) -> Result<f64, Error> {
-    let score = match &cell.formatted_value {
-      Some(value) => value.parse::<f64>(),
-      None => Ok(Scores::UNITITIALIZED_SCORE),
+  /// `None` for an empty cell
+  fn parse_score(cell: &CellData, index: usize) -> Result<Option<f64>, Error> {
+    // Checkbox cell is recognized by its boolean value, or by the column configured as the checkbox one
+    let is_checkbox = Category::from_column(index).is_some_and(|c| c.is_checkbox());
+    let bool_value = cell.effective_value.as_ref().and_then(|v| v.bool_value);
+    if is_checkbox || bool_value.is_some() {
+      return ScoreTableRecord::parse_checkbox(cell, bool_value, index);
+    }
+
+    let score = match cell.formatted_value.as_deref().filter(|v| !v.trim().is_empty()) {
+      Some(value) => ScoreTableRecord::normalize_number(value).parse::<f64>(),
+      None => return Ok(None),
     };
     let score = match score {
       Ok(value) => value,
       Err(erro) => {
         let derived_error = Error::ParseError(ScoreParseError(index, erro));
         if let Some(effective_value) = &cell.effective_value {
-          return effective_value.number_value.ok_or(derived_error);
+          return effective_value.number_value.map(Some).ok_or(derived_error);
         }
         return Err(derived_error);
       }
     };
-    Ok(score)
+    Ok(Some(score))
+  }
+
+  /// Checked box counts as 1.0, unchecked one as 0.0, an empty cell has no value
+  fn parse_checkbox(cell: &CellData, bool_value: Option<bool>, index: usize) -> Result<Option<f64>, Error> {
+    if let Some(checked) = bool_value {
+      return Ok(Some(if checked { 1. } else { 0. }));
+    }
+    match cell.formatted_value.as_deref().map(|v| v.trim().to_uppercase()) {
+      None => Ok(None),
+      Some(value) => match value.as_str() {
+        "" => Ok(None),
+        "FALSE" | "ЛОЖЬ" => Ok(Some(0.)),
+        "TRUE" | "ИСТИНА" => Ok(Some(1.)),
+        _ => Err(Error::ParseError(CheckboxParseError(index, value))),
+      },
+    }
+  }
+
+  /// Brings locale-formatted number to the form accepted by `f64::parse`:
+  /// drops (non-breaking) spaces and group separators, uses '.' as decimal separator.
+  /// If both ',' and '.' are present, the rightmost one is decimal; repeated separator is a grouping one;
+  /// a single ',' is decimal as the sheet uses Russian locale.
+  fn normalize_number(value: &str) -> String {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let (group, decimal) = match (value.rfind(','), value.rfind('.')) {
+      (Some(comma), Some(dot)) if comma > dot => ('.', Some(',')),
+      (Some(_), Some(_)) => (',', Some('.')),
+      (Some(_), None) if value.matches(',').count() > 1 => (',', None),
+      (Some(_), None) => ('.', Some(',')),
+      (None, Some(_)) if value.matches('.').count() > 1 => ('.', None),
+      _ => (',', Some('.')),
+    };
+    value
+      .chars()
+      .filter(|&c| c != group)
+      .map(|c| if Some(c) == decimal { '.' } else { c })
+      .collect()
   }
 }
 
+/// MarkdownV2 rendering, the values are escaped while the markup is kept
 impl std::fmt::Display for ScoreTableRecord {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(
@@ -160,11 +360,248 @@ impl std::fmt::Display for ScoreTableRecord {
       "🗓️ __Дата__: {}\n\n{}\n\
        ✅ *Total*: {}\n\
        💯 *Rate*: {} {}\n",
-      self.date.format("%d.%m.%Y"),
-      self.scores,
-      self.total_score,
-      self.percent,
+      helpers::escape_markdown_v2(&self.date.format("%d.%m.%Y").to_string()),
+      helpers::escape_markdown_v2(&self.scores.to_string()),
+      helpers::escape_markdown_v2(&Scores::display(self.total_score)),
+      helpers::escape_markdown_v2(&self.percent.to_string()),
       self.percent.emoji()
     )
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use google_sheets4::api::{CellData, CellFormat, ExtendedValue, NumberFormat};
+
+  use super::*;
+  use crate::dashboard::fake::{date, empty_record, record, table};
+
+  fn date_cell(value: &str) -> CellData {
+    CellData {
+      formatted_value: Some(value.to_string()),
+      effective_format: Some(CellFormat {
+        number_format: Some(NumberFormat {
+          pattern: Some("dd.mm.yyyy".to_string()),
+          type_: Some("DATE".to_string()),
+        }),
+        ..Default::default()
+      }),
+      ..Default::default()
+    }
+  }
+
+  fn cell(value: &str) -> CellData {
+    CellData {
+      formatted_value: Some(value.to_string()),
+      ..Default::default()
+    }
+  }
+
+  fn row(scores: [&str; 6], total: &str, percent: &str) -> Vec<CellData> {
+    let mut row = vec![date_cell("15.01.2024")];
+    row.extend(scores.iter().map(|score| cell(score)));
+    row.push(cell(total));
+    row.push(cell(percent));
+    row
+  }
+
+  #[test]
+  fn parses_filled_row() {
+    let record = ScoreTableRecord::from_vec(&row(["1", "2,5", "", "3", "0", "4"], "10,5", "70%")).unwrap();
+
+    assert_eq!(*record.date(), NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+    assert_eq!(record.scores().get(Category::Sport), Some(1.));
+    assert_eq!(record.scores().get(Category::ProfessionalGrowth), Some(2.5));
+    assert_eq!(record.scores().get(Category::Health), None);
+    assert_eq!(record.scores().get(Category::SpiritualGrowth), Some(3.));
+    assert_eq!(record.scores().get(Category::ForeignLanguage), Some(0.));
+    assert_eq!(record.scores().get(Category::PersonalDev), Some(4.));
+    assert_eq!(record.total_score(), Some(10.5));
+    assert_eq!(record.percent().value(), 70);
+    assert!(record.has_total());
+  }
+
+  #[test]
+  fn rejects_date_cell_without_effective_format() {
+    let mut row = row(["1", "1", "1", "1", "1", "1"], "6", "40%");
+    row[0] = cell("15.01.2024");
+
+    let result = ScoreTableRecord::from_vec(&row);
+    assert!(matches!(result, Err(Error::Empty(EmptyEffectiveFormat(0)))));
+  }
+
+  #[test]
+  fn rejects_percent_cell_without_percent_sign() {
+    let result = ScoreTableRecord::from_vec(&row(["1", "1", "1", "1", "1", "1"], "6", "40"));
+    assert!(matches!(result, Err(Error::InvalidCell(InvalidPercentCell(_)))));
+  }
+
+  #[test]
+  fn unparseable_score_falls_back_to_number_value() {
+    let mut row = row(["1", "1", "1", "1", "1", "1"], "6", "40%");
+    row[1] = CellData {
+      formatted_value: Some("три".to_string()),
+      effective_value: Some(ExtendedValue {
+        number_value: Some(3.),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+
+    let record = ScoreTableRecord::from_vec(&row).unwrap();
+    assert_eq!(record.scores().get(Category::Sport), Some(3.));
+  }
+
+  #[test]
+  fn unparseable_score_without_number_value_is_error() {
+    let result = ScoreTableRecord::from_vec(&row(["три", "1", "1", "1", "1", "1"], "6", "40%"));
+    assert!(matches!(result, Err(Error::ParseError(ScoreParseError(1, _)))));
+  }
+
+  #[test]
+  fn parses_serial_date_and_fractional_percent() {
+    let mut row = row(["1", "1", "1", "1", "1", "1"], "6", "40%");
+    row[0] = CellData {
+      effective_value: Some(ExtendedValue {
+        number_value: Some(45306.),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    row[8] = CellData {
+      effective_value: Some(ExtendedValue {
+        number_value: Some(0.725),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+
+    let record = ScoreTableRecord::from_vec(&row).unwrap();
+    assert_eq!(*record.date(), NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+    assert_eq!(record.percent().value(), 73);
+  }
+
+  #[test]
+  fn real_zero_is_told_apart_from_empty_cell() {
+    let record = ScoreTableRecord::from_vec(&row(["0", "", "", "", "", ""], "0", "0%")).unwrap();
+    assert_eq!(record.scores().get(Category::Sport), Some(0.));
+    assert_eq!(record.scores().get(Category::ProfessionalGrowth), None);
+    assert_eq!(record.total_score(), Some(0.));
+    assert!(record.has_total());
+  }
+
+  #[test]
+  fn total_without_scores_is_not_filled() {
+    // The total formula evaluates to 0 on an untouched row
+    let record = ScoreTableRecord::from_vec(&row(["", "", "", "", "", ""], "0", "0%")).unwrap();
+    assert_eq!(record.total_score(), Some(0.));
+    assert!(!record.has_total());
+  }
+
+  #[test]
+  fn unchecked_checkbox_is_not_filled() {
+    Category::set_test_checkbox_categories(vec![Category::PersonalDev]);
+
+    let unchecked = ScoreTableRecord::from_vec(&row(["", "", "", "", "", "FALSE"], "0", "0%")).unwrap();
+    assert_eq!(unchecked.scores().get(Category::PersonalDev), Some(0.));
+    assert!(!unchecked.has_total());
+
+    let checked = ScoreTableRecord::from_vec(&row(["", "", "", "", "", "TRUE"], "1", "10%")).unwrap();
+    assert_eq!(checked.scores().get(Category::PersonalDev), Some(1.));
+    assert!(checked.has_total());
+  }
+
+  #[test]
+  fn rejects_extra_column() {
+    let mut row = row(["1", "1", "1", "1", "1", "1"], "6", "40%");
+    row.push(cell("extra"));
+
+    let result = ScoreTableRecord::from_vec(&row);
+    assert!(matches!(result, Err(Error::UnexpectedFieldIndex(9))));
+  }
+
+  #[test]
+  fn sheets_date_patterns_are_translated() {
+    let format = |pattern| ScoreTableRecord::chrono_date_format(pattern);
+    assert_eq!(format("dd.mm.yyyy").as_deref(), Some("%d.%m.%Y"));
+    assert_eq!(format("m/d/yyyy").as_deref(), Some("%m/%d/%Y"));
+    assert_eq!(format("yyyy-mm-dd").as_deref(), Some("%Y-%m-%d"));
+    assert_eq!(format("hh:mm"), None);
+  }
+
+  #[test]
+  fn quoted_date_pattern_literals_are_kept_as_text() {
+    let format = ScoreTableRecord::chrono_date_format("dd.mm.yyyy \"г.\"").unwrap();
+    assert_eq!(format, "%d.%m.%Y г.");
+    let date = NaiveDate::parse_from_str("15.01.2024 г.", &format).unwrap();
+    assert_eq!(date, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+
+    assert_eq!(ScoreTableRecord::chrono_date_format("dd\\%mm").as_deref(), Some("%d%%%m"));
+    assert_eq!(ScoreTableRecord::chrono_date_format("dd \"г."), None);
+  }
+
+  #[test]
+  fn filled_records_skip_the_empty_ones() {
+    let table = table(
+      "Анна",
+      vec![
+        record(date(2024, 1, 1), 50),
+        empty_record(date(2024, 1, 2)),
+        record(date(2024, 1, 3), 70),
+        empty_record(date(2024, 1, 4)),
+      ],
+    );
+
+    let dates: Vec<_> = table.filled_records().map(|rec| *rec.date()).collect();
+    assert_eq!(dates, [date(2024, 1, 1), date(2024, 1, 3)]);
+    assert_eq!(table.last_filled_record().map(|rec| *rec.date()), Some(date(2024, 1, 3)));
+    assert_eq!(table.last_record().map(|rec| *rec.date()), Some(date(2024, 1, 4)));
+  }
+
+  #[test]
+  fn checkbox_values_are_parsed_in_both_locales() {
+    let checkbox = |value| ScoreTableRecord::parse_checkbox(&cell(value), None, 6);
+    assert_eq!(checkbox("TRUE").unwrap(), Some(1.));
+    assert_eq!(checkbox("ИСТИНА").unwrap(), Some(1.));
+    assert_eq!(checkbox("FALSE").unwrap(), Some(0.));
+    assert_eq!(checkbox("ложь").unwrap(), Some(0.));
+    assert_eq!(checkbox(" ").unwrap(), None);
+    assert!(matches!(checkbox("да"), Err(Error::ParseError(CheckboxParseError(6, _)))));
+  }
+
+  #[test]
+  fn boolean_cells_are_parsed_next_to_numeric_ones() {
+    let mut row = row(["1", "2", "", "", "", ""], "4", "40%");
+    row[3] = CellData {
+      formatted_value: Some("ИСТИНА".to_string()),
+      effective_value: Some(ExtendedValue {
+        bool_value: Some(true),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+
+    let record = ScoreTableRecord::from_vec(&row).unwrap();
+    assert_eq!(record.scores().get(Category::Sport), Some(1.));
+    assert_eq!(record.scores().get(Category::ProfessionalGrowth), Some(2.));
+    assert_eq!(record.scores().get(Category::Health), Some(1.));
+    assert_eq!(record.scores().get(Category::SpiritualGrowth), None);
+  }
+
+  #[test]
+  fn locale_formatted_numbers_are_normalized() {
+    let normalize = ScoreTableRecord::normalize_number;
+    assert_eq!(normalize("1\u{a0}234"), "1234");
+    assert_eq!(normalize("1 234,5"), "1234.5");
+    assert_eq!(normalize("1,234.5"), "1234.5");
+    assert_eq!(normalize("1.234,5"), "1234.5");
+    assert_eq!(normalize("1,234,567"), "1234567");
+    assert_eq!(normalize("2,5"), "2.5");
+    assert_eq!(normalize("42"), "42");
+  }
+
+  #[test]
+  fn row_without_date_is_an_error() {
+    assert!(matches!(ScoreTableRecord::from_vec(&[]), Err(Error::Empty(EmptyFormattedValue(0)))));
+  }
+}
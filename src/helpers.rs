@@ -1,17 +1,29 @@
-use std::{fmt::Display, future::Future};
+pub mod clock;
+pub mod i18n;
+
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, fmt::Display, future::Future, sync::OnceLock};
 
 use crate::{
   bot::tasks::TaskHandle,
-  dashboard::score_table::{entities::Person, ScoreTableRecord},
+  dashboard::analyzer::{Comparison, GoalEstimate, MonthlyStats, ParticipantTotals, YearlyAverage},
+  dashboard::Dashboard,
+  dashboard::score_table::{
+    entities::{Category, DisplayRounding, Percentage, Person, Scores},
+    ScoreTable, ScoreTableRecord,
+  },
 };
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Utc, Weekday};
 use google_sheets4::api::Sheet;
 use itertools::free::join;
 use log::{debug, info, trace};
-use tokio_schedule::{every, EveryDay, EveryMinute, Job};
+use tokio_schedule::{every, EveryDay, EveryMinute, EveryWeekDay, Job};
+
+/// Telegram's limit for the text of a single message
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
 
 pub type EveryDayTime = EveryDay<Utc, Local>;
 pub type EveryMinuteTime = EveryMinute<Utc, Local>;
+pub type EveryWeekTime = EveryWeekDay<Utc, Local>;
 
 #[allow(dead_code)]
 struct Month {
@@ -136,25 +148,185 @@ pub fn current_time_format(format: &str) -> String {
 }
 
 pub fn current_time_utc() -> DateTime<Utc> {
-  chrono::Utc::now()
+  clock::now()
+}
+
+/// Offset of the participants' timezone from UTC, installed once at startup
+static TIMEZONE_OFFSET_HOURS: OnceLock<i32> = OnceLock::new();
+
+pub const DEFAULT_TIMEZONE_OFFSET_HOURS: i32 = 3;
+
+/// Returns `false` if the offset was already installed
+pub fn install_timezone_offset(hours: i32) -> bool {
+  TIMEZONE_OFFSET_HOURS.set(hours).is_ok()
+}
+
+pub fn timezone_offset_hours() -> i32 {
+  TIMEZONE_OFFSET_HOURS.get().copied().unwrap_or(DEFAULT_TIMEZONE_OFFSET_HOURS)
 }
 
-pub fn current_time_utc_msk() -> DateTime<Utc> {
-  current_time_utc() + Duration::hours(3)
+pub fn current_time_with_offset(offset_hours: i32) -> DateTime<Utc> {
+  current_time_utc() + Duration::hours(offset_hours as i64)
+}
+
+/// Current time in the participants' timezone (shifted UTC)
+pub fn current_time_local() -> DateTime<Utc> {
+  current_time_with_offset(timezone_offset_hours())
+}
+
+/// Every time assumption the bot makes at the `now` moment, for verification by operators
+pub fn format_time_handling_msg(now: &DateTime<Utc>, deadline: Option<NaiveTime>) -> String {
+  let offset = timezone_offset_hours();
+  let local = *now + Duration::hours(offset as i64);
+  let deadline = match deadline {
+    Some(deadline) => deadline.format("%H:%M").to_string(),
+    None => "не настроен".to_string(),
+  };
+  format!(
+    "🕰 Текущее время UTC: {}\n\
+     🌍 Местное время (UTC{:+}): {}\n\
+     📅 «Сегодня» для команд, дедлайна и вечерней сводки: {}\n\
+     📄 Лист таблицы: {}\n\
+     ⏳ Дедлайн (местное время): {}",
+    now.format("%d.%m.%Y %H:%M:%S"),
+    offset,
+    local.format("%d.%m.%Y %H:%M:%S"),
+    local.date_naive().format("%d.%m.%Y"),
+    derive_title_name_for(&local.date_naive()),
+    deadline
+  )
+}
+
+pub fn last_day_of_month(date: &NaiveDate) -> NaiveDate {
+  let (year, month) = if date.month() == 12 {
+    (date.year() + 1, 1)
+  } else {
+    (date.year(), date.month() + 1)
+  };
+  NaiveDate::from_ymd_opt(year, month, 1).and_then(|d| d.pred_opt()).unwrap_or(*date)
+}
+
+pub fn parse_time_hm(time: &str) -> chrono::ParseResult<NaiveTime> {
+  NaiveTime::parse_from_str(time.trim(), "%H:%M")
+}
+
+/// Longest duration accepted by [`parse_duration_spec`], anything longer is surely a typo
+pub const MAX_DURATION_SPEC: std::time::Duration = std::time::Duration::from_secs(366 * 24 * 60 * 60);
+
+/// Parses durations like "2h", "30m", "1h30m" or "1d" (also Russian "ч", "м", "д" units).
+/// Durations longer than [`MAX_DURATION_SPEC`] are rejected.
+pub fn parse_duration_spec(spec: &str) -> Option<std::time::Duration> {
+  let mut total = 0u64;
+  let mut number = String::new();
+  for c in spec.trim().to_lowercase().chars() {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+    let unit = match c {
+      'd' | 'д' => 24 * 60 * 60,
+      'h' | 'ч' => 60 * 60,
+      'm' | 'м' => 60,
+      _ => return None,
+    };
+    total = number.parse::<u64>().ok()?.checked_mul(unit).and_then(|secs| total.checked_add(secs))?;
+    number.clear();
+  }
+  let duration = std::time::Duration::from_secs(total);
+  (number.is_empty() && total > 0 && duration <= MAX_DURATION_SPEC).then_some(duration)
+}
+
+/// Formats positive duration as "2 ч 15 мин", rounding seconds down
+pub fn format_duration_ru(duration: &Duration) -> String {
+  let hours = duration.num_hours();
+  let minutes = duration.num_minutes() % 60;
+  match (hours, minutes) {
+    (0, 0) => "меньше минуты".to_string(),
+    (0, m) => format!("{m} мин"),
+    (h, 0) => format!("{h} ч"),
+    (h, m) => format!("{h} ч {m} мин"),
+  }
+}
+
+/// Summary (MarkdownV2) followed by the reminder addressed to the ones who missed the day
+pub fn format_evening_msg(summary_msg: &str, reminder: &str, missed: &[&Person]) -> String {
+  if missed.is_empty() {
+    return summary_msg.to_string();
+  }
+  let names = missed.iter().map(|p| escape_markdown_v2(&p.display_name()));
+  format!(
+    "{}\n\n{}\n{}",
+    summary_msg,
+    escape_markdown_v2(reminder),
+    join(names, ", ")
+  )
 }
 
+/// "по понедельникам", "по средам", ...
+pub fn weekday_every_ru(weekday: Weekday) -> &'static str {
+  match weekday {
+    Weekday::Mon => "по понедельникам",
+    Weekday::Tue => "по вторникам",
+    Weekday::Wed => "по средам",
+    Weekday::Thu => "по четвергам",
+    Weekday::Fri => "по пятницам",
+    Weekday::Sat => "по субботам",
+    Weekday::Sun => "по воскресеньям",
+  }
+}
+
+pub fn weekday_short_ru(weekday: Weekday) -> &'static str {
+  match weekday {
+    Weekday::Mon => "Пн",
+    Weekday::Tue => "Вт",
+    Weekday::Wed => "Ср",
+    Weekday::Thu => "Чт",
+    Weekday::Fri => "Пт",
+    Weekday::Sat => "Сб",
+    Weekday::Sun => "Вс",
+  }
+}
+
+pub fn month_short_ru(month: u32) -> &'static str {
+  const MONTHS: [&str; 12] = ["янв", "фев", "мар", "апр", "мая", "июн", "июл", "авг", "сен", "окт", "ноя", "дек"];
+  MONTHS.get(month.wrapping_sub(1) as usize).copied().unwrap_or_default()
+}
+
+/// Sorted dates as consecutive day ranges per month, e.g. "1–5, 8, 10–12 окт"
+pub fn format_day_ranges(dates: &[NaiveDate]) -> String {
+  let months = dates.chunk_by(|a, b| (a.year(), a.month()) == (b.year(), b.month())).map(|days| {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for day in days.iter().map(|date| date.day()) {
+      match ranges.last_mut() {
+        Some((_, end)) if *end + 1 == day => *end = day,
+        _ => ranges.push((day, day)),
+      }
+    }
+    let ranges = ranges
+      .iter()
+      .map(|&(start, end)| if start == end { start.to_string() } else { format!("{start}–{end}") });
+    format!("{} {}", join(ranges, ", "), month_short_ru(days[0].month()))
+  });
+  join(months, "; ")
+}
+
+/// Title of the current month's sheet, the month switches at the participants' local midnight
 pub fn derive_title_name() -> String {
-  let current_time = current_time_utc();
-  let month_number: u8 = current_time.month().try_into().unwrap();
-  let year_number: u16 = current_time.year().try_into().unwrap();
+  derive_title_name_for(&current_time_local().date_naive())
+}
+
+/// Title of the monthly sheet holding the data for the given date, e.g. "Январь 24"
+pub fn derive_title_name_for(date: &NaiveDate) -> String {
+  let month_number: u8 = date.month().try_into().unwrap();
+  let year_number: u16 = date.year().try_into().unwrap();
 
   let month = Month::new(month_number);
   let year_str = year_number.to_string();
   let year_str = &year_str[year_str.len() - 2..];
 
   debug!(
-    "[API] Deriving relevant title name: current_time={:}, corresponding month(ru)={:}({:}), year={:}",
-    current_time.format("%d.%m.%Y"),
+    "[API] Deriving relevant title name: date={:}, corresponding month(ru)={:}({:}), year={:}",
+    date.format("%d.%m.%Y"),
     month.get_en(),
     month.get_ru(),
     year_str
@@ -166,27 +338,674 @@ pub fn derive_title_name() -> String {
   result
 }
 
+/// Participants' yesterday along with the title of its sheet when that's not the current one (on the 1st of the month)
+pub fn local_yesterday() -> Option<(NaiveDate, Option<String>)> {
+  let today = current_time_local().date_naive();
+  let yesterday = today.pred_opt()?;
+  let other_sheet = (yesterday.month() != today.month()).then(|| derive_title_name_for(&yesterday));
+  Some((yesterday, other_sheet))
+}
+
+/// Renders records as CSV with a header row, one line per record
+pub fn records_to_csv(records: &[&ScoreTableRecord]) -> String {
+  let mut header = vec!["date"];
+  header.extend(Category::ALL.iter().map(|c| c.key()));
+  header.extend(["total", "percent"]);
+
+  let mut csv = join(&header, ",");
+  csv.push('\n');
+  for record in records {
+    let scores = Category::ALL.iter().map(|&c| csv_number(record.scores().get(c)));
+    csv.push_str(&format!(
+      "{},{},{},{}\n",
+      record.date().format("%Y-%m-%d"),
+      join(scores, ","),
+      csv_number(record.total_score()),
+      record.percent().value()
+    ));
+  }
+  csv
+}
+
+/// Number of the CSV field, an empty cell stays blank
+fn csv_number(value: Option<f64>) -> String {
+  value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quotes the CSV field if it holds a separator, a quote or a line break
+fn csv_field(value: &str) -> Cow<'_, str> {
+  if value.contains([',', '"', '\n', '\r']) {
+    Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+  } else {
+    Cow::Borrowed(value)
+  }
+}
+
+/// Renders the whole score table of a participant as CSV with a header row.
+/// Unfilled records (without total) have only the date, the other cells are blank.
+pub fn person_table_to_csv(table: &ScoreTable) -> String {
+  let mut header = vec!["date"];
+  header.extend(Category::ALL.iter().map(|c| c.key()));
+  header.extend(["total", "percent"]);
+
+  let mut csv = join(header.iter().map(|h| csv_field(h)), ",");
+  csv.push('\n');
+  for record in table.records() {
+    let mut row = vec![record.date().format("%Y-%m-%d").to_string()];
+    if record.has_total() {
+      row.extend(Category::ALL.iter().map(|&c| csv_number(record.scores().get(c))));
+      row.push(csv_number(record.total_score()));
+      row.push(record.percent().value().to_string());
+    } else {
+      row.extend(std::iter::repeat_n(String::new(), Category::ALL.len() + 2));
+    }
+    csv.push_str(&join(row.iter().map(|v| csv_field(v)), ","));
+    csv.push('\n');
+  }
+  csv
+}
+
+/// Participants with their records as JSON array. Records without total have null total and percent.
+pub fn dashboard_to_json(dashboard: &Dashboard) -> serde_json::Value {
+  let participants = dashboard.tables().into_iter().flatten().map(|table| {
+    let records: Vec<_> = table
+      .records()
+      .iter()
+      .map(|record| {
+        let mut object = serde_json::Map::new();
+        object.insert("date".to_string(), record.date().format("%Y-%m-%d").to_string().into());
+        for category in Category::ALL {
+          object.insert(category.key().to_string(), record.scores().get(category).into());
+        }
+        let filled = record.has_total();
+        object.insert("total".to_string(), record.total_score().filter(|_| filled).into());
+        object.insert("percent".to_string(), filled.then(|| record.percent().value()).into());
+        serde_json::Value::Object(object)
+      })
+      .collect();
+    serde_json::json!({ "name": table.person().name(), "records": records })
+  });
+  serde_json::Value::Array(participants.collect())
+}
+
+/// Maps values onto the eight block glyphs between the lowest and the highest value.
+/// Returns `None` for less than 2 values since there is no trend to show.
+pub fn sparkline(values: &[i32]) -> Option<String> {
+  const GLYPHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+  if values.len() < 2 {
+    return None;
+  }
+  let min = *values.iter().min()?;
+  let max = *values.iter().max()?;
+  let line = values
+    .iter()
+    .map(|&v| {
+      if max == min {
+        GLYPHS[GLYPHS.len() / 2]
+      } else {
+        let idx = (v - min) as i64 * (GLYPHS.len() as i64 - 1) / (max - min) as i64;
+        GLYPHS[idx as usize]
+      }
+    })
+    .collect();
+  Some(line)
+}
+
+/// Rewrites leading "/alias" (with optional "@botname" mention) into "/canonical" keeping the arguments.
+/// Returns `None` if the text doesn't start with a known alias.
+pub fn apply_command_alias(text: &str, aliases: &HashMap<String, String>) -> Option<String> {
+  let rest = text.strip_prefix('/')?;
+  let (head, tail) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+  let (name, mention) = match head.split_once('@') {
+    Some((name, bot_name)) => (name, format!("@{bot_name}")),
+    None => (head, String::new()),
+  };
+  let canonical = aliases.get(&name.to_lowercase())?;
+  Some(format!("/{canonical}{mention}{tail}"))
+}
+
+/// Escapes every character reserved by Telegram MarkdownV2 so the text is rendered as is
+pub fn escape_markdown_v2(input: &str) -> String {
+  const RESERVED: [char; 19] = [
+    '\\', '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
+  ];
+  let mut escaped = String::with_capacity(input.len());
+  for c in input.chars() {
+    if RESERVED.contains(&c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+/// Turns MarkdownV2 text into plain one: escaped characters are kept, formatting markup is dropped
+pub fn strip_markdown_v2(input: &str) -> String {
+  let mut plain = String::with_capacity(input.len());
+  let mut chars = input.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => plain.extend(chars.next()),
+      '*' | '_' | '~' | '`' | '|' => {}
+      _ => plain.push(c),
+    }
+  }
+  plain
+}
+
 pub fn format_user_score_msg(score_table: &ScoreTableRecord, person: &Person) -> String {
-  format!("🫥 __Пользователь__: {}\n{}", person.name(), score_table)
-    .replace('-', "\\-")
-    .replace('.', "\\.")
+  format!(
+    "🫥 __Пользователь__: {}\n{}{}",
+    escape_markdown_v2(&person.display_name()),
+    score_table,
+    render_score_bars(score_table)
+  )
+}
+
+/// Categories of the record as bars (MarkdownV2 code block) scaled to the record's highest category.
+/// Empty if no category has a positive value.
+pub fn render_score_bars(record: &ScoreTableRecord) -> String {
+  const BAR_WIDTH: f64 = 10.;
+  let values: Vec<(Category, f64)> = Category::shown()
+    .map(|category| (category, record.scores().get(category).unwrap_or(0.)))
+    .collect();
+  let max = values.iter().map(|(_, value)| *value).fold(0., f64::max);
+  if max <= 0. {
+    return String::new();
+  }
+  let key_width = Category::ALL.iter().map(|c| c.key().len()).max().unwrap_or(0);
+  let lines = values.iter().map(|(category, value)| {
+    let length = (value.max(0.) / max * BAR_WIDTH).round() as usize;
+    format!(
+      "{:<key_width$} {:<bar_width$} {}",
+      category.key(),
+      "█".repeat(length),
+      DisplayRounding::score(*value),
+      bar_width = BAR_WIDTH as usize
+    )
+  });
+  // Only ` and \ are special inside a code block, neither can appear in the lines
+  format!("```\n{}\n```", join(lines, "\n"))
+}
+
+/// Splits the text into chunks of at most `limit` UTF-16 units (as Telegram counts them) on line
+/// boundaries, so the per-line MarkdownV2 entities stay whole. A line longer than the limit is cut
+/// in pieces, never between an escaping backslash and the escaped character.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+  let mut chunks = Vec::new();
+  let mut chunk = String::new();
+  let mut chunk_len = 0;
+  for piece in text.split('\n').flat_map(|line| split_long_line(line, limit)) {
+    let piece_len = piece.encode_utf16().count();
+    if !chunk.is_empty() && chunk_len + 1 + piece_len > limit {
+      chunks.push(chunk.trim_end_matches('\n').to_string());
+      chunk.clear();
+      chunk_len = 0;
+    }
+    if !chunk.is_empty() {
+      chunk.push('\n');
+      chunk_len += 1;
+    }
+    chunk.push_str(piece);
+    chunk_len += piece_len;
+  }
+  if !chunk.is_empty() || chunks.is_empty() {
+    chunks.push(chunk);
+  }
+  chunks
+}
+
+fn split_long_line(line: &str, limit: usize) -> Vec<&str> {
+  let mut pieces = Vec::new();
+  let mut rest = line;
+  while rest.encode_utf16().count() > limit {
+    let mut cut = 0;
+    let mut len = 0;
+    for (i, c) in rest.char_indices() {
+      if len + c.len_utf16() > limit {
+        break;
+      }
+      len += c.len_utf16();
+      cut = i + c.len_utf8();
+    }
+    let backslashes = rest[..cut].chars().rev().take_while(|c| *c == '\\').count();
+    if backslashes % 2 == 1 {
+      cut -= 1;
+    }
+    if cut == 0 {
+      break;
+    }
+    pieces.push(&rest[..cut]);
+    rest = &rest[cut..];
+  }
+  pieces.push(rest);
+  pieces
 }
 
-pub fn format_summary_msg(summary: &Vec<String>, by_date: &NaiveDate) -> String {
-  if !summary.is_empty() {
+/// Summary (MarkdownV2) prefixed with the admin's note for the date, if any
+pub fn format_summary_msg(summary: &Vec<String>, by_date: &NaiveDate, note: Option<&str>) -> String {
+  let msg = if !summary.is_empty() {
     join(summary, "\n")
   } else {
+    i18n::fill(
+      i18n::tr(i18n::Text::NobodyFilled),
+      &[&escape_markdown_v2(&by_date.format("%d.%m.%Y").to_string())],
+    )
+  };
+  match note {
+    Some(note) => format!("📝 _{}_\n\n{}", escape_markdown_v2(note), msg),
+    None => msg,
+  }
+}
+
+pub fn format_rank_history_msg(ranks: &[(NaiveDate, Option<(usize, usize)>)], person: &Person) -> String {
+  let lines = ranks.iter().map(|(date, rank)| {
+    let rank = match rank {
+      Some((position, total)) => format!("{position}/{total}"),
+      None => "—".to_string(),
+    };
+    format!("{} {}: {}", weekday_short_ru(date.weekday()), date.format("%d.%m"), rank)
+  });
+  format!("📈 Места {} за неделю:\n{}", person.name(), join(lines, "\n"))
+}
+
+pub fn format_goal_estimate_msg(estimate: &GoalEstimate, person: &Person, target: f64, daily_percent: f64) -> String {
+  match estimate {
+    GoalEstimate::AlreadyReached { average } => format!(
+      "🎯 {}: цель {:.0}% уже достигнута (среднее за месяц {}%)",
+      person.name(),
+      target,
+      DisplayRounding::percent(*average)
+    ),
+    GoalEstimate::Days { needed, average } => format!(
+      "🎯 {}: сейчас среднее {}%. Чтобы выйти на {:.0}%, нужно {} дн. по {:.0}%",
+      person.name(),
+      DisplayRounding::percent(*average),
+      target,
+      needed,
+      daily_percent
+    ),
+    GoalEstimate::Unreachable {
+      needed: Some(needed),
+      available,
+      average,
+    } => format!(
+      "😔 {}: сейчас среднее {}%. Для {:.0}% нужно {} дн. по {:.0}%, а в месяце осталось {} — не хватит дней",
+      person.name(),
+      DisplayRounding::percent(*average),
+      target,
+      needed,
+      daily_percent,
+      available
+    ),
+    GoalEstimate::Unreachable { needed: None, average, .. } => format!(
+      "😔 {}: сейчас среднее {}%. При {:.0}% в день выйти на {:.0}% невозможно",
+      person.name(),
+      DisplayRounding::percent(*average),
+      daily_percent,
+      target
+    ),
+  }
+}
+
+pub fn format_participation_msg(rates: &[(NaiveDate, f64)]) -> String {
+  let percents: Vec<i32> = rates.iter().map(|(_, rate)| (rate * 100.).round() as i32).collect();
+  let lines = rates
+    .iter()
+    .zip(&percents)
+    .map(|((date, _), percent)| format!("{} {}: {}%", weekday_short_ru(date.weekday()), date.format("%d.%m"), percent));
+  let mut msg = format!("👥 Доля заполнивших таблицу:\n{}", join(lines, "\n"));
+  if let Some(line) = sparkline(&percents) {
+    msg.push_str(&format!("\n📈 {}", line));
+  }
+  msg
+}
+
+pub fn format_improvers_msg(improvements: &[(&Person, f64)]) -> String {
+  if improvements.is_empty() {
+    return "Пока недостаточно данных, чтобы сравнить эту неделю с прошлой 🤷".to_string();
+  }
+  let lines = improvements.iter().enumerate().map(|(i, (person, delta))| {
+    // Sign of the shown value, a tiny drop rounded to zero is not a drop
+    let rising = DisplayRounding::rounded_percent(*delta) >= 0.;
+    let arrow = if rising { "▲" } else { "▼" };
+    let sign = if rising { "+" } else { "" };
+    format!("{}. {} {} {}{}%", i + 1, person.name(), arrow, sign, DisplayRounding::percent(*delta))
+  });
+  format!("🚀 Прогресс по сравнению с прошлой неделей:\n{}", join(lines, "\n"))
+}
+
+pub fn format_trends_msg(trends: &[(&Person, f64, Option<Ordering>)]) -> String {
+  if trends.is_empty() {
+    return "В этом месяце таблицу еще никто не заполнял 🤷".to_string();
+  }
+  let lines = trends.iter().enumerate().map(|(i, (person, average, trend))| {
+    let arrow = match trend {
+      Some(Ordering::Greater) => " ⬆️",
+      Some(Ordering::Less) => " ⬇️",
+      _ => "",
+    };
+    format!("{}. {} — {}%{}", i + 1, person.name(), DisplayRounding::percent(*average), arrow)
+  });
+  format!(
+    "📊 Среднее за месяц (стрелка — последний день выше или ниже своего среднего):\n{}",
+    join(lines, "\n")
+  )
+}
+
+pub fn format_group_categories_msg(totals: &[(Category, f64, usize)], date: &NaiveDate) -> String {
+  if totals.is_empty() {
+    return format!("За {} ещё никто не заполнил таблицу 🤷", date.format("%d.%m.%Y"));
+  }
+  let lines = totals
+    .iter()
+    .map(|(category, sum, count)| {
+      format!(
+        "{} {}: {} ({} чел.)",
+        category.emoji(),
+        category.label(),
+        DisplayRounding::score(*sum),
+        count
+      )
+    });
+  format!("👥 Итоги группы по категориям за {}:\n{}", date.format("%d.%m.%Y"), join(lines, "\n"))
+}
+
+pub fn format_category_leaders_msg(leaders: &[(Category, Vec<&Person>, f64)], date: &NaiveDate) -> String {
+  if leaders.is_empty() {
+    return format!("За {} ещё никто не заполнил таблицу 🤷", date.format("%d.%m.%Y"));
+  }
+  let lines = leaders.iter().map(|(category, persons, value)| {
+    format!(
+      "{} {}: {} ({})",
+      category.emoji(),
+      category.label(),
+      join(persons.iter().map(|p| p.name()), ", "),
+      DisplayRounding::score(*value)
+    )
+  });
+  format!("🏅 Лидеры по категориям за {}:\n{}", date.format("%d.%m.%Y"), join(lines, "\n"))
+}
+
+/// Month standings (MarkdownV2) with the moment of the last update
+pub fn format_standings_msg(standings: &[(&Person, f64)], updated_at: &DateTime<Utc>) -> String {
+  let lines = if standings.is_empty() {
+    "В этом месяце таблицу еще никто не заполнял 🤷".to_string()
+  } else {
+    let lines = standings.iter().enumerate().map(|(i, (person, avg))| {
+      let medal = match i {
+        0 => "🥇",
+        1 => "🥈",
+        2 => "🥉",
+        _ => "▫️",
+      };
+      format!(
+        "{} {}\\. {} — *{}%*",
+        medal,
+        i + 1,
+        escape_markdown_v2(&person.display_name()),
+        escape_markdown_v2(&DisplayRounding::percent(*avg))
+      )
+    });
+    join(lines, "\n")
+  };
+  format!(
+    "🏁 *Турнирная таблица месяца*\n\n{}\n\n_Обновлено {}_",
+    lines,
+    escape_markdown_v2(&format!("{} (UTC{:+})", updated_at.format("%d.%m %H:%M"), timezone_offset_hours()))
+  )
+}
+
+/// Local time of the last fetch and its age at the `now` moment
+pub fn format_freshness_msg(last_fetched: Option<&DateTime<Utc>>, now: &DateTime<Utc>) -> String {
+  match last_fetched {
+    Some(fetched) => format!(
+      "🔄 Данные загружены: {} (UTC{:+}), {} назад",
+      (*fetched + Duration::hours(timezone_offset_hours() as i64)).format("%d.%m %H:%M"),
+      timezone_offset_hours(),
+      format_duration_ru(&(*now - *fetched).max(Duration::zero()))
+    ),
+    None => "🔄 Время загрузки данных неизвестно".to_string(),
+  }
+}
+
+/// Sheet vs computed totals of the participants' latest filled records, mismatches only unless `all` is set
+pub fn format_verify_msg(records: &[(&Person, &ScoreTableRecord)], all: bool) -> String {
+  let lines: Vec<String> = records
+    .iter()
+    .filter(|(_, rec)| all || !rec.total_matches())
+    .map(|(person, rec)| {
+      format!(
+        "{} {} ({}): в таблице {}, посчитано {}",
+        if rec.total_matches() { "✅" } else { "❌" },
+        person.name(),
+        rec.date().format("%d.%m.%Y"),
+        Scores::display(rec.total_score()),
+        rec.scores().total()
+      )
+    })
+    .collect();
+  if lines.is_empty() {
+    return "✅ Итоги в таблице совпадают с посчитанными для всех участников".to_string();
+  }
+  format!("🔎 Сверка итогов за последний заполненный день:\n{}", lines.join("\n"))
+}
+
+pub fn format_alltime_msg(totals: &[ParticipantTotals], year: i32, months: usize) -> String {
+  if totals.is_empty() {
+    return format!("Данных за {} год не найдено 🤷", year);
+  }
+  let lines = totals.iter().enumerate().map(|(i, t)| {
+    format!(
+      "{}. {} — {} дн., в среднем {}%",
+      i + 1,
+      t.name,
+      t.filled_days,
+      DisplayRounding::percent(t.average_percent)
+    )
+  });
+  format!(
+    "🗂 Итоги {} года (месяцев: {}):\n{}",
+    year,
+    months,
+    join(lines, "\n")
+  )
+}
+
+pub fn format_year_overview_msg(averages: &[YearlyAverage], year: i32, months: usize) -> String {
+  if averages.is_empty() {
+    return format!("Данных за {} год не найдено 🤷", year);
+  }
+  let lines = averages.iter().enumerate().map(|(i, a)| {
+    let monthly = a
+      .monthly
+      .iter()
+      .map(|(month, average)| format!("{} {}%", month_short_ru(month.month()), DisplayRounding::percent(*average)));
     format!(
-      "*{}* пока еще *ни один* из участников таблицу не заполнял 😩😭",
-      by_date.format("%d.%m.%Y")
+      "{}. {} — {}% ({})",
+      i + 1,
+      a.name,
+      DisplayRounding::percent(a.average_percent),
+      join(monthly, ", ")
     )
-    .replace('.', "\\.")
+  });
+  format!("📅 Средний процент с начала {} года (месяцев: {}):\n{}", year, months, join(lines, "\n"))
+}
+
+pub fn format_personal_bests_msg(records: &[(&Person, Percentage, Percentage)]) -> String {
+  if records.is_empty() {
+    return "Сегодня новых личных рекордов пока нет 💪".to_string();
+  }
+  let lines = records
+    .iter()
+    .map(|(person, today, best)| format!("🏆 {}: {} (прежний рекорд {})", person.name(), today, best));
+  format!("🎉 Новый личный рекорд!\n{}", join(lines, "\n"))
+}
+
+pub fn format_most_consistent_msg(counts: &[(&Person, u32)]) -> String {
+  if counts.is_empty() {
+    return "Список пользователей пуст 😩😭".to_string();
+  }
+  let lines = counts
+    .iter()
+    .enumerate()
+    .map(|(i, (person, days))| format!("{}. {} — {} дн.", i + 1, person.name(), days));
+  format!("📆 Заполненные дни в этом месяце:\n{}", join(lines, "\n"))
+}
+
+/// MarkdownV2 ranking of the day, the top three get medals
+pub fn format_leaderboard_msg(ranking: &[(&Person, &Percentage)], date: &NaiveDate) -> String {
+  let date = escape_markdown_v2(&date.format("%d.%m.%Y").to_string());
+  if ranking.is_empty() {
+    return format!("*{}* пока еще *ни один* из участников таблицу не заполнял 😩😭", date);
+  }
+  let lines = ranking.iter().enumerate().map(|(i, (person, percent))| {
+    let place = match i {
+      0 => "🥇".to_string(),
+      1 => "🥈".to_string(),
+      2 => "🥉".to_string(),
+      _ => escape_markdown_v2(&format!("{}.", i + 1)),
+    };
+    format!(
+      "{} {} — *{}*",
+      place,
+      escape_markdown_v2(&person.display_name()),
+      escape_markdown_v2(&percent.to_string())
+    )
+  });
+  format!("🏆 *Рейтинг за {}*\n\n{}", date, join(lines, "\n"))
+}
+
+pub fn format_no_filled_days_msg(person: &Person) -> String {
+  format!(
+    "*{}* не заполнил\\(а\\) *ни одного* дня за последний месяц 😢",
+    escape_markdown_v2(&person.display_name())
+  )
+}
+
+/// MarkdownV2 list of the days the person filled the table on
+pub fn format_filled_dates_msg(dates: &[NaiveDate], person: &Person) -> String {
+  if dates.is_empty() {
+    return format_no_filled_days_msg(person);
+  }
+  format!(
+    "📅 *{}* заполнил\\(а\\) таблицу {} дн\\.: {}",
+    escape_markdown_v2(&person.display_name()),
+    dates.len(),
+    escape_markdown_v2(&format_day_ranges(dates))
+  )
+}
+
+pub fn format_monthly_stats_msg(stats: &MonthlyStats, person: &Person) -> String {
+  format!(
+    "📊 *Статистика за месяц*: {}\n\n\
+     🗓 Заполнено дней: {}\n\
+     📈 Средний результат: {}%\n\
+     🏆 Лучший день: {} — {}\n\
+     🐢 Худший день: {} — {}",
+    escape_markdown_v2(&person.display_name()),
+    stats.filled_days,
+    escape_markdown_v2(&DisplayRounding::percent(stats.average_percent)),
+    escape_markdown_v2(&stats.best.0.format("%d.%m.%Y").to_string()),
+    escape_markdown_v2(&stats.best.1.to_string()),
+    escape_markdown_v2(&stats.worst.0.format("%d.%m.%Y").to_string()),
+    escape_markdown_v2(&stats.worst.1.to_string())
+  )
+}
+
+/// Side-by-side (MarkdownV2) of the latest filled records with the leader of every category
+pub fn format_comparison_msg(comparison: &Comparison) -> String {
+  let (first, second) = (comparison.first.0, comparison.second.0);
+  let header = format!(
+    "⚔️ *{}* vs *{}*",
+    escape_markdown_v2(&first.display_name()),
+    escape_markdown_v2(&second.display_name())
+  );
+  let latest = |(person, record): (&Person, Option<&ScoreTableRecord>)| match record {
+    Some(rec) => format!(
+      "📅 {}: {} — {}",
+      escape_markdown_v2(&person.display_name()),
+      escape_markdown_v2(&rec.date().format("%d.%m.%Y").to_string()),
+      escape_markdown_v2(&rec.percent().to_string())
+    ),
+    None => format!("📅 {}: в этом месяце нет заполненных дней", escape_markdown_v2(&person.display_name())),
+  };
+  let mut lines = vec![header, latest(comparison.first), latest(comparison.second)];
+  if let (Some(a), Some(b)) = (comparison.first.1, comparison.second.1) {
+    // An empty cell loses to any entered score
+    let leader = |a: Option<f64>, b: Option<f64>| match a.partial_cmp(&b) {
+      Some(std::cmp::Ordering::Greater) => "⬅️",
+      Some(std::cmp::Ordering::Less) => "➡️",
+      _ => "🤝",
+    };
+    lines.push(String::new());
+    for category in Category::ALL {
+      let (a, b) = (a.scores().get(category), b.scores().get(category));
+      lines.push(format!(
+        "{} {}: {} — {} {}",
+        category.emoji(),
+        escape_markdown_v2(category.label()),
+        escape_markdown_v2(&Scores::display(a)),
+        escape_markdown_v2(&Scores::display(b)),
+        leader(a, b)
+      ));
+    }
+    lines.push(format!(
+      "🧮 *Итого*: {} — {} {}",
+      escape_markdown_v2(&Scores::display(a.total_score())),
+      escape_markdown_v2(&Scores::display(b.total_score())),
+      leader(a.total_score(), b.total_score())
+    ));
+  }
+  join(lines, "\n")
+}
+
+pub fn format_streaks_msg(streaks: &[(&Person, u32)]) -> String {
+  if streaks.is_empty() {
+    return "Список пользователей пуст 😩😭".to_string();
+  }
+  let lines = streaks.iter().map(|(person, days)| {
+    let mark = if *days > 0 { "🔥" } else { "▫️" };
+    format!("{} {} — {} дн.", mark, person.name(), days)
+  });
+  format!("🔥 Текущие серии заполнения:\n{}", join(lines, "\n"))
+}
+
+/// Splits `<name> key=value ...` into the name and the category overrides.
+/// Unknown category key or malformed value is returned as an error.
+pub fn parse_score_overrides(args: &str) -> Result<(String, Vec<(Category, f64)>), String> {
+  let mut name = Vec::new();
+  let mut overrides = Vec::new();
+  for token in args.split_whitespace() {
+    match token.split_once('=') {
+      Some((key, value)) => {
+        let category = Category::from_key(&key.to_lowercase()).ok_or_else(|| key.to_string())?;
+        let value = value.replace(',', ".").parse::<f64>().map_err(|_| token.to_string())?;
+        overrides.push((category, value));
+      }
+      None => name.push(token),
+    }
   }
+  Ok((name.join(" "), overrides))
+}
+
+pub fn format_whatif_msg(person: &Person, scores: &Scores, max: f64) -> String {
+  let total = scores.total();
+  let percent = Percentage::from((total / max * 100.).round() as i32);
+  format!(
+    "🔮 Если {} внесет такие значения сегодня:\n\n{}\n✅ Итого: {}\n💯 Процент: {} {}",
+    person.name(),
+    scores,
+    DisplayRounding::score(total),
+    percent,
+    percent.emoji()
+  )
 }
 
 #[derive(Debug, Clone)]
 pub enum PeriodicTimeUtc {
   EveryDay(EveryDayTime, u32, u32, u32),
+  EveryWeek(EveryWeekTime, Weekday, u32, u32, u32),
   EveryMin(EveryMinuteTime, u32),
 }
 
@@ -197,6 +1016,18 @@ impl PeriodicTimeUtc {
     PeriodicTimeUtc::EveryDay(every_day, h, m, s)
   }
 
+  /// Every day at the given time of the participants' timezone
+  pub fn every_day_local_time(h: u32, m: u32, s: u32) -> Self {
+    let utc_hour = (h as i32 - timezone_offset_hours()).rem_euclid(24) as u32;
+    PeriodicTimeUtc::every_day_time_utc(utc_hour, m, s)
+  }
+
+  pub fn every_week_time_utc(weekday: Weekday, h: u32, m: u32, s: u32) -> Self {
+    let every_week = every(1).week().on(weekday).at(h, m, s).in_timezone(&Utc);
+
+    PeriodicTimeUtc::EveryWeek(every_week, weekday, h, m, s)
+  }
+
   pub fn every_min_time_utc(period: u32) -> Self {
     let every_min = every(period).minutes().in_timezone(&Utc);
 
@@ -210,6 +1041,7 @@ impl PeriodicTimeUtc {
   {
     let job = match self {
       PeriodicTimeUtc::EveryDay(t, _, _, _) => t.perform(func),
+      PeriodicTimeUtc::EveryWeek(t, _, _, _, _) => t.perform(func),
       PeriodicTimeUtc::EveryMin(t, _) => t.perform(func),
     };
     tokio::spawn(job)
@@ -220,7 +1052,212 @@ impl Display for PeriodicTimeUtc {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       PeriodicTimeUtc::EveryDay(_, h, m, s) => write!(f, "ежедневно в {h:02}:{m:02}:{s:02} UTC"),
+      PeriodicTimeUtc::EveryWeek(_, weekday, h, m, s) => {
+        write!(f, "еженедельно {} в {h:02}:{m:02}:{s:02} UTC", weekday_every_ru(*weekday))
+      }
       PeriodicTimeUtc::EveryMin(_, period) => write!(f, "каждые {period} минут(ы)"),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use chrono::TimeZone;
+
+  use super::*;
+  use crate::{
+    dashboard::fake::date,
+    helpers::clock::{set_clock, FixedClock},
+  };
+
+  #[test]
+  fn score_bars_are_scaled_to_the_highest_category() {
+    let overrides = [(Category::Sport, 4.), (Category::Health, 2.), (Category::PersonalDev, 0.)];
+    let scores = Scores::default().with_overrides(&overrides);
+    let record = ScoreTableRecord::new(date(2024, 1, 15), scores, Some(6.), Percentage::from(60));
+
+    let bars = render_score_bars(&record);
+    let lengths: Vec<usize> = bars
+      .lines()
+      .filter(|line| !line.starts_with("```"))
+      .map(|line| line.matches('█').count())
+      .collect();
+    // Sport is the longest bar, health is half of it, the empty and zero categories have none
+    assert_eq!(lengths, [10, 0, 5, 0, 0, 0]);
+  }
+
+  #[test]
+  fn score_bars_are_empty_without_positive_scores() {
+    let scores = Scores::default().with_overrides(&[(Category::Sport, 0.)]);
+    let record = ScoreTableRecord::new(date(2024, 1, 15), scores, Some(0.), Percentage::from(0));
+    assert_eq!(render_score_bars(&record), "");
+  }
+
+  #[test]
+  fn improvers_msg_has_no_negative_zero() {
+    let (anna, boris) = (Person::new("Анна".to_string()), Person::new("Борис".to_string()));
+    let msg = format_improvers_msg(&[(&anna, 12.34), (&boris, -0.04)]);
+    assert!(msg.ends_with("1. Анна ▲ +12.3%\n2. Борис ▲ +0.0%"), "{msg}");
+  }
+
+  #[test]
+  fn duration_spec_is_parsed() {
+    let minutes = |m: u64| Some(std::time::Duration::from_secs(m * 60));
+    assert_eq!(parse_duration_spec("30m"), minutes(30));
+    assert_eq!(parse_duration_spec("1h30m"), minutes(90));
+    assert_eq!(parse_duration_spec("1д 2ч"), None);
+    assert_eq!(parse_duration_spec("1Д2Ч"), minutes(26 * 60));
+    assert_eq!(parse_duration_spec("2h5"), None);
+    assert_eq!(parse_duration_spec("0m"), None);
+  }
+
+  #[test]
+  fn absurd_duration_spec_is_rejected() {
+    assert_eq!(parse_duration_spec("366d"), Some(MAX_DURATION_SPEC));
+    assert_eq!(parse_duration_spec("367d"), None);
+    // Overflows u64 seconds instead of wrapping around to a short pause
+    assert_eq!(parse_duration_spec("18446744073709551615d"), None);
+    assert_eq!(parse_duration_spec("99999999999999999999m"), None);
+  }
+
+  #[test]
+  fn split_message_keeps_short_text_whole() {
+    assert_eq!(split_message("a\nb", TELEGRAM_MESSAGE_LIMIT), vec!["a\nb"]);
+  }
+
+  #[test]
+  fn split_message_splits_on_newlines() {
+    let lines: Vec<String> = (0..10).map(|i| format!("Участник {i} \\- *50%* 🙂")).collect();
+    let text = lines.join("\n");
+    let chunks = split_message(&text, 60);
+
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|chunk| chunk.encode_utf16().count() <= 60));
+    // Every line ends up whole in some chunk
+    let rejoined: Vec<&str> = chunks.iter().flat_map(|chunk| chunk.split('\n')).collect();
+    assert_eq!(rejoined, lines);
+  }
+
+  #[test]
+  fn split_message_cuts_long_line_outside_escapes() {
+    let line = "ab\\.".repeat(10);
+    let chunks = split_message(&line, 7);
+
+    assert!(chunks.iter().all(|chunk| chunk.encode_utf16().count() <= 7));
+    assert!(chunks.iter().all(|chunk| !chunk.ends_with('\\')));
+    assert_eq!(chunks.concat(), line);
+  }
+
+  #[test]
+  fn yesterday_of_the_first_is_on_the_previous_month_sheet() {
+    // 00:30 of February 1st for the participants (default offset is +3)
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 31, 21, 30, 0).unwrap())));
+    assert_eq!(local_yesterday(), Some((date(2024, 1, 31), Some("Январь 24".to_string()))));
+
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 31, 20, 30, 0).unwrap())));
+    assert_eq!(local_yesterday(), Some((date(2024, 1, 30), None)));
+  }
+
+  #[test]
+  fn reserved_characters_of_names_are_escaped() {
+    assert_eq!(escape_markdown_v2("Анна (старшая)"), r"Анна \(старшая\)");
+    assert_eq!(escape_markdown_v2("boris_s"), r"boris\_s");
+    assert_eq!(escape_markdown_v2("Вера!"), r"Вера\!");
+    assert_eq!(escape_markdown_v2("Борис"), "Борис");
+  }
+
+  #[test]
+  fn weekly_time_is_displayed_with_the_weekday() {
+    let time = PeriodicTimeUtc::every_week_time_utc(Weekday::Sun, 7, 5, 0);
+    assert_eq!(time.to_string(), "еженедельно по воскресеньям в 07:05:00 UTC");
+  }
+
+  #[test]
+  fn remaining_time_is_rounded_down_to_minutes() {
+    assert_eq!(format_duration_ru(&Duration::seconds(59)), "меньше минуты");
+    assert_eq!(format_duration_ru(&Duration::seconds(5 * 60 + 59)), "5 мин");
+    assert_eq!(format_duration_ru(&Duration::hours(3)), "3 ч");
+    assert_eq!(format_duration_ru(&(Duration::hours(2) + Duration::minutes(15))), "2 ч 15 мин");
+  }
+
+  #[test]
+  fn command_alias_is_rewritten_with_mention_and_arguments() {
+    let aliases = HashMap::from([("итоги".to_string(), "summary".to_string())]);
+    assert_eq!(apply_command_alias("/итоги", &aliases).as_deref(), Some("/summary"));
+    assert_eq!(apply_command_alias("/Итоги 15.01.2024", &aliases).as_deref(), Some("/summary 15.01.2024"));
+    assert_eq!(apply_command_alias("/итоги@test_bot вчера", &aliases).as_deref(), Some("/summary@test_bot вчера"));
+    assert_eq!(apply_command_alias("/help", &aliases), None);
+    assert_eq!(apply_command_alias("итоги", &aliases), None);
+  }
+
+  #[test]
+  fn sparkline_spans_from_the_lowest_to_the_highest_glyph() {
+    assert_eq!(sparkline(&[0, 50, 100]).as_deref(), Some("▁▄█"));
+    assert_eq!(sparkline(&[70, 40, 40, 100]).as_deref(), Some("▄▁▁█"));
+    // Flat line is drawn in the middle
+    assert_eq!(sparkline(&[60, 60]).as_deref(), Some("▅▅"));
+    assert_eq!(sparkline(&[60]), None);
+    assert_eq!(sparkline(&[]), None);
+  }
+
+  #[test]
+  fn markdown_markup_is_stripped_and_escapes_are_kept() {
+    assert_eq!(strip_markdown_v2(r"*Анна* набрала __70\%__ \(рекорд\)"), "Анна набрала 70% (рекорд)");
+    assert_eq!(strip_markdown_v2(r"||спойлер|| ~зачеркнуто~ `код` \\"), r"спойлер зачеркнуто код \");
+  }
+
+  #[test]
+  fn evening_message_names_the_ones_who_missed() {
+    let (anna, vera) = (Person::new("Анна".to_string()), Person::new("Вера (мл.)".to_string()));
+    assert_eq!(
+      format_evening_msg("*Итоги*", "Заполните таблицу!", &[&anna, &vera]),
+      "*Итоги*\n\nЗаполните таблицу\\!\nАнна, Вера \\(мл\\.\\)"
+    );
+    assert_eq!(format_evening_msg("*Итоги*", "Заполните таблицу!", &[]), "*Итоги*");
+  }
+
+  #[test]
+  fn whatif_arguments_are_split_into_name_and_overrides() {
+    let (name, overrides) = parse_score_overrides("Анна Мария sport=2 Health=1,5").unwrap();
+    assert_eq!(name, "Анна Мария");
+    assert_eq!(overrides, [(Category::Sport, 2.), (Category::Health, 1.5)]);
+    assert_eq!(parse_score_overrides("Анна cooking=1"), Err("cooking".to_string()));
+    assert_eq!(parse_score_overrides("Анна sport=много"), Err("sport=много".to_string()));
+  }
+
+  #[test]
+  fn whatif_percent_is_computed_from_the_overridden_scores() {
+    let today = Scores::default().with_overrides(&[(Category::Sport, 1.), (Category::Health, 2.)]);
+    let whatif = today.with_overrides(&[(Category::Sport, 4.), (Category::PersonalDev, 1.5)]);
+    assert_eq!(whatif.get(Category::Health), Some(2.));
+    assert_eq!(whatif.total(), 7.5);
+
+    let msg = format_whatif_msg(&Person::new("Анна".to_string()), &whatif, 10.);
+    assert!(msg.starts_with("🔮 Если Анна внесет такие значения сегодня:"));
+    assert!(msg.ends_with("✅ Итого: 7.5\n💯 Процент: 75% 👍"), "{msg}");
+  }
+
+  #[test]
+  fn verify_lists_the_mismatched_totals() {
+    let scores = Scores::default().with_overrides(&[(Category::Sport, 2.), (Category::Health, 1.5)]);
+    let matching = ScoreTableRecord::new(date(2024, 1, 15), scores.clone(), Some(3.5), Percentage::from(35));
+    let mismatched = ScoreTableRecord::new(date(2024, 1, 14), scores, Some(5.), Percentage::from(50));
+    let empty_total = ScoreTableRecord::new(date(2024, 1, 15), Scores::default(), None, Percentage::default());
+    assert!(matching.total_matches() && empty_total.total_matches());
+    assert!(!mismatched.total_matches());
+
+    let (anna, boris) = (Person::new("Анна".to_string()), Person::new("Борис".to_string()));
+    let records = [(&anna, &matching), (&boris, &mismatched)];
+    assert_eq!(
+      format_verify_msg(&records, false),
+      "🔎 Сверка итогов за последний заполненный день:\n❌ Борис (14.01.2024): в таблице 5.0, посчитано 3.5"
+    );
+    assert_eq!(format_verify_msg(&records, true).lines().count(), 3);
+    assert_eq!(
+      format_verify_msg(&records[..1], false),
+      "✅ Итоги в таблице совпадают с посчитанными для всех участников"
+    );
+  }
+}
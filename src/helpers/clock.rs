@@ -0,0 +1,60 @@
+use std::{cell::RefCell, sync::Arc};
+
+use chrono::{DateTime, Utc};
+
+/// Source of the current moment, every "now" of the bot goes through it
+pub trait Clock: Send + Sync {
+  fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> DateTime<Utc> {
+    Utc::now()
+  }
+}
+
+/// Clock standing still at the given moment
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+  fn now(&self) -> DateTime<Utc> {
+    self.0
+  }
+}
+
+thread_local! {
+  // Per thread, so that the tests running in parallel can pin their own moments
+  static CLOCK: RefCell<Arc<dyn Clock>> = RefCell::new(Arc::new(SystemClock));
+}
+
+pub fn now() -> DateTime<Utc> {
+  CLOCK.with(|clock| clock.borrow().now())
+}
+
+/// Replaces the clock of the current thread
+#[cfg(test)]
+pub fn set_clock(clock: Arc<dyn Clock>) {
+  CLOCK.with(|current| *current.borrow_mut() = clock);
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{NaiveDate, TimeZone};
+
+  use super::*;
+  use crate::helpers;
+
+  #[test]
+  fn local_date_follows_the_fixed_clock() {
+    set_clock(Arc::new(FixedClock(Utc.with_ymd_and_hms(2024, 1, 31, 22, 30, 0).unwrap())));
+
+    let local = helpers::current_time_with_offset(3).date_naive();
+    assert_eq!(local, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+    // Already February for the participants (default offset is +3), while it's still January in UTC
+    assert_eq!(helpers::derive_title_name(), "Февраль 24");
+  }
+}
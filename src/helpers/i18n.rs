@@ -0,0 +1,109 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Language of the user-facing texts, installed once at startup
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+  #[default]
+  Ru,
+  En,
+}
+
+impl Locale {
+  /// Returns `false` if the locale was already installed
+  pub fn install(self) -> bool {
+    LOCALE.set(self).is_ok()
+  }
+
+  pub fn current() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+  }
+}
+
+/// Keys of the localized texts. MarkdownV2 ones are already escaped, `{}` placeholders are filled by `fill`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+  /// MarkdownV2
+  ScoresPrompt,
+  EmptyParticipants,
+  /// MarkdownV2, `{}` is the date
+  NobodyFilled,
+  /// MarkdownV2, `{}` are the name, the percentage and its emoji
+  SummaryLine,
+  Reminder,
+}
+
+impl Text {
+  pub const ALL: [Text; 5] = [
+    Text::ScoresPrompt,
+    Text::EmptyParticipants,
+    Text::NobodyFilled,
+    Text::SummaryLine,
+    Text::Reminder,
+  ];
+
+  pub fn template(self, locale: Locale) -> &'static str {
+    match (self, locale) {
+      (Text::ScoresPrompt, Locale::Ru) => {
+        "Могу показать последнюю статистику для какого\\-нибудь *конкретного* \
+         пользователя из списка ниже\\. Чьи цифры будем смотреть?"
+      }
+      (Text::ScoresPrompt, Locale::En) => {
+        "I can show the latest stats of a *particular* participant from the list below\\. \
+         Whose numbers shall we look at?"
+      }
+      (Text::EmptyParticipants, Locale::Ru) => "Список пользователей пуст 😩😭",
+      (Text::EmptyParticipants, Locale::En) => "The participants list is empty 😩😭",
+      (Text::NobodyFilled, Locale::Ru) => "*{}* пока еще *ни один* из участников таблицу не заполнял 😩😭",
+      (Text::NobodyFilled, Locale::En) => "As of *{}*, *nobody* has filled the table yet 😩😭",
+      (Text::SummaryLine, Locale::Ru) => "{} молодец на {} {}",
+      (Text::SummaryLine, Locale::En) => "{} did great with {} {}",
+      (Text::Reminder, Locale::Ru) => "Заполните таблицу 📋",
+      (Text::Reminder, Locale::En) => "Fill in the table 📋",
+    }
+  }
+}
+
+/// Template of the text in the installed locale
+pub fn tr(text: Text) -> &'static str {
+  text.template(Locale::current())
+}
+
+/// Replaces the `{}` placeholders of the template with `args` in order
+pub fn fill(template: &str, args: &[&str]) -> String {
+  let mut parts = template.split("{}");
+  let mut filled = parts.next().unwrap_or_default().to_string();
+  for (i, part) in parts.enumerate() {
+    filled.push_str(args.get(i).copied().unwrap_or_default());
+    filled.push_str(part);
+  }
+  filled
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_text_is_translated() {
+    // Stops compiling once a text is added, so that it's added to `ALL` as well
+    match Text::ALL[0] {
+      Text::ScoresPrompt | Text::EmptyParticipants | Text::NobodyFilled | Text::SummaryLine | Text::Reminder => {}
+    }
+    for text in Text::ALL {
+      let (ru, en) = (text.template(Locale::Ru), text.template(Locale::En));
+      assert!(!ru.is_empty() && !en.is_empty(), "{text:?} is empty");
+      assert_ne!(ru, en, "{text:?} isn't translated");
+      assert_eq!(ru.matches("{}").count(), en.matches("{}").count(), "{text:?} placeholders differ");
+    }
+  }
+
+  #[test]
+  fn placeholders_are_filled_in_order() {
+    assert_eq!(fill(Text::SummaryLine.template(Locale::En), &["Анна", "70%"]), "Анна did great with 70% ");
+  }
+}
@@ -11,7 +11,14 @@ async fn main() -> Result<(), CongratulatorError> {
   pretty_env_logger::init();
   // Load application config
   let app_config = CongratulatorConfig::load_from_env()?;
+  // Fetch and print the dashboard once without connecting to Telegram
+  if std::env::args().any(|arg| arg == "--dry-run") || std::env::var_os("DRY_RUN").is_some() {
+    return Congratulator::dry_run(app_config).await;
+  }
   // Start listening events
-  Congratulator::new(app_config).await?.listen().await;
+  let mut congratulator = Congratulator::new(app_config).await?;
+  congratulator.listen().await;
+  // Ctrl-C stops the dispatcher, tear down the rest
+  congratulator.shutdown().await;
   Ok(())
 }